@@ -4,8 +4,11 @@ import tempfile
 import asyncio
 import logging
 import glob
+import hashlib
+import inspect
+import math
 from contextlib import contextmanager
-from typing import Optional, Tuple, Dict, Any
+from typing import Optional, Tuple, Dict, Any, List
 
 import pypowsybl.network as pn
 
@@ -38,32 +41,151 @@ class NetworkService:
         "substation_description_displayed": True,
     }
 
+    # Attributes compared between two networks, per element type
+    COMPARED_ATTRIBUTES = {
+        "SUBSTATION": ("get_substations", ["name", "country", "TSO"]),
+        "VOLTAGE_LEVEL": (
+            "get_voltage_levels",
+            [
+                "substation_id",
+                "nominal_v",
+                "high_voltage_limit",
+                "low_voltage_limit",
+                "topology_kind",
+            ],
+        ),
+        "LINE": (
+            "get_lines",
+            [
+                "voltage_level1_id",
+                "voltage_level2_id",
+                "r",
+                "x",
+                "connected1",
+                "connected2",
+            ],
+        ),
+        "TWO_WINDINGS_TRANSFORMER": (
+            "get_2_windings_transformers",
+            [
+                "voltage_level1_id",
+                "voltage_level2_id",
+                "r",
+                "x",
+                "rated_u1",
+                "rated_u2",
+                "connected1",
+                "connected2",
+            ],
+        ),
+        "GENERATOR": (
+            "get_generators",
+            ["voltage_level_id", "min_p", "max_p", "target_p", "target_v", "connected"],
+        ),
+        "LOAD": ("get_loads", ["voltage_level_id", "p0", "q0", "connected"]),
+        "SHUNT_COMPENSATOR": (
+            "get_shunt_compensators",
+            ["voltage_level_id", "section_count", "max_section_count", "connected"],
+        ),
+        "SWITCH": ("get_switches", ["voltage_level_id", "kind", "open", "retained"]),
+    }
+
+    # Single terminal equipment attached to the buses of the topology
+    TOPOLOGY_INJECTIONS = [
+        ("LOAD", "get_loads"),
+        ("GENERATOR", "get_generators"),
+        ("BATTERY", "get_batteries"),
+        ("SHUNT_COMPENSATOR", "get_shunt_compensators"),
+        ("STATIC_VAR_COMPENSATOR", "get_static_var_compensators"),
+        ("VSC_CONVERTER_STATION", "get_vsc_converter_stations"),
+        ("LCC_CONVERTER_STATION", "get_lcc_converter_stations"),
+        ("DANGLING_LINE", "get_dangling_lines"),
+    ]
+
     # Storage paths
     UPLOAD_FOLDER = "uploads"
     LAST_NETWORK_FILE = os.path.join(UPLOAD_FOLDER, "last_loaded_network.json")
 
     def __init__(self):
         """Initialize the NetworkService."""
-        self._current_network = None
-        self._current_file_path = None
+        # Loaded networks by id, each with its label and source file
+        self._networks = {}
+        self._active_network_id = None
 
         # Ensure upload folder exists
         os.makedirs(self.UPLOAD_FOLDER, exist_ok=True)
 
     @property
     def current_network(self):
-        """Get the current network."""
-        return self._current_network
-
-    @current_network.setter
-    def current_network(self, network):
-        """Set the current network."""
-        self._current_network = network
+        """Get the active network."""
+        return self.get_network()
 
     @property
     def current_file_path(self):
-        """Get the path to the currently loaded network file."""
-        return self._current_file_path
+        """Get the path to the active network file."""
+        entry = self._networks.get(self._active_network_id)
+        return entry["file_path"] if entry else None
+
+    @property
+    def active_network_id(self):
+        """Get the id of the active network."""
+        return self._active_network_id
+
+    def get_network(self, network_id: Optional[str] = None):
+        """Get a loaded network by id, or the active network when no id is given.
+
+        Args:
+            network_id: Optional id of the network
+
+        Returns:
+            The network, or None if it is not loaded
+        """
+        entry = self._networks.get(network_id or self._active_network_id)
+        return entry["network"] if entry else None
+
+    def list_networks(self) -> Dict[str, Any]:
+        """Describe every loaded network."""
+        return {
+            "active_network_id": self._active_network_id,
+            "networks": [
+                {
+                    "id": network_id,
+                    "label": entry["label"],
+                    "file_path": entry["file_path"],
+                    "version": entry["version"],
+                    "active": network_id == self._active_network_id,
+                }
+                for network_id, entry in self._networks.items()
+            ],
+        }
+
+    def set_active_network(self, network_id: str) -> Optional[str]:
+        """Select the network used when a request has no network id.
+
+        Returns:
+            Optional[str]: Error message if the network is unknown, None otherwise
+        """
+        if network_id not in self._networks:
+            return f"Network '{network_id}' is not loaded"
+
+        self._active_network_id = network_id
+        self._save_network_metadata()
+        return None
+
+    def remove_network(self, network_id: str) -> Optional[str]:
+        """Unload a network.
+
+        Returns:
+            Optional[str]: Error message if the network is unknown, None otherwise
+        """
+        if network_id not in self._networks:
+            return f"Network '{network_id}' is not loaded"
+
+        del self._networks[network_id]
+        if self._active_network_id == network_id:
+            self._active_network_id = next(iter(self._networks), None)
+        self._save_network_metadata()
+        return None
 
     @contextmanager
     def _temp_file(self, suffix: str):
@@ -85,19 +207,36 @@ class NetworkService:
             if os.path.exists(file_path):
                 os.remove(file_path)
 
-    async def process_iidm_file(self, file_path: str) -> Optional[str]:
-        """Load an IIDM file and set it as the current network.
+    async def process_iidm_file(
+        self,
+        file_path: str,
+        network_id: Optional[str] = None,
+        label: Optional[str] = None,
+        activate: bool = True,
+    ) -> Optional[str]:
+        """Load an IIDM file and register it as a network.
 
         Args:
             file_path: Path to the IIDM file
+            network_id: Id of the network, replaced if already loaded
+            label: Human readable name of the network
+            activate: Whether the network becomes the active one
 
         Returns:
             Optional[str]: Error message if loading fails, None otherwise
         """
         try:
             network = pn.load(file_path)
-            self.current_network = network
-            self._current_file_path = file_path
+            network_id = network_id or os.path.splitext(os.path.basename(file_path))[0]
+            self._networks[network_id] = {
+                "network": network,
+                "file_path": file_path,
+                "label": label or network_id,
+                "version": self._file_version(file_path),
+            }
+
+            if activate or self._active_network_id is None:
+                self._active_network_id = network_id
 
             # Save information about the last loaded network
             self._save_network_metadata()
@@ -106,11 +245,23 @@ class NetworkService:
         except Exception as e:
             return f"Error loading network: {str(e)}"
 
+    @staticmethod
+    def _file_version(file_path: str) -> str:
+        """Hash the content of a network file, used to version cached results."""
+        digest = hashlib.sha256()
+        with open(file_path, "rb") as f:
+            for chunk in iter(lambda: f.read(1 << 20), b""):
+                digest.update(chunk)
+        return digest.hexdigest()
+
     def _save_network_metadata(self):
-        """Save metadata about the current network for persistence."""
+        """Save metadata about the active network for persistence."""
         try:
+            entry = self._networks.get(self._active_network_id, {})
             metadata = {
-                "file_path": self._current_file_path,
+                "file_path": entry.get("file_path"),
+                "network_id": self._active_network_id,
+                "label": entry.get("label"),
                 "timestamp": asyncio.get_event_loop().time(),
             }
             with open(self.LAST_NETWORK_FILE, "w") as f:
@@ -140,7 +291,9 @@ class NetworkService:
                 return await self._load_most_recent_network()
 
             # Load the network from the saved path
-            return await self.process_iidm_file(file_path)
+            return await self.process_iidm_file(
+                file_path, metadata.get("network_id"), metadata.get("label")
+            )
 
         except Exception as e:
             return f"Failed to load last network: {str(e)}"
@@ -196,26 +349,30 @@ class NetworkService:
         except Exception as e:
             logging.error(f"Error during network cleanup: {str(e)}")
 
-    async def element_exists(self, element_id: str) -> bool:
-        """Check if an element exists in the current network.
+    async def element_exists(
+        self, element_id: str, network_id: Optional[str] = None
+    ) -> bool:
+        """Check if an element exists in a loaded network.
 
         Args:
             element_id: The element ID to check
+            network_id: Optional network id, the active network by default
 
         Returns:
             bool: True if the element exists, False otherwise
         """
-        if not self.current_network:
+        network = self.get_network(network_id)
+        if not network:
             return False
 
         try:
             # Check in voltage levels
-            voltage_levels = self.current_network.get_voltage_levels()
+            voltage_levels = network.get_voltage_levels()
             if element_id in voltage_levels.index:
                 return True
 
             # Check in substations
-            substations = self.current_network.get_substations()
+            substations = network.get_substations()
             if element_id in substations.index:
                 return True
 
@@ -224,17 +381,19 @@ class NetworkService:
             return False
 
     async def generate_single_line_diagram(
-        self, element_id: str
+        self, element_id: str, network_id: Optional[str] = None
     ) -> Tuple[Optional[str], Optional[Dict[str, Any]]]:
         """Generate a single line diagram for a network element.
 
         Args:
             element_id: The element ID (voltage level or substation)
+            network_id: Optional network id, the active network by default
 
         Returns:
             tuple: (SVG diagram content, JSON metadata) or (None, None) on error
         """
-        if not self.current_network:
+        network = self.get_network(network_id)
+        if not network:
             return None, None
 
         # Configure diagram parameters
@@ -246,7 +405,7 @@ class NetworkService:
                 self._temp_file(".json") as metadata_path,
             ):
                 # Generate the SVG with metadata
-                self.current_network.write_single_line_diagram_svg(
+                network.write_single_line_diagram_svg(
                     container_id=element_id,
                     svg_file=svg_path,
                     metadata_file=metadata_path,
@@ -265,21 +424,181 @@ class NetworkService:
         except Exception as e:
             return None, {"error": str(e)}
 
-    async def convert_network_to_json(
+    def _sld_parameters(self, layout: Optional[Dict[str, Any]] = None):
+        """Build the SLD parameters, with the layout overrides accepted by the
+        installed pypowsybl.
+
+        Args:
+            layout: Layout parameters by snake case name, e.g.
+                zone_layout_snake_line_padding
+
+        Returns:
+            pn.SldParameters: Parameters of the diagram
+        """
+        parameters = dict(self.SLD_PARAMETERS)
+        supported = inspect.signature(pn.SldParameters).parameters
+        for name, value in (layout or {}).items():
+            if value is None:
+                continue
+            if name in supported:
+                parameters[name] = value
+            else:
+                logging.warning(
+                    f"Layout parameter '{name}' is not supported by pypowsybl, ignored"
+                )
+        return pn.SldParameters(**parameters)
+
+    async def generate_zone_single_line_diagram(
         self,
+        substation_ids: List[str],
+        network_id: Optional[str] = None,
+        layout: Optional[Dict[str, Any]] = None,
+    ) -> Tuple[Optional[str], Optional[Dict[str, Any]]]:
+        """Generate a single line diagram spanning several substations.
+
+        The substations are laid out side by side on a single row. The layout
+        parameters applied are reported in the layoutParams of the metadata.
+
+        Args:
+            substation_ids: Substations of the zone, in display order
+            network_id: Optional network id, the active network by default
+            layout: Optional zone layout parameters
+
+        Returns:
+            tuple: (SVG diagram content, JSON metadata with the feeder mapping)
+                or (None, None) on error
+        """
+        network = self.get_network(network_id)
+        if not network:
+            return None, None
+
+        try:
+            params = self._sld_parameters(layout)
+            with (
+                self._temp_file(".svg") as svg_path,
+                self._temp_file(".json") as metadata_path,
+            ):
+                network.write_matrix_multi_substation_single_line_diagram_svg(
+                    matrix_ids=[substation_ids],
+                    svg_file=svg_path,
+                    metadata_file=metadata_path,
+                    parameters=params,
+                )
+
+                with open(svg_path, "r") as svg_file:
+                    svg_content = svg_file.read()
+
+                with open(metadata_path, "r") as metadata_file:
+                    metadata_content = json.load(metadata_file)
+
+            feeder_mapping = self._feeder_mapping(
+                network, metadata_content.get("feederInfos", [])
+            )
+            return svg_content, {
+                "metadata": metadata_content,
+                "feeder_mapping": feeder_mapping,
+            }
+        except Exception as e:
+            return None, {"error": str(e)}
+
+    @staticmethod
+    def _feeder_mapping(network, feeder_infos) -> List[Dict[str, Any]]:
+        """Locate the voltage level and substation of every feeder of a diagram."""
+        terminals = network.get_terminals()
+        substation_by_vl = network.get_voltage_levels()["substation_id"].to_dict()
+
+        mapping = []
+        for feeder in feeder_infos:
+            equipment_id = feeder.get("equipmentId")
+            if equipment_id not in terminals.index:
+                continue
+
+            rows = terminals.loc[[equipment_id]]
+            side = feeder.get("side")
+            if side and "element_side" in rows.columns:
+                sided = rows[rows["element_side"].astype(str).str.endswith(side)]
+                if not sided.empty:
+                    rows = sided
+
+            voltage_level_id = rows.iloc[0]["voltage_level_id"]
+            mapping.append(
+                {
+                    "feeder_id": feeder.get("id"),
+                    "equipment_id": equipment_id,
+                    "component_type": feeder.get("componentType"),
+                    "side": side,
+                    "voltage_level_id": voltage_level_id,
+                    "substation_id": substation_by_vl.get(voltage_level_id),
+                }
+            )
+        return mapping
+
+    async def generate_network_area_diagram(
+        self,
+        voltage_level_ids: List[str],
+        depth: int = 0,
+        network_id: Optional[str] = None,
+    ) -> Tuple[Optional[str], Optional[Dict[str, Any]]]:
+        """Generate a network area diagram around a set of voltage levels.
+
+        Args:
+            voltage_level_ids: Voltage levels the diagram is centered on
+            depth: Number of voltage levels displayed around the given ones
+            network_id: Optional network id, the active network by default
+
+        Returns:
+            tuple: (SVG diagram content, JSON metadata) or (None, None) on error
+        """
+        network = self.get_network(network_id)
+        if not network:
+            return None, None
+
+        # Configure diagram parameters
+        params = pn.NadParameters(**self.NAD_PARAMETERS)
+
+        try:
+            with (
+                self._temp_file(".svg") as svg_path,
+                self._temp_file(".json") as metadata_path,
+            ):
+                # Generate the SVG with metadata
+                network.write_network_area_diagram(
+                    svg_file=svg_path,
+                    voltage_level_ids=voltage_level_ids,
+                    depth=depth,
+                    nad_parameters=params,
+                    metadata_file=metadata_path,
+                )
+
+                with open(svg_path, "r") as svg_file:
+                    svg_content = svg_file.read()
+
+                with open(metadata_path, "r") as metadata_file:
+                    metadata_content = json.load(metadata_file)
+
+                return svg_content, metadata_content
+        except Exception as e:
+            return None, {"error": str(e)}
+
+    async def convert_network_to_json(
+        self, network_id: Optional[str] = None
     ) -> Tuple[Optional[Dict[str, Any]], Optional[str]]:
-        """Convert the current network to JSON format.
+        """Convert a loaded network to JSON format.
+
+        Args:
+            network_id: Optional network id, the active network by default
 
         Returns:
             tuple: (JSON content, error message) where one will be None
         """
-        if not self.current_network:
+        network = self.get_network(network_id)
+        if not network:
             return None, "No network loaded"
 
         try:
             with self._temp_file(".jiidm") as json_path:
                 # Export network to JSON format
-                self.current_network.save(json_path, format="JIIDM")
+                network.save(json_path, format="JIIDM")
 
                 # Read the generated JSON content
                 with open(json_path, "r", encoding="utf-8") as json_file:
@@ -291,18 +610,24 @@ class NetworkService:
         except Exception as e:
             return None, f"Error converting network to JSON: {str(e)}"
 
-    async def get_substations(self) -> Tuple[Dict[str, Any], Optional[str]]:
+    async def get_substations(
+        self, network_id: Optional[str] = None
+    ) -> Tuple[Dict[str, Any], Optional[str]]:
         """Get a JSON representation of all substations in the network.
 
+        Args:
+            network_id: Optional network id, the active network by default
+
         Returns:
             tuple: (JSON content, error message) where one will be None
         """
-        if not self.current_network:
+        network = self.get_network(network_id)
+        if not network:
             return None, "No network loaded"
 
         try:
             # Get substations dataframe
-            substations_df = self.current_network.get_substations()
+            substations_df = network.get_substations()
 
             # Create the result dictionary
             result = {"substations": []}
@@ -327,18 +652,24 @@ class NetworkService:
         except Exception as e:
             return None, f"Error retrieving substations: {str(e)}"
 
-    async def get_voltage_levels(self) -> Tuple[Dict[str, Any], Optional[str]]:
+    async def get_voltage_levels(
+        self, network_id: Optional[str] = None
+    ) -> Tuple[Dict[str, Any], Optional[str]]:
         """Get a JSON representation of all voltage levels in the network.
 
+        Args:
+            network_id: Optional network id, the active network by default
+
         Returns:
             tuple: (JSON content, error message) where one will be None
         """
-        if not self.current_network:
+        network = self.get_network(network_id)
+        if not network:
             return None, "No network loaded"
 
         try:
             # Get voltage levels dataframe
-            voltage_levels_df = self.current_network.get_voltage_levels()
+            voltage_levels_df = network.get_voltage_levels()
 
             # Create the result dictionary
             result = {"voltage_levels": []}
@@ -359,3 +690,256 @@ class NetworkService:
             return result, None
         except Exception as e:
             return None, f"Error retrieving voltage levels: {str(e)}"
+
+    async def get_topology(
+        self, network_id: Optional[str] = None
+    ) -> Tuple[Dict[str, Any], Optional[str]]:
+        """Get the substations, voltage levels, buses, branches and injections of the network.
+
+        Args:
+            network_id: Optional network id, the active network by default
+
+        Returns:
+            tuple: (JSON content, error message) where one will be None
+        """
+        network = self.get_network(network_id)
+        if not network:
+            return None, "No network loaded"
+
+        try:
+            result = {
+                "substations": [],
+                "voltage_levels": [],
+                "buses": [],
+                "branches": [],
+            }
+
+            for substation_id, substation in network.get_substations().iterrows():
+                result["substations"].append(
+                    {"id": substation_id, "name": substation.get("name", "")}
+                )
+
+            for vl_id, vl in network.get_voltage_levels().iterrows():
+                result["voltage_levels"].append(
+                    {
+                        "id": vl_id,
+                        "substation_id": vl.get("substation_id", ""),
+                        "nominal_v": float(vl.get("nominal_v", 0)),
+                    }
+                )
+
+            for bus_id, bus in network.get_buses().iterrows():
+                result["buses"].append(
+                    {"id": bus_id, "voltage_level_id": bus.get("voltage_level_id", "")}
+                )
+
+            branch_frames = [
+                ("LINE", network.get_lines()),
+                ("TWO_WINDINGS_TRANSFORMER", network.get_2_windings_transformers()),
+            ]
+            for branch_type, branches_df in branch_frames:
+                for branch_id, branch in branches_df.iterrows():
+                    result["branches"].append(
+                        {
+                            "id": branch_id,
+                            "type": branch_type,
+                            "voltage_level1_id": branch.get("voltage_level1_id", ""),
+                            "voltage_level2_id": branch.get("voltage_level2_id", ""),
+                            "bus1_id": branch.get("bus1_id", "") or None,
+                            "bus2_id": branch.get("bus2_id", "") or None,
+                            "connected1": bool(branch.get("connected1", False)),
+                            "connected2": bool(branch.get("connected2", False)),
+                            "x": float(branch.get("x", 0)),
+                        }
+                    )
+
+            # Tie lines join two dangling lines, which are no longer injections
+            dangling_lines = network.get_dangling_lines()
+            paired_dangling_lines = set()
+            for tie_line_id, tie_line in network.get_tie_lines().iterrows():
+                ends = [tie_line["dangling_line1_id"], tie_line["dangling_line2_id"]]
+                if not all(end in dangling_lines.index for end in ends):
+                    continue
+                paired_dangling_lines.update(ends)
+                end1, end2 = (dangling_lines.loc[end] for end in ends)
+                result["branches"].append(
+                    {
+                        "id": tie_line_id,
+                        "type": "TIE_LINE",
+                        "voltage_level1_id": end1.get("voltage_level_id", ""),
+                        "voltage_level2_id": end2.get("voltage_level_id", ""),
+                        "bus1_id": end1.get("bus_id", "") or None,
+                        "bus2_id": end2.get("bus_id", "") or None,
+                        "connected1": bool(end1.get("connected", False)),
+                        "connected2": bool(end2.get("connected", False)),
+                        "x": float(end1.get("x", 0)) + float(end2.get("x", 0)),
+                    }
+                )
+
+            # HVDC lines link the buses of their converter stations, without reactance
+            converter_stations = {}
+            for stations_df in (
+                network.get_vsc_converter_stations(),
+                network.get_lcc_converter_stations(),
+            ):
+                converter_stations.update(
+                    {station_id: station for station_id, station in stations_df.iterrows()}
+                )
+            for hvdc_id, hvdc in network.get_hvdc_lines().iterrows():
+                ends = [hvdc["converter_station1_id"], hvdc["converter_station2_id"]]
+                if not all(end in converter_stations for end in ends):
+                    continue
+                station1, station2 = (converter_stations[end] for end in ends)
+                result["branches"].append(
+                    {
+                        "id": hvdc_id,
+                        "type": "HVDC_LINE",
+                        "voltage_level1_id": station1.get("voltage_level_id", ""),
+                        "voltage_level2_id": station2.get("voltage_level_id", ""),
+                        "bus1_id": station1.get("bus_id", "") or None,
+                        "bus2_id": station2.get("bus_id", "") or None,
+                        "connected1": bool(
+                            hvdc.get("connected1", station1.get("connected", False))
+                        ),
+                        "connected2": bool(
+                            hvdc.get("connected2", station2.get("connected", False))
+                        ),
+                        "x": 0.0,
+                    }
+                )
+
+            # Three windings transformers, with leg reactances referred to rated_u0
+            result["three_windings_transformers"] = []
+            for transformer_id, transformer in (
+                network.get_3_windings_transformers().iterrows()
+            ):
+                result["three_windings_transformers"].append(
+                    {
+                        "id": transformer_id,
+                        "rated_u0": float(transformer.get("rated_u0", 0)),
+                        "legs": [
+                            {
+                                "side": side,
+                                "voltage_level_id": transformer.get(
+                                    f"voltage_level{index}_id", ""
+                                ),
+                                "bus_id": transformer.get(f"bus{index}_id", "") or None,
+                                "connected": bool(
+                                    transformer.get(f"connected{index}", False)
+                                ),
+                                "x": float(transformer.get(f"x{index}", 0)),
+                            }
+                            for index, side in enumerate(["ONE", "TWO", "THREE"], start=1)
+                        ],
+                    }
+                )
+
+            # Injections and the legs of three windings transformers, by bus
+            result["injections"] = []
+            for injection_type, getter in self.TOPOLOGY_INJECTIONS:
+                for injection_id, injection in getattr(network, getter)().iterrows():
+                    if injection_id in paired_dangling_lines:
+                        continue
+                    result["injections"].append(
+                        {
+                            "id": injection_id,
+                            "type": injection_type,
+                            "bus_id": injection.get("bus_id", "") or None,
+                            "connected": bool(injection.get("connected", False)),
+                        }
+                    )
+            for transformer_id, transformer in (
+                network.get_3_windings_transformers().iterrows()
+            ):
+                for index, side in enumerate(["ONE", "TWO", "THREE"], start=1):
+                    result["injections"].append(
+                        {
+                            "id": transformer_id,
+                            "type": "THREE_WINDINGS_TRANSFORMER",
+                            "side": side,
+                            "bus_id": transformer.get(f"bus{index}_id", "") or None,
+                            "connected": bool(
+                                transformer.get(f"connected{index}", False)
+                            ),
+                        }
+                    )
+
+            return result, None
+        except Exception as e:
+            return None, f"Error retrieving topology: {str(e)}"
+
+    async def load_network(self, file_path: str):
+        """Load a network file without changing the current network.
+
+        Args:
+            file_path: Path to the network file
+
+        Returns:
+            tuple: (network, error message) where one will be None
+        """
+        try:
+            return pn.load(file_path), None
+        except Exception as e:
+            return None, f"Error loading network: {str(e)}"
+
+    @staticmethod
+    def _scalar(value):
+        """Convert a dataframe cell to a JSON-compatible value."""
+        if value is None:
+            return None
+        if hasattr(value, "item"):
+            value = value.item()
+        if isinstance(value, float) and not math.isfinite(value):
+            return None
+        return value
+
+    async def get_network_elements(self, network) -> Tuple[Dict[str, Any], Optional[str]]:
+        """Get the comparable attributes of every element of a network.
+
+        Args:
+            network: The network to describe
+
+        Returns:
+            tuple: (JSON content, error message) where one will be None
+        """
+        try:
+            result = {"elements": []}
+
+            for element_type, (getter, attributes) in self.COMPARED_ATTRIBUTES.items():
+                elements_df = getattr(network, getter)()
+                columns = [a for a in attributes if a in elements_df.columns]
+
+                for element_id, element in elements_df.iterrows():
+                    result["elements"].append(
+                        {
+                            "id": element_id,
+                            "element_type": element_type,
+                            "attributes": {
+                                column.lower(): self._scalar(element[column])
+                                for column in columns
+                            },
+                        }
+                    )
+
+            limits_df = network.get_operational_limits()
+            elements = {(e["element_type"], e["id"]): e for e in result["elements"]}
+            for element_id, limit in limits_df.iterrows():
+                element = next(
+                    (
+                        elements[(element_type, element_id)]
+                        for element_type in ("LINE", "TWO_WINDINGS_TRANSFORMER")
+                        if (element_type, element_id) in elements
+                    ),
+                    None,
+                )
+                if element is None:
+                    continue
+
+                duration = int(limit.get("acceptable_duration", -1))
+                name = "permanent" if duration < 0 else f"{duration}s"
+                key = f"limit_{str(limit.get('type', '')).lower()}_{str(limit.get('side', '')).lower()}_{name}"
+                element["attributes"][key] = self._scalar(limit.get("value"))
+
+            return result, None
+        except Exception as e:
+            return None, f"Error retrieving network elements: {str(e)}"
@@ -28,9 +28,16 @@ class ZmqHandler:
             "get_current_network_info": self.handle_get_current_network_info,
             "get_single_line_diagram": self.handle_get_single_line_diagram,
             "get_single_line_diagram_metadata": self.handle_get_single_line_diagram_metadata,
+            "get_network_area_diagram": self.handle_get_network_area_diagram,
+            "get_zone_single_line_diagram": self.handle_get_zone_single_line_diagram,
             "get_network_substations": self.handle_get_network_substations,
             "get_network_voltage_levels": self.handle_get_network_voltage_levels,
-            "get_voltage_levels_for_substation": self.handle_get_voltage_levels_for_substation
+            "get_voltage_levels_for_substation": self.handle_get_voltage_levels_for_substation,
+            "get_network_topology": self.handle_get_network_topology,
+            "get_network_elements": self.handle_get_network_elements,
+            "list_networks": self.handle_list_networks,
+            "set_active_network": self.handle_set_active_network,
+            "remove_network": self.handle_remove_network
         }
         
         # Ensure upload folder exists
@@ -113,7 +120,10 @@ class ZmqHandler:
         """Handle IIDM file upload.
         
         Args:
-            params: Dict containing file_data as base64 and filename
+            params: Dict containing file_data as base64 and filename, and
+                optionally the network_id and label of the uploaded network.
+                Without a network_id, the file is loaded as a new network;
+                with the id of a loaded network, that network is replaced.
             
         Returns:
             tuple: (status_code, result)
@@ -138,7 +148,11 @@ class ZmqHandler:
             
             # Process the file
             self.logger.info(f"File received and saved to {destination}. Processing in progress...")
-            error = await self.network_service.process_iidm_file(destination)
+            network_id = params.get("network_id") or uuid.uuid4().hex
+            label = params.get("label") or os.path.basename(filename)
+            error = await self.network_service.process_iidm_file(
+                destination, network_id, label, params.get("activate", True)
+            )
             
             if error:
                 os.remove(destination)
@@ -149,7 +163,12 @@ class ZmqHandler:
             # After successful upload, clean up old files
             await self.network_service.cleanup_old_networks()
             
-            return 201, {"status": "IIDM file loaded", "file_path": destination}
+            return 201, {
+                "status": "IIDM file loaded",
+                "file_path": destination,
+                "network_id": network_id,
+                "label": label,
+            }
             
         except Exception as e:
             self.logger.error(f"Error during upload: {str(e)}")
@@ -162,12 +181,14 @@ class ZmqHandler:
             tuple: (status_code, result)
         """
         try:
+            network_id = params.get("network_id")
+
             # Check if a network is available
-            if not self.network_service.current_network:
+            if not self.network_service.get_network(network_id):
                 return 404, {"error": "No network available"}
             
             # Convert the network to JSON
-            json_content, error = await self.network_service.convert_network_to_json()
+            json_content, error = await self.network_service.convert_network_to_json(network_id)
             
             if error:
                 return 500, {"error": f"Error converting network to JSON: {error}"}
@@ -191,6 +212,7 @@ class ZmqHandler:
             # Basic network info
             info = {
                 "status": "Network loaded",
+                "network_id": self.network_service.active_network_id,
                 "file_path": self.network_service.current_file_path,
                 "filename": os.path.basename(self.network_service.current_file_path)
                 if self.network_service.current_file_path
@@ -231,18 +253,21 @@ class ZmqHandler:
                 return 400, {"error": "Element ID is required"}
                 
             element_id = params["id"]
+            network_id = params.get("network_id")
             response_format = params.get("format", "svg")
             
             # Check if a network is available
-            if not self.network_service.current_network:
+            if not self.network_service.get_network(network_id):
                 return 404, {"error": "No network available"}
                 
             # Check if the ID exists in the network
-            if not await self.network_service.element_exists(element_id):
+            if not await self.network_service.element_exists(element_id, network_id):
                 return 404, {"error": f"The identifier '{element_id}' doesn't exist in the network"}
                 
             # Generate the SVG and metadata
-            svg_content, metadata = await self.network_service.generate_single_line_diagram(element_id)
+            svg_content, metadata = await self.network_service.generate_single_line_diagram(
+                element_id, network_id
+            )
             
             if svg_content is None:
                 return 500, {
@@ -276,14 +301,17 @@ class ZmqHandler:
                 return 400, {"error": "Element ID is required"}
                 
             element_id = params["id"]
+            network_id = params.get("network_id")
             
-            if not self.network_service.current_network:
+            if not self.network_service.get_network(network_id):
                 return 404, {"error": "No network available"}
                 
-            if not await self.network_service.element_exists(element_id):
+            if not await self.network_service.element_exists(element_id, network_id):
                 return 404, {"error": f"The identifier '{element_id}' doesn't exist in the network"}
                 
-            _, metadata = await self.network_service.generate_single_line_diagram(element_id)
+            _, metadata = await self.network_service.generate_single_line_diagram(
+                element_id, network_id
+            )
             
             if metadata is None or "error" in metadata:
                 return 500, {
@@ -297,6 +325,86 @@ class ZmqHandler:
             self.logger.error(f"Error when retrieving metadata: {str(e)}")
             return 500, {"error": f"Unable to retrieve metadata: {str(e)}"}
     
+    async def handle_get_zone_single_line_diagram(self, params):
+        """Handle request for a single line diagram spanning several substations.
+        
+        Args:
+            params: Dict containing substation_ids, and optionally the
+                network_id and the zone layout parameters
+            
+        Returns:
+            tuple: (status_code, result)
+        """
+        try:
+            substation_ids = params.get("substation_ids")
+            if not substation_ids:
+                return 400, {"error": "At least one substation ID is required"}
+                
+            network_id = params.get("network_id")
+            
+            if not self.network_service.get_network(network_id):
+                return 404, {"error": "No network available"}
+                
+            for substation_id in substation_ids:
+                if not await self.network_service.element_exists(substation_id, network_id):
+                    return 404, {"error": f"The identifier '{substation_id}' doesn't exist in the network"}
+                    
+            svg_content, result = await self.network_service.generate_zone_single_line_diagram(
+                substation_ids, network_id, params.get("layout")
+            )
+            
+            if svg_content is None:
+                return 500, {
+                    "error": "Failed to generate zone diagram",
+                    "details": (result or {}).get("error", "Unknown error"),
+                }
+                
+            return 200, {"svg": svg_content, **result}
+                
+        except Exception as e:
+            self.logger.error(f"Error when generating zone diagram: {str(e)}")
+            return 500, {"error": f"Unable to generate zone diagram: {str(e)}"}
+    
+    async def handle_get_network_area_diagram(self, params):
+        """Handle request for a network area diagram.
+        
+        Args:
+            params: Dict containing voltage_level_ids and an optional depth
+            
+        Returns:
+            tuple: (status_code, result)
+        """
+        try:
+            voltage_level_ids = params.get("voltage_level_ids")
+            if not voltage_level_ids:
+                return 400, {"error": "At least one voltage level ID is required"}
+                
+            depth = int(params.get("depth") or 0)
+            network_id = params.get("network_id")
+            
+            if not self.network_service.get_network(network_id):
+                return 404, {"error": "No network available"}
+                
+            for voltage_level_id in voltage_level_ids:
+                if not await self.network_service.element_exists(voltage_level_id, network_id):
+                    return 404, {"error": f"The identifier '{voltage_level_id}' doesn't exist in the network"}
+                    
+            svg_content, metadata = await self.network_service.generate_network_area_diagram(
+                voltage_level_ids, depth, network_id
+            )
+            
+            if svg_content is None:
+                return 500, {
+                    "error": "Failed to generate network area diagram",
+                    "details": (metadata or {}).get("error", "Unknown error"),
+                }
+                
+            return 200, {"svg": svg_content, "metadata": metadata}
+                
+        except Exception as e:
+            self.logger.error(f"Error when generating network area diagram: {str(e)}")
+            return 500, {"error": f"Unable to generate network area diagram: {str(e)}"}
+    
     async def handle_get_network_substations(self, params):
         """Handle request for all network substations.
         
@@ -304,12 +412,14 @@ class ZmqHandler:
             tuple: (status_code, result)
         """
         try:
+            network_id = params.get("network_id")
+
             # Check if a network is available
-            if not self.network_service.current_network:
+            if not self.network_service.get_network(network_id):
                 return 404, {"error": "No network available"}
                 
             # Get substations JSON
-            substations_json, error = await self.network_service.get_substations()
+            substations_json, error = await self.network_service.get_substations(network_id)
             
             if error:
                 return 500, {"error": f"Error retrieving substations: {error}"}
@@ -327,12 +437,14 @@ class ZmqHandler:
             tuple: (status_code, result)
         """
         try:
+            network_id = params.get("network_id")
+
             # Check if a network is available
-            if not self.network_service.current_network:
+            if not self.network_service.get_network(network_id):
                 return 404, {"error": "No network available"}
                 
             # Get voltage levels JSON
-            voltage_levels_json, error = await self.network_service.get_voltage_levels()
+            voltage_levels_json, error = await self.network_service.get_voltage_levels(network_id)
             
             if error:
                 return 500, {"error": f"Error retrieving voltage levels: {error}"}
@@ -357,18 +469,19 @@ class ZmqHandler:
                 return 400, {"error": "Substation ID is required"}
                 
             substation_id = params["substation_id"]
+            network = self.network_service.get_network(params.get("network_id"))
             
             # Check if a network is available
-            if not self.network_service.current_network:
+            if not network:
                 return 404, {"error": "No network available"}
                 
             # Check if the substation exists
-            substations_df = self.network_service.current_network.get_substations()
+            substations_df = network.get_substations()
             if substation_id not in substations_df.index:
                 return 404, {"error": f"Substation '{substation_id}' not found"}
                 
             # Get all voltage levels
-            voltage_levels_df = self.network_service.current_network.get_voltage_levels()
+            voltage_levels_df = network.get_voltage_levels()
             
             # Filter voltage levels belonging to the specified substation
             vl_for_substation = voltage_levels_df[
@@ -399,3 +512,135 @@ class ZmqHandler:
         except Exception as e:
             self.logger.error(f"Error when getting voltage levels for substation: {str(e)}")
             return 500, {"error": f"Unable to get voltage levels for substation: {str(e)}"}
+
+    async def handle_get_network_topology(self, params):
+        """Handle request for the network topology (substations, buses, branches and injections).
+        
+        Returns:
+            tuple: (status_code, result)
+        """
+        try:
+            network_id = params.get("network_id")
+
+            # Check if a network is available
+            if not self.network_service.get_network(network_id):
+                return 404, {"error": "No network available"}
+                
+            # Get topology JSON
+            topology_json, error = await self.network_service.get_topology(network_id)
+            
+            if error:
+                return 500, {"error": f"Error retrieving topology: {error}"}
+                
+            return 200, topology_json
+                
+        except Exception as e:
+            self.logger.error(f"Error when getting network topology: {str(e)}")
+            return 500, {"error": f"Unable to get network topology: {str(e)}"}
+
+    async def handle_get_network_elements(self, params):
+        """Handle request for the comparable elements of a network.
+        
+        Args:
+            params: Dict optionally containing file_data as base64 and filename.
+                Without them, the loaded network given by network_id is used.
+            
+        Returns:
+            tuple: (status_code, result)
+        """
+        try:
+            if "file_data" in params:
+                if "filename" not in params:
+                    return 400, {"error": "Filename is required with file data"}
+
+                file_data = base64.b64decode(params["file_data"])
+                _, extension = os.path.splitext(params["filename"])
+                destination = os.path.join(
+                    self.network_service.UPLOAD_FOLDER,
+                    f"compare_{uuid.uuid4().hex}{extension or '.xiidm'}",
+                )
+
+                with open(destination, "wb") as f:
+                    f.write(file_data)
+
+                try:
+                    network, error = await self.network_service.load_network(destination)
+                finally:
+                    os.remove(destination)
+
+                if error:
+                    return 400, {"error": error}
+            else:
+                network = self.network_service.get_network(params.get("network_id"))
+                if not network:
+                    return 404, {"error": "No network available"}
+
+            elements_json, error = await self.network_service.get_network_elements(network)
+
+            if error:
+                return 500, {"error": f"Error retrieving network elements: {error}"}
+
+            return 200, elements_json
+
+        except Exception as e:
+            self.logger.error(f"Error when getting network elements: {str(e)}")
+            return 500, {"error": f"Unable to get network elements: {str(e)}"}
+
+    async def handle_list_networks(self, params):
+        """Handle request for the list of loaded networks.
+        
+        Returns:
+            tuple: (status_code, result)
+        """
+        try:
+            return 200, self.network_service.list_networks()
+            
+        except Exception as e:
+            self.logger.error(f"Error when listing networks: {str(e)}")
+            return 500, {"error": f"Unable to list networks: {str(e)}"}
+
+    async def handle_set_active_network(self, params):
+        """Handle selection of the active network.
+        
+        Args:
+            params: Dict containing network_id
+            
+        Returns:
+            tuple: (status_code, result)
+        """
+        try:
+            if "network_id" not in params:
+                return 400, {"error": "Network ID is required"}
+
+            error = self.network_service.set_active_network(params["network_id"])
+            if error:
+                return 404, {"error": error}
+
+            return 200, self.network_service.list_networks()
+            
+        except Exception as e:
+            self.logger.error(f"Error when setting active network: {str(e)}")
+            return 500, {"error": f"Unable to set active network: {str(e)}"}
+
+    async def handle_remove_network(self, params):
+        """Handle unloading of a network.
+        
+        Args:
+            params: Dict containing network_id
+            
+        Returns:
+            tuple: (status_code, result)
+        """
+        try:
+            if "network_id" not in params:
+                return 400, {"error": "Network ID is required"}
+
+            error = self.network_service.remove_network(params["network_id"])
+            if error:
+                return 404, {"error": error}
+
+            return 200, self.network_service.list_networks()
+            
+        except Exception as e:
+            self.logger.error(f"Error when removing network: {str(e)}")
+            return 500, {"error": f"Unable to remove network: {str(e)}"}
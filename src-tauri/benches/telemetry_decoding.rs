@@ -0,0 +1,142 @@
+//! Decoding throughput of a telemetry message in JSON, MessagePack and CBOR.
+//!
+//! Run with `cargo bench --bench telemetry_decoding`.
+
+use argus_lib::telemetry_codec::{
+    BinaryTelemetry, BinaryValues, TelemetryDecoder, TelemetryEncoding, TelemetryHeader,
+};
+use argus_lib::TelemetryCurves;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::collections::HashMap;
+
+/// Number of values of a step, in the order of a large substation set
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+fn ids(size: usize) -> Vec<String> {
+    (0..size)
+        .map(|i| format!("NETWORK_VL{:05}_LINE{:05}_ONE_ARROW_ACTIVE", i / 8, i))
+        .collect()
+}
+
+fn encode<T: serde::Serialize>(encoding: TelemetryEncoding, value: &T) -> Vec<u8> {
+    match encoding {
+        TelemetryEncoding::Json => serde_json::to_vec(value).unwrap(),
+        TelemetryEncoding::MessagePack => rmp_serde::to_vec_named(value).unwrap(),
+        TelemetryEncoding::Cbor => {
+            let mut buffer = Vec::new();
+            ciborium::into_writer(value, &mut buffer).unwrap();
+            buffer
+        }
+    }
+}
+
+fn header(encoding: TelemetryEncoding, dictionary: bool) -> Vec<u8> {
+    TelemetryHeader {
+        encoding,
+        dictionary,
+    }
+    .to_string()
+    .into_bytes()
+}
+
+// Decode a message once, checking it gives the expected values before timing it
+fn check(decoded: Option<TelemetryCurves>, expected: &HashMap<String, f64>) {
+    let decoded = decoded.expect("a message with values");
+    assert_eq!(decoded.curves.time, 42);
+    assert_eq!(&decoded.curves.values, expected);
+}
+
+fn bench_decoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("telemetry_decoding");
+
+    for size in SIZES {
+        let ids = ids(size);
+        // Quarters are exact in binary, so every encoding gives them back unchanged
+        let named: HashMap<String, f64> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.clone(), i as f64 * 0.25))
+            .collect();
+        let indexed: Vec<Option<f64>> = (0..size).map(|i| Some(i as f64 * 0.25)).collect();
+        group.throughput(Throughput::Elements(size as u64));
+
+        // JSON as published today, without header
+        let json = serde_json::to_vec(&serde_json::json!({
+            "curves": { "values": named, "time": 42 }
+        }))
+        .unwrap();
+        group.bench_with_input(BenchmarkId::new("json", size), &json, |b, payload| {
+            let mut decoder = TelemetryDecoder::default();
+            check(decoder.decode([payload.as_slice()]).unwrap(), &named);
+            b.iter(|| decoder.decode([payload.as_slice()]).unwrap())
+        });
+
+        for (name, encoding) in [
+            ("msgpack", TelemetryEncoding::MessagePack),
+            ("cbor", TelemetryEncoding::Cbor),
+        ] {
+            let named_payload = encode(
+                encoding,
+                &BinaryTelemetry {
+                    time: 42,
+                    values: BinaryValues::Named(named.clone()),
+                },
+            );
+            let data_header = header(encoding, false);
+            group.bench_with_input(
+                BenchmarkId::new(name, size),
+                &named_payload,
+                |b, payload| {
+                    let mut decoder = TelemetryDecoder::default();
+                    check(
+                        decoder
+                            .decode([data_header.as_slice(), payload.as_slice()])
+                            .unwrap(),
+                        &named,
+                    );
+                    b.iter(|| {
+                        decoder
+                            .decode([data_header.as_slice(), payload.as_slice()])
+                            .unwrap()
+                    })
+                },
+            );
+
+            // Ids sent once, then values only
+            let dictionary = encode(encoding, &ids);
+            let indexed_payload = encode(
+                encoding,
+                &BinaryTelemetry {
+                    time: 42,
+                    values: BinaryValues::Indexed(indexed.clone()),
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new(format!("{}_dict", name), size),
+                &indexed_payload,
+                |b, payload| {
+                    let mut decoder = TelemetryDecoder::default();
+                    decoder
+                        .decode([header(encoding, true).as_slice(), dictionary.as_slice()])
+                        .unwrap();
+                    check(
+                        decoder
+                            .decode([data_header.as_slice(), payload.as_slice()])
+                            .unwrap(),
+                        &named,
+                    );
+                    b.iter(|| {
+                        decoder
+                            .decode([data_header.as_slice(), payload.as_slice()])
+                            .unwrap()
+                    })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_decoding);
+criterion_main!(benches);
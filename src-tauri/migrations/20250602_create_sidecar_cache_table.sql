@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS sidecar_cache (
+    method TEXT NOT NULL,
+    params TEXT NOT NULL,
+    network_version TEXT NOT NULL,
+    network_id TEXT NOT NULL,
+    response JSON NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (method, params, network_version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sidecar_cache_network
+ON sidecar_cache(network_id);
@@ -0,0 +1,36 @@
+CREATE TABLE IF NOT EXISTS historian_series (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+-- Raw values, one row per series and simulation time
+CREATE TABLE IF NOT EXISTS historian_samples (
+    series_id INTEGER NOT NULL,
+    sim_time INTEGER NOT NULL,
+    wall_time INTEGER NOT NULL,
+    value REAL NOT NULL,
+    PRIMARY KEY (series_id, sim_time)
+) WITHOUT ROWID;
+
+CREATE INDEX IF NOT EXISTS idx_historian_samples_wall_time
+ON historian_samples(wall_time);
+
+-- Downsampled values, one row per series, tier interval and bucket
+CREATE TABLE IF NOT EXISTS historian_rollups (
+    series_id INTEGER NOT NULL,
+    tier INTEGER NOT NULL,
+    bucket INTEGER NOT NULL,
+    count INTEGER NOT NULL,
+    sum REAL NOT NULL,
+    min REAL NOT NULL,
+    max REAL NOT NULL,
+    first_time INTEGER NOT NULL,
+    first REAL NOT NULL,
+    last_time INTEGER NOT NULL,
+    last REAL NOT NULL,
+    wall_time INTEGER NOT NULL,
+    PRIMARY KEY (series_id, tier, bucket)
+) WITHOUT ROWID;
+
+CREATE INDEX IF NOT EXISTS idx_historian_rollups_wall_time
+ON historian_rollups(tier, wall_time);
@@ -0,0 +1,20 @@
+-- Raise, clear and acknowledge events of the limit alarms
+CREATE TABLE IF NOT EXISTS alarm_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    alarm_id TEXT NOT NULL,
+    telemetry_id TEXT NOT NULL,
+    limit_name TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    value REAL NOT NULL,
+    threshold REAL NOT NULL,
+    sim_time INTEGER NOT NULL,
+    wall_time INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_alarm_events_wall_time
+ON alarm_events(wall_time);
+
+CREATE INDEX IF NOT EXISTS idx_alarm_events_alarm_id
+ON alarm_events(alarm_id, wall_time);
@@ -0,0 +1,18 @@
+-- State changes of the breakers and disconnectors, in the order they were received
+CREATE TABLE IF NOT EXISTS switch_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    telemetry_id TEXT NOT NULL,
+    equipment_id TEXT NOT NULL,
+    component_type TEXT NOT NULL,
+    old_state TEXT NOT NULL,
+    new_state TEXT NOT NULL,
+    value REAL NOT NULL,
+    sim_time INTEGER NOT NULL,
+    wall_time INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_switch_events_wall_time
+ON switch_events(wall_time);
+
+CREATE INDEX IF NOT EXISTS idx_switch_events_equipment_id
+ON switch_events(equipment_id, wall_time);
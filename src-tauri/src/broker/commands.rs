@@ -1,12 +1,25 @@
-use async_nats::Message;
+use async_nats::Subscriber;
 use futures::stream::StreamExt;
 use log::{debug, info, warn};
 use std::collections::HashMap;
-use tauri::{ipc::Channel, State};
+use std::sync::Arc;
+use std::time::Instant;
+use tauri::{ipc::Channel, AppHandle, Manager, State};
 use tokio::sync::broadcast;
 
 use crate::{
-    shared::entities::dynawo::GameMasterOutput, sld_metadata::SldMetadata, state::AppState,
+    powsybl::{
+        calculations::Calculator,
+        entities::{Quality, TelemetryData},
+        quality::QualityMonitor,
+    },
+    sessions::{
+        state::{next_replayed, TelemetryFeed},
+        SessionMessage,
+    },
+    shared::entities::dynawo::GameMasterOutput,
+    sld_metadata::SldMetadata,
+    state::AppState,
 };
 
 use super::{
@@ -18,11 +31,15 @@ const TOPIC: &str = "GameMaster";
 
 #[tauri::command(rename_all = "snake_case")]
 pub async fn connect_broker(
+    app_handle: AppHandle,
     state: State<'_, BrokerState>,
     app: State<'_, AppState>,
+    feed: State<'_, TelemetryFeed>,
+    quality: State<'_, QualityMonitor>,
     substation_id: String,
     metadata: SldMetadata,
     channel: Channel<HashMap<String, f64>>,
+    quality_channel: Option<Channel<HashMap<String, Quality>>>,
 ) -> BrokerResult<()> {
     // Vérifier d'abord si une connexion existe déjà pour cette sous-station
     let mut state = state.lock().await;
@@ -36,12 +53,12 @@ pub async fn connect_broker(
 
     // Subscribe to topic
     let topic = format!("{}.{}", TOPIC, substation_id.replace(".", "_"));  // Sanitize substation_id before use as topic ('.' is a delimiter in NATS)
-    let mut telemetry_subscription = state.client.subscribe(topic.clone()).await?;
+    let (mut telemetry_subscription, mut time_subscription, mut stop_subscription) =
+        subscribe_topics(&state.client, &topic).await?;
     debug!("Subscribe to topic '{}'", topic);
 
-    // Subscribe to special topic
-    let mut time_subscription = state.client.subscribe("time").await?;
-    let mut stop_subscription = state.client.subscribe("stop").await?;
+    // Serveur NATS remplacé lors d'un rechargement de la configuration
+    let mut client_watch = state.watch_client();
 
     // Handler channel for stopping
     let (stop_tx, mut stop_rx) = broadcast::channel::<()>(1);
@@ -63,44 +80,81 @@ pub async fn connect_broker(
         }
     };
 
+    // Source de la télémétrie : en direct ou rejouée
+    let mut feed = feed.watch();
+    let quality = quality.inner().clone();
+
     // let toto = app.try_read().unwrap();
     let task = tokio::spawn(async move {
         // Values state
         let mut telemetry_values: HashMap<String, f64> = HashMap::new();
+        // Identifiant de télémétrie de chaque identifiant graphique reçu
+        let mut telemetry_ids: HashMap<String, String> = HashMap::new();
+        // Points calculés à partir des valeurs reçues
+        let mut calculator = Calculator::default();
+        let mut replay = feed.replay();
         info!("Tâche de surveillance démarrée pour '{}'", topic);
 
         loop {
             tokio::select! {
                 Some(msg) = telemetry_subscription.next() => {
+                    // Les messages en direct sont ignorés pendant un rejeu
+                    if replay.is_some() {
+                        continue;
+                    }
                     let milliseconds_timestamp: u128 = std::time::SystemTime::now()
                         .duration_since(std::time::UNIX_EPOCH)
                         .unwrap()
                         .as_millis();
                     debug!("Message de télémétrie reçu sur '{}' (time:{}): {:?}", topic, milliseconds_timestamp, &msg.payload);
-                    process_telemetry_message(msg, &mut telemetry_values, &outputs);
+                    update_calculator(&app_handle, &mut calculator);
+                    process_telemetry_message(&msg.payload, &mut telemetry_values, &mut telemetry_ids, &outputs, &mut calculator, &quality);
                 }
                 Some(msg) = time_subscription.next() => {
-                    if let Ok(time_str) = std::str::from_utf8(&msg.payload) {
-                        if let Ok(time) = time_str.parse::<f64>() {
-                            debug!("Message de temps reçu: {}", time);
-
-                            if !telemetry_values.is_empty() {
-                                match channel.send(telemetry_values.clone()) {
-                                    Ok(_) => debug!("Données envoyées au canal ({} valeurs)", telemetry_values.len()),
-                                    Err(e) => warn!("Erreur lors de l'envoi des données au canal: {}", e),
-                                }
-                            }
-                        }
+                    if replay.is_none() {
+                        send_telemetry_values(&msg.payload, &telemetry_values, &channel);
+                        send_telemetry_quality(&telemetry_values, &telemetry_ids, &quality, &quality_channel);
                     }
                 }
                 Some(msg) = stop_subscription.next() => {
-                    if let Ok(payload) = std::str::from_utf8(&msg.payload) {
-                        if payload == "stop" {
-                            debug!("Message d'arrêt reçu sur le topic. Arrêt du client.");
-                            break;
+                    if replay.is_none() && is_stop_message(&msg.payload) {
+                        debug!("Message d'arrêt reçu sur le topic. Arrêt du client.");
+                        break;
+                    }
+                }
+                message = next_replayed(&mut replay) => {
+                    // Les messages d'arrêt rejoués ne ferment pas la connexion
+                    if let SessionMessage::Nats { subject, payload } = message.as_ref() {
+                        if *subject == topic {
+                            update_calculator(&app_handle, &mut calculator);
+                            process_telemetry_message(payload, &mut telemetry_values, &mut telemetry_ids, &outputs, &mut calculator, &quality);
+                        } else if subject == "time" {
+                            send_telemetry_values(payload, &telemetry_values, &channel);
+                            send_telemetry_quality(&telemetry_values, &telemetry_ids, &quality, &quality_channel);
                         }
                     }
                 }
+                Ok(()) = client_watch.changed() => {
+                    // Les abonnements sont repris sur le nouveau serveur
+                    let client = client_watch.borrow_and_update().clone();
+                    match subscribe_topics(&client, &topic).await {
+                        Ok(subscriptions) => {
+                            (telemetry_subscription, time_subscription, stop_subscription) = subscriptions;
+                            info!("Abonnements de '{}' repris sur le nouveau serveur", topic);
+                        }
+                        Err(e) => warn!("Impossible de reprendre les abonnements de '{}': {}", topic, e),
+                    }
+                }
+                _ = feed.changed() => {
+                    replay = feed.replay();
+                    telemetry_values.clear();
+                    telemetry_ids.clear();
+                    calculator = Calculator::default();
+                    match replay {
+                        Some(_) => info!("Rejeu d'une session pour '{}'", topic),
+                        None => info!("Retour à la télémétrie en direct pour '{}'", topic),
+                    }
+                }
                 result = stop_rx.recv() => {
                     match result {
                         Ok(_) => debug!("Signal d'arrêt reçu. Arrêt du client."),
@@ -188,12 +242,40 @@ pub async fn send_command_broker(
     }
 }
 
+// S'abonner au topic de la sous-station et aux topics spéciaux
+async fn subscribe_topics(
+    client: &async_nats::Client,
+    topic: &str,
+) -> Result<(Subscriber, Subscriber, Subscriber), async_nats::SubscribeError> {
+    let telemetry_subscription = client.subscribe(topic.to_string()).await?;
+    let time_subscription = client.subscribe("time").await?;
+    let stop_subscription = client.subscribe("stop").await?;
+    Ok((telemetry_subscription, time_subscription, stop_subscription))
+}
+
+// Suivre les calculs rechargés, en repartant des dernières valeurs reçues
+fn update_calculator(app_handle: &AppHandle, calculator: &mut Calculator) {
+    let set = match app_handle.state::<AppState>().read() {
+        Ok(state) => state.powsybl.calculations.clone(),
+        Err(_) => {
+            warn!("Impossible de lire les calculs chargés");
+            return;
+        }
+    };
+    if !Arc::ptr_eq(calculator.set(), &set) {
+        *calculator = Calculator::new(set);
+    }
+}
+
 fn process_telemetry_message(
-    msg: Message,
+    payload: &[u8],
     values: &mut HashMap<String, f64>,
+    telemetry_ids: &mut HashMap<String, String>,
     outputs: &Vec<GameMasterOutput>,
+    calculator: &mut Calculator,
+    quality: &QualityMonitor,
 ) {
-    if let Ok(payload) = std::str::from_utf8(&msg.payload) {
+    if let Ok(payload) = std::str::from_utf8(payload) {
         if let Some(index) = payload.find(':') {
             // Expected format is {"ID": VALUE}
 
@@ -202,28 +284,111 @@ fn process_telemetry_message(
 
             let aa = find(outputs, id);
 
-            if let Some(id) = aa {
+            if let Some((id, telemetry_id)) = aa {
                 let value_str = &value_str[2..value_str.len() - 1];
                 if let Ok(value) = value_str.parse::<f64>() {
-                    values.insert(id.clone(), value);
+                    // Les points calculés à partir de la valeur sont reçus avec elle
+                    let mut data = TelemetryData {
+                        values: HashMap::from([(telemetry_id.clone(), value)]),
+                        time: 0,
+                    };
+                    calculator.apply(&mut data, Instant::now(), quality.stale_timeout());
+
+                    for (data_id, value) in data.values {
+                        let id = if data_id == telemetry_id {
+                            id.clone()
+                        } else {
+                            // Seuls les points calculés affichés sont transmis
+                            match find_calculated(calculator, &data_id) {
+                                Some(id) => id,
+                                None => continue,
+                            }
+                        };
+                        // Les valeurs NaN ou infinies sont rejetées
+                        let valid = quality.assess_value(&data_id, value);
+                        telemetry_ids.insert(id.clone(), data_id);
+                        if valid {
+                            values.insert(id.clone(), value);
+                            debug!("Télémétrie reçue: {} = {:.2}", id, value);
+                        } else {
+                            values.remove(&id);
+                            warn!("Valeur invalide rejetée: {} = {}", id, value);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
 
-                    println!("Télémétrie reçue: {} = {:.2}", id, value);
+fn send_telemetry_values(
+    payload: &[u8],
+    values: &HashMap<String, f64>,
+    channel: &Channel<HashMap<String, f64>>,
+) {
+    if let Ok(time_str) = std::str::from_utf8(payload) {
+        if let Ok(time) = time_str.parse::<f64>() {
+            debug!("Message de temps reçu: {}", time);
+
+            if !values.is_empty() {
+                match channel.send(values.clone()) {
+                    Ok(_) => debug!("Données envoyées au canal ({} valeurs)", values.len()),
+                    Err(e) => warn!("Erreur lors de l'envoi des données au canal: {}", e),
                 }
             }
         }
     }
 }
 
-fn find(outputs: &Vec<GameMasterOutput>, id: &str) -> Option<String> {
+// Qualité des mesures reçues qui ne sont pas bonnes, par identifiant graphique
+fn send_telemetry_quality(
+    values: &HashMap<String, f64>,
+    telemetry_ids: &HashMap<String, String>,
+    quality: &QualityMonitor,
+    channel: &Option<Channel<HashMap<String, Quality>>>,
+) {
+    let Some(channel) = channel else {
+        return;
+    };
+    if values.is_empty() {
+        return;
+    }
+
+    let degraded = quality.degraded(telemetry_ids.values().map(String::as_str));
+    let qualities: HashMap<String, Quality> = telemetry_ids
+        .iter()
+        .filter_map(|(id, telemetry_id)| Some((id.clone(), *degraded.get(telemetry_id)?)))
+        .collect();
+    if let Err(e) = channel.send(qualities) {
+        warn!("Erreur lors de l'envoi de la qualité au canal: {}", e);
+    }
+}
+
+fn is_stop_message(payload: &[u8]) -> bool {
+    std::str::from_utf8(payload).is_ok_and(|payload| payload == "stop")
+}
+
+// Identifiant graphique et identifiant de télémétrie de la sortie d'un message
+fn find(outputs: &Vec<GameMasterOutput>, id: &str) -> Option<(String, String)> {
     for o in outputs {
         if id.contains(&o.dynawo_id) {
-            return o.graphical_id.clone();
+            return o.graphical_id.clone().map(|graphical_id| (graphical_id, o.dynawo_id.clone()));
         }
     }
 
     None
 }
 
+// Identifiant graphique d'un point calculé
+fn find_calculated(calculator: &Calculator, telemetry_id: &str) -> Option<String> {
+    calculator
+        .set()
+        .outputs()
+        .iter()
+        .find(|o| o.dynawo_id == telemetry_id)
+        .and_then(|o| o.graphical_id.clone())
+}
+
 fn find_from_graphical_id(outputs: &Vec<GameMasterOutput>, graphical_id: &str) -> Option<String> {
     outputs
         .iter()
@@ -1,6 +1,7 @@
 use std::{collections::HashMap, sync::Arc};
 
 use async_nats::ConnectError;
+use tokio::sync::watch;
 
 const ADDRESS: &str = "nats://localhost:4222";
 
@@ -12,6 +13,8 @@ pub type Task = (
 pub struct BrokerStateInner {
     pub channels: HashMap<String, Task>,
     pub client: Arc<async_nats::Client>,
+    /// Client in use, followed by the running connections
+    client_watch: watch::Sender<Arc<async_nats::Client>>,
 }
 
 impl BrokerStateInner {
@@ -20,11 +23,29 @@ impl BrokerStateInner {
         let client = async_nats::connect(ADDRESS).await?;
         log::info!("Connecté à NATS sur {}", ADDRESS);
 
+        let client = Arc::new(client);
         Ok(Self {
             channels: HashMap::default(),
-            client: Arc::new(client),
+            client_watch: watch::Sender::new(client.clone()),
+            client,
         })
     }
+
+    /// Connect to another NATS server, to which the running connections subscribe again
+    pub async fn reconnect(&mut self, address: &str) -> Result<(), ConnectError> {
+        log::debug!("Tentative de connexion à {}", address);
+        let client = async_nats::connect(address).await?;
+        log::info!("Connecté à NATS sur {}", address);
+
+        self.client = Arc::new(client);
+        self.client_watch.send_replace(self.client.clone());
+        Ok(())
+    }
+
+    /// Follow the client replaced by [`BrokerStateInner::reconnect`]
+    pub fn watch_client(&self) -> watch::Receiver<Arc<async_nats::Client>> {
+        self.client_watch.subscribe()
+    }
 }
 
 pub type BrokerState = tokio::sync::Mutex<BrokerStateInner>;
@@ -6,16 +6,30 @@ use tauri_plugin_shell::process::CommandChild;
 mod broker;
 mod database;
 mod powsybl;
+mod sessions;
 mod settings;
 mod shared;
 mod sidecars;
 mod state;
 
+// Exposed for the benchmarks and integration tests
+pub use powsybl::{entities::TelemetryCurves, render_svg, telemetry_codec, ExportFormat};
+
 use broker::{
     commands::*,
     state::{BrokerState, BrokerStateInner},
 };
+use powsybl::alarms::{AlarmEngine, AlarmEngineState};
+use powsybl::balance::BalanceCheckerState;
+use powsybl::cache::{QueryCache, QueryCacheState};
 use powsybl::commands::*;
+use powsybl::historian::{Historian, HistorianState};
+use powsybl::quality::QualityMonitor;
+use powsybl::switching::{SwitchLog, SwitchLogState};
+use sessions::{
+    commands::*,
+    state::{SessionState, TelemetryFeed},
+};
 use settings::commands::*;
 use sidecars::{commands::*, despawn_sidecar, spawn_and_monitor_sidecar};
 use state::AppStateInner;
@@ -48,11 +62,17 @@ pub fn run() {
             set_zmq_subscription,
             get_zmq_url,
             load_config_file,
+            validate_config_file,
             // Loaders
             load_client,
             load_game_master_outputs_in_db,
             load_iidm_file,
             upload_iidm,
+            // Networks
+            upload_network,
+            list_networks,
+            set_active_network,
+            remove_network,
             // Substations
             get_substations,
             get_substation_by_id,
@@ -66,12 +86,83 @@ pub fn run() {
             get_voltage_levels_for_substation,
             search_voltage_levels,
             load_voltage_levels,
+            // Topology
+            load_network_topology,
+            get_topology_summary,
+            get_topology_neighbors,
+            get_topology_shortest_path,
+            get_topology_islands,
+            get_topology_articulation_points,
+            // Comparison
+            compare_networks,
+            export_network_comparison,
+            // Query cache
+            get_query_cache_stats,
+            clear_query_cache,
             // Diagrams
             get_single_line_diagram,
             get_single_line_diagram_metadata,
             get_single_line_diagram_with_metadata,
             subscribe_single_line_diagram,
             unsubscribe_single_line_diagram,
+            get_network_area_diagram,
+            subscribe_network_area_diagram,
+            unsubscribe_network_area_diagram,
+            get_zone_single_line_diagram,
+            subscribe_zone_single_line_diagram,
+            unsubscribe_zone_single_line_diagram,
+            annotate_single_line_diagram,
+            get_single_line_diagram_states,
+            subscribe_single_line_diagram_states,
+            unsubscribe_single_line_diagram_states,
+            export_single_line_diagram,
+            export_single_line_diagrams,
+            // Historian
+            start_historian,
+            stop_historian,
+            get_historian_status,
+            query_historian,
+            query_historian_trend,
+            export_historian,
+            clear_historian,
+            // Alarms
+            start_alarm_engine,
+            stop_alarm_engine,
+            get_alarm_engine_status,
+            get_active_alarms,
+            acknowledge_alarms,
+            get_alarm_history,
+            clear_alarm_history,
+            // Switch log
+            start_switch_log,
+            stop_switch_log,
+            get_switch_log_status,
+            get_switch_events,
+            clear_switch_events,
+            // Calculations
+            load_calculations,
+            get_calculations,
+            clear_calculations,
+            // Balance checks
+            start_balance_checks,
+            stop_balance_checks,
+            get_balance_status,
+            get_balance_report,
+            // Data quality
+            get_quality_summary,
+            get_measurement_quality,
+            get_quality_config,
+            set_quality_config,
+            // Sessions
+            start_session_recording,
+            stop_session_recording,
+            open_session_replay,
+            play_session_replay,
+            pause_session_replay,
+            seek_session_replay,
+            set_session_replay_speed,
+            close_session_replay,
+            get_session_status,
         ])
         .setup(|app| {
             tauri::async_runtime::block_on(async move {
@@ -93,6 +184,23 @@ pub fn run() {
                 let database_state = DatabaseInner::new(&app.handle())
                     .await
                     .expect("Failed to initialize database state");
+
+                // Sidecar query cache, persisted next to the application data
+                app.manage(QueryCacheState::new(QueryCache::new(
+                    database_state.pool.clone(),
+                )));
+                // Telemetry historian, stored in the same database
+                app.manage(HistorianState::new(Historian::new(
+                    database_state.pool.clone(),
+                )));
+                // Limit alarms, with their history in the same database
+                app.manage(AlarmEngineState::new(AlarmEngine::new(
+                    database_state.pool.clone(),
+                )));
+                // Switching sequence of events, in the same database
+                app.manage(SwitchLogState::new(SwitchLog::new(
+                    database_state.pool.clone(),
+                )));
                 app.manage(DatabaseState::new(database_state));
 
                 // Broker state
@@ -100,6 +208,16 @@ pub fn run() {
                     .await
                     .expect("Failed to initialize broker state");
                 app.manage(BrokerState::new(broker_state));
+
+                // Telemetry feed, switched to a replayed session on demand
+                app.manage(TelemetryFeed::default());
+                app.manage(SessionState::default());
+                // Measurement quality, shared by the ZMQ and broker paths
+                app.manage(QualityMonitor::default());
+                // Kirchhoff checks of the measured power flows
+                app.manage(BalanceCheckerState::default());
+                // Watch of the loaded configuration file
+                app.manage(ConfigWatcherState::default());
             });
 
             Ok(())
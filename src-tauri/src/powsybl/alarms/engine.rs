@@ -0,0 +1,115 @@
+use super::super::diagrams::errors::{SubscriptionError, SubscriptionResult};
+use super::super::entities::TelemetryData;
+use super::super::historian::now_millis;
+use super::evaluator::{AlarmEvent, Evaluator};
+use super::limits::Limit;
+use super::store::AlarmStore;
+
+use log::{debug, error, info, warn};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+
+/// Event carrying every raise, clear and acknowledgement of an alarm
+pub const ALARM_EVENT: &str = "alarm-event";
+
+/// Interval at which delayed raises and clears are checked between values
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Control channel of a running engine, swapping its limits without losing its alarms
+pub type LimitsSender = mpsc::UnboundedSender<HashMap<String, Vec<Limit>>>;
+
+/// Sending half of the alarm engine, handed to the telemetry subscription.
+///
+/// Like the historian, frames are dropped and counted rather than queued
+/// without bound when the engine lags behind.
+#[derive(Clone)]
+pub struct AlarmFeed {
+    sender: mpsc::Sender<TelemetryData>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl AlarmFeed {
+    pub fn send(&self, data: TelemetryData) -> SubscriptionResult<()> {
+        match self.sender.try_send(data) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                warn!("Alarm engine queue full, telemetry frame dropped");
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(SubscriptionError::ChannelSendError(
+                "alarm engine stopped".to_string(),
+            )),
+        }
+    }
+}
+
+/// Store the events and push them to the frontend
+pub async fn publish(store: &AlarmStore, app_handle: &AppHandle, events: Vec<AlarmEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    if let Err(e) = store.insert(&events).await {
+        error!("Failed to store {} alarm events: {}", events.len(), e);
+    }
+    for event in events {
+        debug!("Alarm {} {:?}", event.alarm.id, event.kind);
+        if let Err(e) = app_handle.emit(ALARM_EVENT, event) {
+            warn!("Failed to emit alarm event: {}", e);
+        }
+    }
+}
+
+/// Create the feed, the control channel and the task evaluating what the feed
+/// receives, which ends when every feed is dropped
+pub fn spawn_engine(
+    evaluator: Evaluator,
+    store: AlarmStore,
+    app_handle: AppHandle,
+    queue_capacity: usize,
+    dropped: Arc<AtomicU64>,
+) -> (AlarmFeed, LimitsSender, tokio::task::JoinHandle<()>) {
+    let (sender, receiver) = mpsc::channel(queue_capacity.max(1));
+    let (control, control_receiver) = mpsc::unbounded_channel();
+    let feed = AlarmFeed { sender, dropped };
+    let handle = tokio::spawn(run(
+        evaluator,
+        store,
+        app_handle,
+        receiver,
+        control_receiver,
+    ));
+    (feed, control, handle)
+}
+
+async fn run(
+    mut evaluator: Evaluator,
+    store: AlarmStore,
+    app_handle: AppHandle,
+    mut receiver: mpsc::Receiver<TelemetryData>,
+    mut control: mpsc::UnboundedReceiver<HashMap<String, Vec<Limit>>>,
+) {
+    info!("Alarm engine started");
+    let mut tick = tokio::time::interval(TICK_INTERVAL);
+
+    loop {
+        let events = tokio::select! {
+            data = receiver.recv() => match data {
+                Some(data) => evaluator.evaluate(&data, Instant::now(), now_millis()),
+                None => break,
+            },
+            Some(limits) = control.recv() => {
+                info!("Alarm engine limits replaced");
+                evaluator.set_limits(limits, Instant::now(), now_millis())
+            }
+            _ = tick.tick() => evaluator.tick(Instant::now(), now_millis()),
+        };
+        publish(&store, &app_handle, events).await;
+    }
+
+    info!("Alarm engine finished");
+}
@@ -0,0 +1,551 @@
+use super::super::entities::TelemetryData;
+use super::limits::{Limit, LimitDirection};
+use super::AlarmSeverity;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlarmState {
+    /// The limit is violated
+    Active,
+    /// The value is back within the limit, but the alarm is not acknowledged yet
+    Cleared,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alarm {
+    /// Telemetry id and limit name, which identify the alarm across raises
+    pub id: String,
+    pub telemetry_id: String,
+    pub limit: String,
+    pub direction: LimitDirection,
+    pub severity: AlarmSeverity,
+    pub threshold: f64,
+    pub state: AlarmState,
+    pub acknowledged: bool,
+    /// Value and simulation time the alarm was raised at
+    pub value: f64,
+    pub sim_time: u64,
+    pub last_value: f64,
+    pub last_time: u64,
+    /// Wall times, in milliseconds since the Unix epoch
+    pub raised_at: i64,
+    pub cleared_at: Option<i64>,
+    pub acknowledged_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlarmEventKind {
+    Raised,
+    Cleared,
+    Acknowledged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlarmEvent {
+    pub kind: AlarmEventKind,
+    /// The alarm right after the event
+    pub alarm: Alarm,
+    pub value: f64,
+    pub sim_time: u64,
+    pub wall_time: i64,
+}
+
+impl AlarmEvent {
+    fn new(kind: AlarmEventKind, alarm: &Alarm, wall_time: i64) -> Self {
+        Self {
+            kind,
+            alarm: alarm.clone(),
+            value: alarm.last_value,
+            sim_time: alarm.last_time,
+            wall_time,
+        }
+    }
+}
+
+/// Alarms that are active or not acknowledged yet, by id
+pub type AlarmTable = Arc<Mutex<BTreeMap<String, Alarm>>>;
+
+// A limit crossed, raising or clearing its alarm once the delay is over
+struct Pending {
+    telemetry_id: String,
+    limit: usize,
+    since: Instant,
+    value: f64,
+    sim_time: u64,
+}
+
+/// Checks the telemetry values against their limits and maintains the alarm table
+pub struct Evaluator {
+    limits: HashMap<String, Vec<Limit>>,
+    pending: HashMap<String, Pending>,
+    table: AlarmTable,
+}
+
+impl Evaluator {
+    pub fn new(limits: HashMap<String, Vec<Limit>>, table: AlarmTable) -> Self {
+        Self {
+            limits,
+            pending: HashMap::new(),
+            table,
+        }
+    }
+
+    pub fn evaluate(
+        &mut self,
+        data: &TelemetryData,
+        now: Instant,
+        wall_time: i64,
+    ) -> Vec<AlarmEvent> {
+        let mut ready = Vec::new();
+
+        for (telemetry_id, &value) in &data.values {
+            let Some(limits) = self.limits.get(telemetry_id) else {
+                continue;
+            };
+            if value.is_nan() {
+                continue;
+            }
+
+            for (index, limit) in limits.iter().enumerate() {
+                let alarm_id = alarm_id(telemetry_id, limit);
+                let active = match self.table.lock() {
+                    Ok(mut table) => match table.get_mut(&alarm_id) {
+                        Some(alarm) if alarm.state == AlarmState::Active => {
+                            alarm.last_value = value;
+                            alarm.last_time = data.time;
+                            true
+                        }
+                        _ => false,
+                    },
+                    Err(_) => continue,
+                };
+
+                let crossed = if active {
+                    limit.restored(value)
+                } else {
+                    limit.violated(value)
+                };
+                if !crossed {
+                    self.pending.remove(&alarm_id);
+                    continue;
+                }
+
+                let pending = self
+                    .pending
+                    .entry(alarm_id.clone())
+                    .or_insert_with(|| Pending {
+                        telemetry_id: telemetry_id.clone(),
+                        limit: index,
+                        since: now,
+                        value,
+                        sim_time: data.time,
+                    });
+                pending.value = value;
+                pending.sim_time = data.time;
+
+                let delay = if active {
+                    limit.clear_delay
+                } else {
+                    limit.delay
+                };
+                if now.duration_since(pending.since) >= delay {
+                    ready.push(alarm_id);
+                }
+            }
+        }
+
+        ready
+            .iter()
+            .filter_map(|alarm_id| self.transition(alarm_id, wall_time))
+            .collect()
+    }
+
+    /// Raise or clear the alarms whose delay is over, without waiting for new values
+    pub fn tick(&mut self, now: Instant, wall_time: i64) -> Vec<AlarmEvent> {
+        let ready: Vec<String> = match self.table.lock() {
+            Ok(table) => self
+                .pending
+                .iter()
+                .filter(|(alarm_id, pending)| {
+                    let limit = &self.limits[&pending.telemetry_id][pending.limit];
+                    let active = table
+                        .get(*alarm_id)
+                        .is_some_and(|alarm| alarm.state == AlarmState::Active);
+                    let delay = if active {
+                        limit.clear_delay
+                    } else {
+                        limit.delay
+                    };
+                    now.duration_since(pending.since) >= delay
+                })
+                .map(|(alarm_id, _)| alarm_id.clone())
+                .collect(),
+            Err(_) => return Vec::new(),
+        };
+
+        ready
+            .iter()
+            .filter_map(|alarm_id| self.transition(alarm_id, wall_time))
+            .collect()
+    }
+
+    /// Check new limits from now on, keeping the alarms whose limit still exists
+    /// and clearing the active ones whose limit was removed. Active alarms take the
+    /// threshold of their new limit, and clear after its delay if their last value
+    /// is within it.
+    pub fn set_limits(
+        &mut self,
+        limits: HashMap<String, Vec<Limit>>,
+        now: Instant,
+        wall_time: i64,
+    ) -> Vec<AlarmEvent> {
+        self.limits = limits;
+
+        // Pending crossings follow their limit to its new position
+        let limits = &self.limits;
+        self.pending.retain(|id, pending| {
+            let index = limits.get(&pending.telemetry_id).and_then(|limits| {
+                limits
+                    .iter()
+                    .position(|limit| alarm_id(&pending.telemetry_id, limit) == *id)
+            });
+            match index {
+                Some(index) => {
+                    pending.limit = index;
+                    true
+                }
+                None => false,
+            }
+        });
+
+        let Ok(mut table) = self.table.lock() else {
+            return Vec::new();
+        };
+        let removed: Vec<String> = table
+            .values()
+            .filter(|alarm| {
+                !limits
+                    .get(&alarm.telemetry_id)
+                    .is_some_and(|limits| limits.iter().any(|limit| limit.name == alarm.limit))
+            })
+            .map(|alarm| alarm.id.clone())
+            .collect();
+
+        for alarm in table.values_mut() {
+            if alarm.state != AlarmState::Active {
+                continue;
+            }
+            let Some((index, limit)) = limits.get(&alarm.telemetry_id).and_then(|limits| {
+                limits
+                    .iter()
+                    .enumerate()
+                    .find(|(_, limit)| limit.name == alarm.limit)
+            }) else {
+                continue;
+            };
+            alarm.direction = limit.direction;
+            alarm.severity = limit.severity;
+            alarm.threshold = limit.threshold;
+
+            if limit.restored(alarm.last_value) {
+                self.pending
+                    .entry(alarm.id.clone())
+                    .or_insert_with(|| Pending {
+                        telemetry_id: alarm.telemetry_id.clone(),
+                        limit: index,
+                        since: now,
+                        value: alarm.last_value,
+                        sim_time: alarm.last_time,
+                    });
+            } else {
+                self.pending.remove(&alarm.id);
+            }
+        }
+
+        let mut events = Vec::new();
+        for alarm_id in removed {
+            let Some(alarm) = table
+                .get_mut(&alarm_id)
+                .filter(|alarm| alarm.state == AlarmState::Active)
+            else {
+                continue;
+            };
+            alarm.state = AlarmState::Cleared;
+            alarm.cleared_at = Some(wall_time);
+            events.push(AlarmEvent::new(AlarmEventKind::Cleared, alarm, wall_time));
+
+            if alarm.acknowledged {
+                table.remove(&alarm_id);
+            }
+        }
+        events
+    }
+
+    fn transition(&mut self, alarm_id: &str, wall_time: i64) -> Option<AlarmEvent> {
+        let pending = self.pending.remove(alarm_id)?;
+        let limit = &self.limits[&pending.telemetry_id][pending.limit];
+        let mut table = self.table.lock().ok()?;
+
+        if let Some(alarm) = table
+            .get_mut(alarm_id)
+            .filter(|alarm| alarm.state == AlarmState::Active)
+        {
+            alarm.state = AlarmState::Cleared;
+            alarm.cleared_at = Some(wall_time);
+            alarm.last_value = pending.value;
+            alarm.last_time = pending.sim_time;
+            let event = AlarmEvent::new(AlarmEventKind::Cleared, alarm, wall_time);
+
+            // Acknowledged alarms leave the table once cleared
+            if alarm.acknowledged {
+                table.remove(alarm_id);
+            }
+            return Some(event);
+        }
+
+        // A cleared alarm not acknowledged yet is replaced by the new one
+        let alarm = Alarm {
+            id: alarm_id.to_string(),
+            telemetry_id: pending.telemetry_id,
+            limit: limit.name.clone(),
+            direction: limit.direction,
+            severity: limit.severity,
+            threshold: limit.threshold,
+            state: AlarmState::Active,
+            acknowledged: false,
+            value: pending.value,
+            sim_time: pending.sim_time,
+            last_value: pending.value,
+            last_time: pending.sim_time,
+            raised_at: wall_time,
+            cleared_at: None,
+            acknowledged_at: None,
+        };
+        let event = AlarmEvent::new(AlarmEventKind::Raised, &alarm, wall_time);
+        table.insert(alarm_id.to_string(), alarm);
+        Some(event)
+    }
+}
+
+/// Acknowledge the given alarms, removing the cleared ones from the table
+pub fn acknowledge(table: &AlarmTable, alarm_ids: &[String], wall_time: i64) -> Vec<AlarmEvent> {
+    let Ok(mut table) = table.lock() else {
+        return Vec::new();
+    };
+
+    let mut events = Vec::new();
+    for alarm_id in alarm_ids {
+        let Some(alarm) = table.get_mut(alarm_id).filter(|alarm| !alarm.acknowledged) else {
+            continue;
+        };
+        alarm.acknowledged = true;
+        alarm.acknowledged_at = Some(wall_time);
+        events.push(AlarmEvent::new(
+            AlarmEventKind::Acknowledged,
+            alarm,
+            wall_time,
+        ));
+
+        if alarm.state == AlarmState::Cleared {
+            table.remove(alarm_id);
+        }
+    }
+    events
+}
+
+fn alarm_id(telemetry_id: &str, limit: &Limit) -> String {
+    format!("{}/{}", telemetry_id, limit.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const ID: &str = "VL1_BUS1_U";
+
+    fn limit(threshold: f64) -> Limit {
+        Limit {
+            name: "output:0:high".to_string(),
+            direction: LimitDirection::High,
+            threshold,
+            hysteresis: 5.0,
+            delay: Duration::from_secs(10),
+            clear_delay: Duration::from_secs(2),
+            severity: AlarmSeverity::Alarm,
+        }
+    }
+
+    fn evaluator(limit: Limit) -> Evaluator {
+        Evaluator::new(
+            HashMap::from([(ID.to_string(), vec![limit])]),
+            AlarmTable::default(),
+        )
+    }
+
+    fn data(time: u64, value: f64) -> TelemetryData {
+        TelemetryData {
+            values: HashMap::from([(ID.to_string(), value)]),
+            time,
+        }
+    }
+
+    fn kinds(events: &[AlarmEvent]) -> Vec<AlarmEventKind> {
+        events.iter().map(|event| event.kind).collect()
+    }
+
+    fn stored_alarm(evaluator: &Evaluator) -> Option<Alarm> {
+        let table = evaluator.table.lock().unwrap();
+        table.values().next().cloned()
+    }
+
+    // Raise the alarm at `start`, with a value of 110
+    fn raise(evaluator: &mut Evaluator, start: Instant) {
+        assert!(evaluator.evaluate(&data(0, 110.0), start, 0).is_empty());
+        let events = evaluator.evaluate(&data(10, 110.0), start + Duration::from_secs(10), 10);
+        assert_eq!(kinds(&events), [AlarmEventKind::Raised]);
+    }
+
+    #[test]
+    fn raises_once_the_delay_is_over() {
+        let start = Instant::now();
+        let mut evaluator = evaluator(limit(100.0));
+
+        assert!(evaluator.evaluate(&data(0, 110.0), start, 0).is_empty());
+        let events = evaluator.evaluate(&data(5, 120.0), start + Duration::from_secs(5), 5);
+        assert!(events.is_empty());
+
+        let events = evaluator.tick(start + Duration::from_secs(10), 10);
+        assert_eq!(kinds(&events), [AlarmEventKind::Raised]);
+        let alarm = stored_alarm(&evaluator).unwrap();
+        assert_eq!(alarm.id, format!("{}/output:0:high", ID));
+        assert_eq!(alarm.state, AlarmState::Active);
+        // Raised with the last value of the crossing
+        assert_eq!((alarm.value, alarm.sim_time), (120.0, 5));
+    }
+
+    #[test]
+    fn forgets_a_crossing_shorter_than_the_delay() {
+        let start = Instant::now();
+        let mut evaluator = evaluator(limit(100.0));
+
+        evaluator.evaluate(&data(0, 110.0), start, 0);
+        evaluator.evaluate(&data(5, 90.0), start + Duration::from_secs(5), 5);
+        evaluator.evaluate(&data(8, 110.0), start + Duration::from_secs(8), 8);
+
+        // The delay starts over with the second crossing
+        assert!(evaluator
+            .tick(start + Duration::from_secs(12), 12)
+            .is_empty());
+        let events = evaluator.tick(start + Duration::from_secs(18), 18);
+        assert_eq!(kinds(&events), [AlarmEventKind::Raised]);
+    }
+
+    #[test]
+    fn clears_past_the_hysteresis_after_the_clear_delay() {
+        let start = Instant::now();
+        let mut evaluator = evaluator(limit(100.0));
+        raise(&mut evaluator, start);
+
+        // Below the threshold, but within the hysteresis band
+        let at = |secs| start + Duration::from_secs(secs);
+        assert!(evaluator.evaluate(&data(20, 97.0), at(20), 20).is_empty());
+        assert!(evaluator.tick(at(30), 30).is_empty());
+
+        assert!(evaluator.evaluate(&data(31, 94.0), at(31), 31).is_empty());
+        assert!(evaluator.evaluate(&data(32, 94.0), at(32), 32).is_empty());
+        let events = evaluator.evaluate(&data(33, 93.0), at(33), 33);
+        assert_eq!(kinds(&events), [AlarmEventKind::Cleared]);
+
+        // Kept until acknowledged
+        let alarm = stored_alarm(&evaluator).unwrap();
+        assert_eq!(alarm.state, AlarmState::Cleared);
+        assert_eq!((alarm.last_value, alarm.last_time), (93.0, 33));
+        assert_eq!(alarm.cleared_at, Some(33));
+
+        let events = acknowledge(&evaluator.table, &[alarm.id], 40);
+        assert_eq!(kinds(&events), [AlarmEventKind::Acknowledged]);
+        assert!(stored_alarm(&evaluator).is_none());
+    }
+
+    #[test]
+    fn removes_an_acknowledged_alarm_once_cleared() {
+        let start = Instant::now();
+        let mut evaluator = evaluator(limit(100.0));
+        raise(&mut evaluator, start);
+
+        let alarm_ids = [stored_alarm(&evaluator).unwrap().id];
+        let events = acknowledge(&evaluator.table, &alarm_ids, 12);
+        assert_eq!(kinds(&events), [AlarmEventKind::Acknowledged]);
+        assert!(events[0].alarm.acknowledged);
+        // Acknowledged once only
+        assert!(acknowledge(&evaluator.table, &alarm_ids, 13).is_empty());
+        assert_eq!(stored_alarm(&evaluator).unwrap().state, AlarmState::Active);
+
+        evaluator.evaluate(&data(20, 90.0), start + Duration::from_secs(20), 20);
+        let events = evaluator.tick(start + Duration::from_secs(22), 22);
+        assert_eq!(kinds(&events), [AlarmEventKind::Cleared]);
+        assert!(stored_alarm(&evaluator).is_none());
+    }
+
+    #[test]
+    fn updates_the_threshold_of_active_alarms() {
+        let start = Instant::now();
+        let mut evaluator = evaluator(limit(100.0));
+        raise(&mut evaluator, start);
+
+        let mut lower = limit(90.0);
+        lower.severity = AlarmSeverity::Critical;
+        let limits = HashMap::from([(ID.to_string(), vec![lower])]);
+        let events = evaluator.set_limits(limits, start + Duration::from_secs(11), 11);
+
+        assert!(events.is_empty());
+        let alarm = stored_alarm(&evaluator).unwrap();
+        assert_eq!(alarm.state, AlarmState::Active);
+        assert_eq!(alarm.threshold, 90.0);
+        assert_eq!(alarm.severity, AlarmSeverity::Critical);
+    }
+
+    #[test]
+    fn clears_active_alarms_within_their_new_limit() {
+        let start = Instant::now();
+        let mut evaluator = evaluator(limit(100.0));
+        raise(&mut evaluator, start);
+
+        let limits = HashMap::from([(ID.to_string(), vec![limit(200.0)])]);
+        let events = evaluator.set_limits(limits, start + Duration::from_secs(11), 11);
+        assert!(events.is_empty());
+
+        assert!(evaluator
+            .tick(start + Duration::from_secs(12), 12)
+            .is_empty());
+        let events = evaluator.tick(start + Duration::from_secs(13), 13);
+        assert_eq!(kinds(&events), [AlarmEventKind::Cleared]);
+        assert_eq!(events[0].alarm.threshold, 200.0);
+    }
+
+    #[test]
+    fn clears_the_alarms_of_removed_limits() {
+        let start = Instant::now();
+        let mut evaluator = evaluator(limit(100.0));
+        raise(&mut evaluator, start);
+        let alarm_id = stored_alarm(&evaluator).unwrap().id;
+        acknowledge(&evaluator.table, &[alarm_id], 12);
+
+        let events = evaluator.set_limits(HashMap::new(), start + Duration::from_secs(13), 13);
+        assert_eq!(kinds(&events), [AlarmEventKind::Cleared]);
+        assert!(stored_alarm(&evaluator).is_none());
+        // Values of a telemetry id without limits are ignored
+        let events = evaluator.evaluate(&data(20, 500.0), start + Duration::from_secs(20), 20);
+        assert!(events.is_empty());
+        assert!(evaluator
+            .tick(start + Duration::from_secs(60), 60)
+            .is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+use super::super::comparison::{NetworkElement, NetworkElements};
+use super::{AlarmConfig, AlarmSeverity, LimitConfig, LimitScope};
+use crate::shared::entities::dynawo::GameMasterOutput;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitDirection {
+    High,
+    Low,
+}
+
+impl LimitDirection {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LimitDirection::High => "high",
+            LimitDirection::Low => "low",
+        }
+    }
+}
+
+/// A threshold checked against the values of one telemetry id
+#[derive(Debug, Clone)]
+pub struct Limit {
+    /// Where the limit comes from, unique among the limits of a telemetry id
+    pub name: String,
+    pub direction: LimitDirection,
+    pub threshold: f64,
+    pub hysteresis: f64,
+    pub delay: Duration,
+    pub clear_delay: Duration,
+    pub severity: AlarmSeverity,
+}
+
+impl Limit {
+    pub fn violated(&self, value: f64) -> bool {
+        match self.direction {
+            LimitDirection::High => value > self.threshold,
+            LimitDirection::Low => value < self.threshold,
+        }
+    }
+
+    /// Whether the value is back within the limit, past the hysteresis band
+    pub fn restored(&self, value: f64) -> bool {
+        match self.direction {
+            LimitDirection::High => value < self.threshold - self.hysteresis,
+            LimitDirection::Low => value > self.threshold + self.hysteresis,
+        }
+    }
+}
+
+/// Limits of every telemetry id.
+///
+/// Limits configured for an output replace the ones of its unit class; the
+/// operational limits of the network, when given, are checked in addition.
+pub fn resolve_limits(
+    config: &AlarmConfig,
+    outputs: &[GameMasterOutput],
+    network: Option<&NetworkElements>,
+) -> HashMap<String, Vec<Limit>> {
+    let mut limits: HashMap<String, Vec<Limit>> = HashMap::new();
+
+    for (index, limit) in config.limits.iter().enumerate() {
+        if let LimitScope::Output(id) = &limit.scope {
+            limits
+                .entry(id.clone())
+                .or_default()
+                .extend(configured_limits(limit, "output", index));
+        }
+    }
+    for (index, limit) in config.limits.iter().enumerate() {
+        let LimitScope::Unit(unit) = &limit.scope else {
+            continue;
+        };
+        for output in outputs {
+            if output.unit.as_ref() != Some(unit) || has_output_limit(config, &output.dynawo_id) {
+                continue;
+            }
+            limits
+                .entry(output.dynawo_id.clone())
+                .or_default()
+                .extend(configured_limits(limit, "unit", index));
+        }
+    }
+
+    if let Some(network) = network {
+        let elements: HashMap<&str, &NetworkElement> = network
+            .elements
+            .iter()
+            .map(|element| (element.id.as_str(), element))
+            .collect();
+        for output in outputs {
+            let found = network_limits(config, output, &elements);
+            if !found.is_empty() {
+                limits
+                    .entry(output.dynawo_id.clone())
+                    .or_default()
+                    .extend(found);
+            }
+        }
+    }
+
+    limits
+}
+
+fn has_output_limit(config: &AlarmConfig, id: &str) -> bool {
+    config
+        .limits
+        .iter()
+        .any(|limit| matches!(&limit.scope, LimitScope::Output(output) if output == id))
+}
+
+// Limits are named after their configuration entry, so several entries for the
+// same output or unit raise distinct alarms
+fn configured_limits(config: &LimitConfig, source: &str, index: usize) -> Vec<Limit> {
+    [
+        (LimitDirection::High, config.high),
+        (LimitDirection::Low, config.low),
+    ]
+    .into_iter()
+    .filter_map(|(direction, threshold)| {
+        Some(Limit {
+            name: format!("{}{}_{}", source, index + 1, direction.as_str()),
+            direction,
+            threshold: threshold?,
+            hysteresis: config.hysteresis.abs(),
+            delay: Duration::from_millis(config.delay_ms),
+            clear_delay: Duration::from_millis(config.clear_delay_ms),
+            severity: config.severity,
+        })
+    })
+    .collect()
+}
+
+fn network_limit(
+    config: &AlarmConfig,
+    name: String,
+    direction: LimitDirection,
+    threshold: f64,
+    severity: AlarmSeverity,
+) -> Limit {
+    Limit {
+        name,
+        direction,
+        threshold,
+        hysteresis: (threshold * config.network_hysteresis).abs(),
+        delay: Duration::from_millis(config.network_delay_ms),
+        clear_delay: Duration::from_millis(config.network_delay_ms),
+        severity,
+    }
+}
+
+/// Operational limits of the equipment an output measures: the current limits of
+/// a branch side, or the voltage limits of a voltage level
+fn network_limits(
+    config: &AlarmConfig,
+    output: &GameMasterOutput,
+    elements: &HashMap<&str, &NetworkElement>,
+) -> Vec<Limit> {
+    let Some(element) = output
+        .equipment_id
+        .as_deref()
+        .and_then(|id| elements.get(id))
+    else {
+        return Vec::new();
+    };
+
+    match output.component_type.as_deref() {
+        Some("ARROW_CURRENT") => current_limits(config, output, element),
+        Some("BUS_VOLTAGE") => {
+            let voltage_level = match element.element_type.as_str() {
+                "VOLTAGE_LEVEL" => Some(*element),
+                _ => element
+                    .attributes
+                    .get("voltage_level_id")
+                    .and_then(|id| id.as_str())
+                    .and_then(|id| elements.get(id).copied()),
+            };
+            let Some(voltage_level) = voltage_level else {
+                return Vec::new();
+            };
+
+            [
+                ("high_voltage_limit", LimitDirection::High),
+                ("low_voltage_limit", LimitDirection::Low),
+            ]
+            .into_iter()
+            .filter_map(|(attribute, direction)| {
+                let threshold = voltage_level.attributes.get(attribute)?.as_f64()?;
+                // Voltage levels without limits report zero
+                (threshold > 0.0).then(|| {
+                    network_limit(
+                        config,
+                        format!("network_voltage_{}", direction.as_str()),
+                        direction,
+                        threshold,
+                        AlarmSeverity::Alarm,
+                    )
+                })
+            })
+            .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+// Current limits are named `limit_current_<side>_<permanent|duration>` by the
+// sidecar; temporary limits, above the permanent one, are critical
+fn current_limits(
+    config: &AlarmConfig,
+    output: &GameMasterOutput,
+    element: &NetworkElement,
+) -> Vec<Limit> {
+    let sides: &[&str] = match output
+        .side
+        .as_deref()
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("one" | "1") => &["one"],
+        Some("two" | "2") => &["two"],
+        _ => &["one", "two"],
+    };
+    let scale = match output.unit.as_deref() {
+        Some("kA") => 1e-3,
+        _ => 1.0,
+    };
+
+    // The lowest limit of the sides, per duration
+    let mut thresholds: HashMap<&str, f64> = HashMap::new();
+    for (attribute, value) in &element.attributes {
+        for side in sides {
+            let Some(duration) = attribute
+                .strip_prefix("limit_current_")
+                .and_then(|rest| rest.strip_prefix(side))
+                .and_then(|rest| rest.strip_prefix('_'))
+            else {
+                continue;
+            };
+            if let Some(value) = value.as_f64() {
+                let threshold = thresholds.entry(duration).or_insert(f64::INFINITY);
+                *threshold = threshold.min(value * scale);
+            }
+        }
+    }
+
+    thresholds
+        .into_iter()
+        .filter(|(_, threshold)| threshold.is_finite())
+        .map(|(duration, threshold)| {
+            let severity = match duration {
+                "permanent" => AlarmSeverity::Alarm,
+                _ => AlarmSeverity::Critical,
+            };
+            network_limit(
+                config,
+                format!("network_current_{}", duration),
+                LimitDirection::High,
+                threshold,
+                severity,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,391 @@
+use super::comparison::fetch_network_elements;
+use super::diagrams::sld_subscriptions::{
+    game_master_outputs, subscribe_diagram, unsubscribe_diagram,
+};
+use super::diagrams::TelemetrySink;
+use super::errors::{PowsyblError, PowsyblResult};
+use super::historian::now_millis;
+use super::resolve_network_id;
+
+use crate::{settings::get_setting, state::AppState};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::task::JoinHandle;
+
+mod engine;
+mod evaluator;
+mod limits;
+mod store;
+
+pub use engine::{AlarmFeed, ALARM_EVENT};
+pub use evaluator::{Alarm, AlarmEvent, AlarmEventKind, AlarmState};
+pub use limits::LimitDirection;
+pub use store::{AlarmHistoryEntry, AlarmHistoryQuery};
+
+use engine::LimitsSender;
+use evaluator::{AlarmTable, Evaluator};
+use limits::Limit;
+use store::AlarmStore;
+
+/// Subscription id of the alarm feed, alongside the diagram ones
+const ALARM_SUBSCRIPTION: &str = "alarms";
+
+/// Time the engine is given to evaluate its last frames when stopped
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlarmSeverity {
+    Warning,
+    #[default]
+    Alarm,
+    Critical,
+}
+
+/// Outputs a configured limit applies to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitScope {
+    /// A single game master output, by its telemetry id
+    Output(String),
+    /// Every output of a unit, such as `kV` or `MW`
+    Unit(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LimitConfig {
+    pub scope: LimitScope,
+    #[serde(default)]
+    pub high: Option<f64>,
+    #[serde(default)]
+    pub low: Option<f64>,
+    /// Margin the value must come back by before the alarm clears
+    #[serde(default)]
+    pub hysteresis: f64,
+    /// Time the limit must stay violated before the alarm is raised
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Time the value must stay back within the limit before the alarm clears
+    #[serde(default)]
+    pub clear_delay_ms: u64,
+    #[serde(default)]
+    pub severity: AlarmSeverity,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlarmConfig {
+    pub limits: Vec<LimitConfig>,
+    /// Also check the operational limits of the network: the current limits of
+    /// the branches and the voltage limits of the voltage levels
+    pub network_limits: bool,
+    /// Hysteresis of the network limits, as a fraction of the limit
+    pub network_hysteresis: f64,
+    pub network_delay_ms: u64,
+    /// Frames waiting for the engine before new ones are dropped
+    pub queue_capacity: usize,
+}
+
+impl Default for AlarmConfig {
+    fn default() -> Self {
+        Self {
+            limits: Vec::new(),
+            network_limits: true,
+            network_hysteresis: 0.02,
+            network_delay_ms: 0,
+            queue_capacity: 1_024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlarmEngineStatus {
+    pub running: bool,
+    pub config: AlarmConfig,
+    /// Telemetry ids with at least one limit, and their limit count
+    pub monitored_ids: usize,
+    pub limits: usize,
+    pub active: usize,
+    pub unacknowledged: usize,
+    pub frames_dropped: u64,
+}
+
+/// Limit checks of every telemetry value received from the ZMQ feed, with the
+/// list of the alarms raised and their history in SQLite
+pub struct AlarmEngine {
+    pool: Pool<Sqlite>,
+    config: AlarmConfig,
+    table: AlarmTable,
+    monitored_ids: usize,
+    limits: usize,
+    dropped: Arc<AtomicU64>,
+    engine: Option<JoinHandle<()>>,
+    control: Option<LimitsSender>,
+}
+
+pub type AlarmEngineState = tokio::sync::Mutex<AlarmEngine>;
+
+impl AlarmEngine {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self {
+            pool,
+            config: AlarmConfig::default(),
+            table: AlarmTable::default(),
+            monitored_ids: 0,
+            limits: 0,
+            dropped: Arc::new(AtomicU64::new(0)),
+            engine: None,
+            control: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.engine
+            .as_ref()
+            .is_some_and(|engine| !engine.is_finished())
+    }
+
+    /// Start an engine with new limits, from an empty alarm list, and return its feed
+    fn start(
+        &mut self,
+        config: AlarmConfig,
+        limits: HashMap<String, Vec<Limit>>,
+        app_handle: AppHandle,
+    ) -> AlarmFeed {
+        self.monitored_ids = limits.len();
+        self.limits = limits.values().map(Vec::len).sum();
+        self.table = AlarmTable::default();
+        self.dropped = Arc::new(AtomicU64::new(0));
+
+        let (feed, control, engine) = engine::spawn_engine(
+            Evaluator::new(limits, self.table.clone()),
+            AlarmStore::new(self.pool.clone()),
+            app_handle,
+            config.queue_capacity,
+            self.dropped.clone(),
+        );
+        self.config = config;
+        self.engine = Some(engine);
+        self.control = Some(control);
+        feed
+    }
+
+    /// Swap the limits of the running engine, which keeps its alarms, and return
+    /// whether it was running
+    fn set_limits(&mut self, config: AlarmConfig, limits: HashMap<String, Vec<Limit>>) -> bool {
+        let monitored_ids = limits.len();
+        let count = limits.values().map(Vec::len).sum();
+        let sent = self.is_running()
+            && self
+                .control
+                .as_ref()
+                .is_some_and(|control| control.send(limits).is_ok());
+        if sent {
+            self.monitored_ids = monitored_ids;
+            self.limits = count;
+            self.config = config;
+        }
+        sent
+    }
+
+    async fn stop(&mut self) {
+        self.control = None;
+        let Some(engine) = self.engine.take() else {
+            return;
+        };
+        match tokio::time::timeout(STOP_TIMEOUT, engine).await {
+            Ok(Ok(())) => info!("Alarm engine stopped"),
+            Ok(Err(e)) => warn!("Alarm engine failed: {}", e),
+            Err(_) => warn!("Timeout while waiting for the alarm engine"),
+        }
+    }
+
+    fn alarms(&self) -> PowsyblResult<Vec<Alarm>> {
+        let table = self.table.lock().map_err(|_| PowsyblError::LockError)?;
+        Ok(table.values().cloned().collect())
+    }
+
+    pub fn status(&self) -> PowsyblResult<AlarmEngineStatus> {
+        let alarms = self.alarms()?;
+        Ok(AlarmEngineStatus {
+            running: self.is_running(),
+            config: self.config.clone(),
+            monitored_ids: self.monitored_ids,
+            limits: self.limits,
+            active: alarms
+                .iter()
+                .filter(|alarm| alarm.state == AlarmState::Active)
+                .count(),
+            unacknowledged: alarms.iter().filter(|alarm| !alarm.acknowledged).count(),
+            frames_dropped: self.dropped.load(Ordering::Relaxed),
+        })
+    }
+}
+
+// Limits of the loaded configuration file, if any
+async fn configured_limits(pool: &Pool<Sqlite>) -> PowsyblResult<Vec<LimitConfig>> {
+    let config = get_setting(pool, "config")
+        .await
+        .map_err(|e| PowsyblError::AlarmError(e.to_string()))?;
+    Ok(config
+        .and_then(|config| serde_json::from_value(config["limits"].clone()).ok())
+        .unwrap_or_default())
+}
+
+// Limits of every telemetry id, with the operational limits of the network when
+// enabled and available
+async fn monitored_limits(
+    state: &State<'_, AppState>,
+    config: &AlarmConfig,
+    network_id: Option<String>,
+) -> PowsyblResult<HashMap<String, Vec<Limit>>> {
+    let outputs = game_master_outputs(state)?;
+
+    // Alarms on the configured limits are still raised without the sidecar
+    let mut network = None;
+    if config.network_limits {
+        let network_id = resolve_network_id(state, network_id)?;
+        match fetch_network_elements(None, network_id.as_deref()).await {
+            Ok(elements) => network = Some(elements),
+            Err(e) => warn!("Network limits unavailable: {}", e),
+        }
+    }
+    Ok(limits::resolve_limits(config, &outputs, network.as_ref()))
+}
+
+/// Start checking the telemetry feed against the configured limits and, unless
+/// disabled, the operational limits of the network, restarting the engine if it runs.
+///
+/// Without a config, the limits of the loaded configuration file are checked.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn start_alarm_engine(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    alarms: State<'_, AlarmEngineState>,
+    config: Option<AlarmConfig>,
+    network_id: Option<String>,
+) -> PowsyblResult<AlarmEngineStatus> {
+    let config = match config {
+        Some(config) => config,
+        None => {
+            let pool = alarms.lock().await.pool.clone();
+            AlarmConfig {
+                limits: configured_limits(&pool).await?,
+                ..AlarmConfig::default()
+            }
+        }
+    };
+    let limits = monitored_limits(&state, &config, network_id).await?;
+
+    let mut alarms = alarms.lock().await;
+    unsubscribe_diagram(&state, ALARM_SUBSCRIPTION);
+    alarms.stop().await;
+
+    let feed = alarms.start(config, limits, app_handle.clone());
+    subscribe_diagram(
+        app_handle,
+        &state,
+        ALARM_SUBSCRIPTION.to_string(),
+        TelemetrySink::Alarms(feed),
+    )
+    .await?;
+
+    let status = alarms.status()?;
+    info!(
+        "Alarm engine started with {} limits on {} telemetry ids",
+        status.limits, status.monitored_ids
+    );
+    Ok(status)
+}
+
+/// Give a running engine new configured limits, keeping its other settings and
+/// the alarms whose limit still exists, and return whether it was running
+pub async fn reload_alarm_limits(
+    app_handle: AppHandle,
+    limits: Vec<LimitConfig>,
+) -> PowsyblResult<bool> {
+    let alarms = app_handle.state::<AlarmEngineState>();
+    let config = {
+        let alarms = alarms.lock().await;
+        if !alarms.is_running() {
+            return Ok(false);
+        }
+        AlarmConfig {
+            limits,
+            ..alarms.config.clone()
+        }
+    };
+
+    let limits = monitored_limits(&app_handle.state(), &config, None).await?;
+    let mut alarms = alarms.lock().await;
+    Ok(alarms.set_limits(config, limits))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn stop_alarm_engine(
+    state: State<'_, AppState>,
+    alarms: State<'_, AlarmEngineState>,
+) -> PowsyblResult<AlarmEngineStatus> {
+    let mut alarms = alarms.lock().await;
+
+    // Dropping the subscription closes the queue of the engine
+    unsubscribe_diagram(&state, ALARM_SUBSCRIPTION);
+    alarms.stop().await;
+
+    alarms.status()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_alarm_engine_status(
+    alarms: State<'_, AlarmEngineState>,
+) -> PowsyblResult<AlarmEngineStatus> {
+    alarms.lock().await.status()
+}
+
+/// Alarms that are active or not acknowledged yet
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_active_alarms(alarms: State<'_, AlarmEngineState>) -> PowsyblResult<Vec<Alarm>> {
+    alarms.lock().await.alarms()
+}
+
+/// Acknowledge alarms by id, returning the alarm list after it.
+///
+/// Cleared alarms leave the list once acknowledged; active ones stay until cleared.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn acknowledge_alarms(
+    app_handle: AppHandle,
+    alarms: State<'_, AlarmEngineState>,
+    alarm_ids: Vec<String>,
+) -> PowsyblResult<Vec<Alarm>> {
+    let alarms = alarms.lock().await;
+
+    let events = evaluator::acknowledge(&alarms.table, &alarm_ids, now_millis());
+    engine::publish(&AlarmStore::new(alarms.pool.clone()), &app_handle, events).await;
+
+    alarms.alarms()
+}
+
+/// Stored alarm events, most recent first
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_alarm_history(
+    alarms: State<'_, AlarmEngineState>,
+    query: Option<AlarmHistoryQuery>,
+) -> PowsyblResult<Vec<AlarmHistoryEntry>> {
+    let pool = alarms.lock().await.pool.clone();
+    AlarmStore::new(pool)
+        .history(&query.unwrap_or_default())
+        .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn clear_alarm_history(alarms: State<'_, AlarmEngineState>) -> PowsyblResult<u64> {
+    let pool = alarms.lock().await.pool.clone();
+    AlarmStore::new(pool).clear().await
+}
@@ -0,0 +1,119 @@
+use super::super::errors::PowsyblResult;
+use super::evaluator::AlarmEvent;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, QueryBuilder, Sqlite};
+
+/// Events per insert statement, within the 32766 bound parameters of SQLite
+const EVENT_ROWS: usize = 2_000;
+
+/// A stored alarm event
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AlarmHistoryEntry {
+    pub id: i64,
+    pub alarm_id: String,
+    pub telemetry_id: String,
+    #[sqlx(rename = "limit_name")]
+    pub limit: String,
+    pub direction: String,
+    pub severity: String,
+    pub kind: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub sim_time: i64,
+    pub wall_time: i64,
+}
+
+/// Filter of the alarm history; wall times are in milliseconds since the Unix epoch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlarmHistoryQuery {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub alarm_id: Option<String>,
+    pub telemetry_id: Option<String>,
+    /// Most recent events returned, 1000 by default
+    pub limit: Option<u32>,
+}
+
+// Serialized name of a unit enum variant, as stored
+fn name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+pub struct AlarmStore {
+    pool: Pool<Sqlite>,
+}
+
+impl AlarmStore {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn insert(&self, events: &[AlarmEvent]) -> PowsyblResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for chunk in events.chunks(EVENT_ROWS) {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "INSERT INTO alarm_events (alarm_id, telemetry_id, limit_name, direction, \
+                 severity, kind, value, threshold, sim_time, wall_time) ",
+            );
+            query.push_values(chunk, |mut row, event| {
+                let alarm = &event.alarm;
+                row.push_bind(&alarm.id)
+                    .push_bind(&alarm.telemetry_id)
+                    .push_bind(&alarm.limit)
+                    .push_bind(name(&alarm.direction))
+                    .push_bind(name(&alarm.severity))
+                    .push_bind(name(&event.kind))
+                    .push_bind(event.value)
+                    .push_bind(alarm.threshold)
+                    .push_bind(event.sim_time as i64)
+                    .push_bind(event.wall_time);
+            });
+            query.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Most recent events first
+    pub async fn history(
+        &self,
+        filter: &AlarmHistoryQuery,
+    ) -> PowsyblResult<Vec<AlarmHistoryEntry>> {
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "SELECT id, alarm_id, telemetry_id, limit_name, direction, severity, kind, value, \
+             threshold, sim_time, wall_time FROM alarm_events WHERE 1 = 1",
+        );
+        if let Some(from) = filter.from {
+            query.push(" AND wall_time >= ").push_bind(from);
+        }
+        if let Some(to) = filter.to {
+            query.push(" AND wall_time <= ").push_bind(to);
+        }
+        if let Some(alarm_id) = &filter.alarm_id {
+            query.push(" AND alarm_id = ").push_bind(alarm_id);
+        }
+        if let Some(telemetry_id) = &filter.telemetry_id {
+            query.push(" AND telemetry_id = ").push_bind(telemetry_id);
+        }
+        query
+            .push(" ORDER BY wall_time DESC, id DESC LIMIT ")
+            .push_bind(filter.limit.unwrap_or(1_000) as i64);
+
+        Ok(query
+            .build_query_as::<AlarmHistoryEntry>()
+            .fetch_all(&self.pool)
+            .await?)
+    }
+
+    pub async fn clear(&self) -> PowsyblResult<u64> {
+        let result = sqlx::query("DELETE FROM alarm_events")
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,161 @@
+use super::model::{BalanceModel, Term};
+use super::{BalanceConfig, BalanceReport, NodeImbalance, Quantity, SuspectMeasurement};
+
+use std::collections::{BTreeSet, HashMap};
+
+// Accumulated suspicion of a measurement
+struct Suspicion<'a> {
+    term: &'a Term,
+    quantity: Quantity,
+    value: f64,
+    score: f64,
+    node_ids: BTreeSet<&'a str>,
+}
+
+fn tolerance(config: &BalanceConfig, quantity: Quantity, magnitude: f64) -> f64 {
+    let absolute = match quantity {
+        Quantity::Active => config.tolerance_mw,
+        Quantity::Reactive => config.tolerance_mvar,
+    };
+    absolute.max(config.relative_tolerance * magnitude)
+}
+
+/// Sum the measured flows of every node and rank the measurements involved in
+/// the imbalances found.
+///
+/// A measurement scores the relative imbalance of every node it belongs to; the
+/// two ends of a branch also score the mismatch between them, as their active
+/// powers should only differ by the losses. A wrong branch measurement thus ranks
+/// above the other measurements of its node.
+pub fn check(
+    config: &BalanceConfig,
+    model: &BalanceModel,
+    values: &HashMap<String, f64>,
+    time: u64,
+    checked_at: i64,
+) -> BalanceReport {
+    let mut report = BalanceReport {
+        level: config.level,
+        time,
+        checked_at,
+        nodes: model.nodes,
+        unobservable: model.unobservable,
+        ..BalanceReport::default()
+    };
+    let mut suspicions: HashMap<&str, Suspicion> = HashMap::new();
+
+    for balance in &model.balances {
+        let measured: Option<Vec<f64>> = balance
+            .terms
+            .iter()
+            .map(|term| values.get(&term.telemetry_id).copied())
+            .collect();
+        // Not every measurement has been received yet
+        let Some(measured) = measured else {
+            report.waiting += 1;
+            continue;
+        };
+        report.checked += 1;
+
+        let mismatch: f64 = balance
+            .terms
+            .iter()
+            .zip(&measured)
+            .map(|(term, value)| term.sign * value)
+            .sum();
+        let magnitude: f64 = measured.iter().map(|value| value.abs()).sum();
+        let tolerance = tolerance(config, balance.quantity, magnitude);
+        if mismatch.abs() <= tolerance {
+            continue;
+        }
+
+        report.imbalances.push(NodeImbalance {
+            node_id: balance.node_id.clone(),
+            substation_id: balance.substation_id.clone(),
+            quantity: balance.quantity,
+            mismatch,
+            tolerance,
+            terms: balance.terms.len(),
+        });
+
+        let ratio = mismatch.abs() / tolerance;
+        for (term, &value) in balance.terms.iter().zip(&measured) {
+            let suspicion = suspicions
+                .entry(term.telemetry_id.as_str())
+                .or_insert_with(|| Suspicion {
+                    term,
+                    quantity: balance.quantity,
+                    value,
+                    score: 0.0,
+                    node_ids: BTreeSet::new(),
+                });
+            suspicion.score += ratio;
+            suspicion.node_ids.insert(balance.node_id.as_str());
+        }
+    }
+
+    // Reactive powers of a branch differ by its charging, so only the active
+    // powers of the two ends are compared
+    let mut compared = BTreeSet::new();
+    for balance in &model.balances {
+        if balance.quantity != Quantity::Active {
+            continue;
+        }
+        for term in &balance.terms {
+            let Some(opposite_id) = term.opposite_id.as_deref() else {
+                continue;
+            };
+            let pair = if term.telemetry_id.as_str() < opposite_id {
+                (term.telemetry_id.as_str(), opposite_id)
+            } else {
+                (opposite_id, term.telemetry_id.as_str())
+            };
+            if !compared.insert(pair) {
+                continue;
+            }
+            let (Some(&value), Some(&opposite)) =
+                (values.get(&term.telemetry_id), values.get(opposite_id))
+            else {
+                continue;
+            };
+
+            let residual = value + opposite;
+            let tolerance = tolerance(config, Quantity::Active, value.abs().max(opposite.abs()));
+            if residual.abs() <= tolerance {
+                continue;
+            }
+            // Only the ends already caught in a node imbalance are reported
+            for id in [pair.0, pair.1] {
+                if let Some(suspicion) = suspicions.get_mut(id) {
+                    suspicion.score += residual.abs() / tolerance;
+                }
+            }
+        }
+    }
+
+    report.imbalances.sort_by(|a, b| {
+        (b.mismatch.abs() / b.tolerance).total_cmp(&(a.mismatch.abs() / a.tolerance))
+    });
+
+    let mut suspects: Vec<SuspectMeasurement> = suspicions
+        .into_iter()
+        .map(|(id, suspicion)| SuspectMeasurement {
+            telemetry_id: id.to_string(),
+            equipment_id: suspicion.term.equipment_id.clone(),
+            side: suspicion.term.side.clone(),
+            quantity: suspicion.quantity,
+            value: suspicion.value,
+            score: suspicion.score,
+            node_ids: suspicion.node_ids.into_iter().map(str::to_string).collect(),
+        })
+        .collect();
+    suspects.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.telemetry_id.cmp(&b.telemetry_id))
+    });
+    suspects.truncate(config.max_suspects);
+    report.suspects = suspects;
+
+    report
+}
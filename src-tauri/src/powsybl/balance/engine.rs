@@ -0,0 +1,92 @@
+use super::super::entities::TelemetryData;
+use super::super::historian::now_millis;
+use super::checker;
+use super::model::BalanceModel;
+use super::{BalanceConfig, BalanceReport, BALANCE_REPORT_EVENT};
+
+use log::{debug, warn};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// Last values of the measurements read by the balances
+#[derive(Debug, Default)]
+struct Latest {
+    values: HashMap<String, f64>,
+    time: u64,
+}
+
+/// Receiving end of the telemetry subscription, keeping the last value of every
+/// measurement read by the balances
+#[derive(Clone)]
+pub struct BalanceFeed {
+    ids: Arc<HashSet<String>>,
+    latest: Arc<Mutex<Latest>>,
+}
+
+impl BalanceFeed {
+    pub fn new(ids: HashSet<String>) -> Self {
+        Self {
+            ids: Arc::new(ids),
+            latest: Arc::default(),
+        }
+    }
+
+    pub fn measurements(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn send(&self, data: TelemetryData) {
+        let Ok(mut latest) = self.latest.lock() else {
+            return;
+        };
+        latest.time = data.time;
+        for (id, value) in data.values {
+            if self.ids.contains(&id) {
+                latest.values.insert(id, value);
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Option<(HashMap<String, f64>, u64)> {
+        let latest = self.latest.lock().ok()?;
+        (!latest.values.is_empty()).then(|| (latest.values.clone(), latest.time))
+    }
+}
+
+pub type LastReport = Arc<Mutex<Option<BalanceReport>>>;
+
+/// Check the balances at every interval, keeping and emitting the last report
+pub fn spawn_checks(
+    config: BalanceConfig,
+    model: BalanceModel,
+    feed: BalanceFeed,
+    last_report: LastReport,
+    app_handle: AppHandle,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(config.interval_ms.max(100)));
+
+        loop {
+            interval.tick().await;
+            let Some((values, time)) = feed.snapshot() else {
+                continue;
+            };
+
+            let report = checker::check(&config, &model, &values, time, now_millis());
+            debug!(
+                "{} imbalances in {} checked balances",
+                report.imbalances.len(),
+                report.checked
+            );
+            if let Err(e) = app_handle.emit(BALANCE_REPORT_EVENT, &report) {
+                warn!("Failed to emit balance report: {}", e);
+            }
+            if let Ok(mut last) = last_report.lock() {
+                *last = Some(report);
+            }
+        }
+    })
+}
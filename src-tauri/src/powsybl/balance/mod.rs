@@ -0,0 +1,270 @@
+use super::diagrams::sld_subscriptions::{
+    game_master_outputs, subscribe_diagram, unsubscribe_diagram,
+};
+use super::diagrams::TelemetrySink;
+use super::errors::{PowsyblError, PowsyblResult};
+use super::topology::with_topology;
+
+use crate::state::AppState;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+use tokio::task::JoinHandle;
+
+mod checker;
+mod engine;
+mod model;
+
+pub use engine::BalanceFeed;
+
+use engine::LastReport;
+use model::BalanceModel;
+
+/// Subscription id of the balance feed, alongside the diagram ones
+const BALANCE_SUBSCRIPTION: &str = "balance";
+
+/// Event carrying every balance report
+pub const BALANCE_REPORT_EVENT: &str = "balance-report";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantity {
+    Active,
+    Reactive,
+}
+
+/// Nodes whose flows are summed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceLevel {
+    /// Every electrical bus
+    #[default]
+    Bus,
+    /// Every substation, from the flows crossing its boundary
+    Substation,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BalanceConfig {
+    pub level: BalanceLevel,
+    /// Mismatch allowed on the active power balances, in MW
+    pub tolerance_mw: f64,
+    /// Mismatch allowed on the reactive power balances, in Mvar
+    pub tolerance_mvar: f64,
+    /// Mismatch allowed as a fraction of the sum of the absolute flows, when larger
+    pub relative_tolerance: f64,
+    /// Whether generators and batteries report the power they produce as
+    /// positive, rather than the power they consume
+    pub generation_positive: bool,
+    pub interval_ms: u64,
+    pub max_suspects: usize,
+}
+
+impl Default for BalanceConfig {
+    fn default() -> Self {
+        Self {
+            level: BalanceLevel::Bus,
+            tolerance_mw: 5.0,
+            tolerance_mvar: 10.0,
+            relative_tolerance: 0.02,
+            generation_positive: true,
+            interval_ms: 5_000,
+            max_suspects: 20,
+        }
+    }
+}
+
+/// A node whose measured flows do not sum to zero
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeImbalance {
+    pub node_id: String,
+    pub substation_id: String,
+    pub quantity: Quantity,
+    /// Sum of the flows leaving the node
+    pub mismatch: f64,
+    pub tolerance: f64,
+    pub terms: usize,
+}
+
+/// A measurement involved in imbalances, with a score growing with their size
+#[derive(Debug, Clone, Serialize)]
+pub struct SuspectMeasurement {
+    pub telemetry_id: String,
+    pub equipment_id: String,
+    pub side: Option<String>,
+    pub quantity: Quantity,
+    pub value: f64,
+    pub score: f64,
+    pub node_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BalanceReport {
+    pub level: BalanceLevel,
+    /// Simulation time of the last values received
+    pub time: u64,
+    pub checked_at: i64,
+    pub nodes: usize,
+    /// Node balances with every measurement received
+    pub checked: usize,
+    /// Node balances still missing a measurement
+    pub waiting: usize,
+    /// Node balances with an end that has no measurement
+    pub unobservable: usize,
+    /// Imbalances, largest relative to their tolerance first
+    pub imbalances: Vec<NodeImbalance>,
+    /// Measurements most likely to be wrong first
+    pub suspects: Vec<SuspectMeasurement>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceStatus {
+    pub running: bool,
+    pub config: BalanceConfig,
+    pub nodes: usize,
+    pub balances: usize,
+    pub unobservable: usize,
+    pub measurements: usize,
+}
+
+/// Kirchhoff checks of the measured power flows, summed at every bus or
+/// substation of the loaded topology
+#[derive(Default)]
+pub struct BalanceChecker {
+    config: BalanceConfig,
+    nodes: usize,
+    balances: usize,
+    unobservable: usize,
+    measurements: usize,
+    last_report: LastReport,
+    checks: Option<JoinHandle<()>>,
+}
+
+pub type BalanceCheckerState = tokio::sync::Mutex<BalanceChecker>;
+
+impl BalanceChecker {
+    pub fn is_running(&self) -> bool {
+        self.checks
+            .as_ref()
+            .is_some_and(|checks| !checks.is_finished())
+    }
+
+    fn start(
+        &mut self,
+        config: BalanceConfig,
+        model: BalanceModel,
+        app_handle: AppHandle,
+    ) -> BalanceFeed {
+        let feed = BalanceFeed::new(model.telemetry_ids());
+        self.nodes = model.nodes;
+        self.balances = model.balances.len();
+        self.unobservable = model.unobservable;
+        self.measurements = feed.measurements();
+        self.last_report = Arc::default();
+        self.config = config.clone();
+        self.checks = Some(engine::spawn_checks(
+            config,
+            model,
+            feed.clone(),
+            self.last_report.clone(),
+            app_handle,
+        ));
+        feed
+    }
+
+    fn stop(&mut self) {
+        if let Some(checks) = self.checks.take() {
+            checks.abort();
+            info!("Balance checks stopped");
+        }
+    }
+
+    fn status(&self) -> BalanceStatus {
+        BalanceStatus {
+            running: self.is_running(),
+            config: self.config.clone(),
+            nodes: self.nodes,
+            balances: self.balances,
+            unobservable: self.unobservable,
+            measurements: self.measurements,
+        }
+    }
+}
+
+/// Start checking the power balances of the loaded topology against the
+/// telemetry feed, restarting the checks if they run
+#[tauri::command(rename_all = "snake_case")]
+pub async fn start_balance_checks(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    balance: State<'_, BalanceCheckerState>,
+    config: Option<BalanceConfig>,
+    network_id: Option<String>,
+) -> PowsyblResult<BalanceStatus> {
+    let config = config.unwrap_or_default();
+    let buses = with_topology(&state, network_id, |graph| Ok(graph.bus_terminals()))?;
+    let outputs = game_master_outputs(&state)?;
+
+    let model = model::build_model(&config, &buses, &outputs);
+    if model.balances.is_empty() {
+        return Err(PowsyblError::BalanceError(format!(
+            "No node balance is fully measured, {} are missing power measurements",
+            model.unobservable
+        )));
+    }
+
+    let mut balance = balance.lock().await;
+    unsubscribe_diagram(&state, BALANCE_SUBSCRIPTION);
+    balance.stop();
+
+    let feed = balance.start(config, model, app_handle.clone());
+    subscribe_diagram(
+        app_handle,
+        &state,
+        BALANCE_SUBSCRIPTION.to_string(),
+        TelemetrySink::Balance(feed),
+    )
+    .await?;
+
+    let status = balance.status();
+    info!(
+        "Balance checks started on {} balances of {} nodes, {} not observable",
+        status.balances, status.nodes, status.unobservable
+    );
+    Ok(status)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn stop_balance_checks(
+    state: State<'_, AppState>,
+    balance: State<'_, BalanceCheckerState>,
+) -> PowsyblResult<BalanceStatus> {
+    let mut balance = balance.lock().await;
+    unsubscribe_diagram(&state, BALANCE_SUBSCRIPTION);
+    balance.stop();
+
+    Ok(balance.status())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_balance_status(
+    balance: State<'_, BalanceCheckerState>,
+) -> PowsyblResult<BalanceStatus> {
+    Ok(balance.lock().await.status())
+}
+
+/// Last report of the balance checks, if any was computed
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_balance_report(
+    balance: State<'_, BalanceCheckerState>,
+) -> PowsyblResult<Option<BalanceReport>> {
+    let balance = balance.lock().await;
+    let report = balance
+        .last_report
+        .lock()
+        .map_err(|_| PowsyblError::LockError)?;
+    Ok(report.clone())
+}
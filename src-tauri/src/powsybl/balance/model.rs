@@ -0,0 +1,192 @@
+use super::super::topology::{BusTerminals, InjectionKind, Terminal};
+use super::{BalanceConfig, BalanceLevel, Quantity};
+use crate::shared::entities::dynawo::GameMasterOutput;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// A measured equipment end in the balance of a node
+#[derive(Debug, Clone)]
+pub struct Term {
+    pub telemetry_id: String,
+    pub equipment_id: String,
+    pub side: Option<String>,
+    /// +1 for power leaving the node, -1 for power entering it
+    pub sign: f64,
+    /// Measurement of the other end of a branch, if any
+    pub opposite_id: Option<String>,
+}
+
+/// Terms of a node for one quantity, known only when every connected end is measured
+#[derive(Debug, Clone)]
+pub struct NodeBalance {
+    pub node_id: String,
+    pub substation_id: String,
+    pub quantity: Quantity,
+    pub terms: Vec<Term>,
+}
+
+/// Balances of every node that can be checked
+#[derive(Debug, Default)]
+pub struct BalanceModel {
+    pub balances: Vec<NodeBalance>,
+    pub nodes: usize,
+    /// Node quantities with at least one end not measured
+    pub unobservable: usize,
+}
+
+impl BalanceModel {
+    /// Telemetry ids read by the balances
+    pub fn telemetry_ids(&self) -> HashSet<String> {
+        self.balances
+            .iter()
+            .flat_map(|balance| balance.terms.iter())
+            .flat_map(|term| std::iter::once(&term.telemetry_id).chain(&term.opposite_id))
+            .cloned()
+            .collect()
+    }
+}
+
+fn component_quantity(component_type: Option<&str>) -> Option<Quantity> {
+    match component_type? {
+        "ARROW_ACTIVE" => Some(Quantity::Active),
+        "ARROW_REACTIVE" => Some(Quantity::Reactive),
+        _ => None,
+    }
+}
+
+fn normalize_side(side: Option<&str>) -> Option<String> {
+    let side = match side?.to_ascii_uppercase().as_str() {
+        "1" | "ONE" => "ONE",
+        "2" | "TWO" => "TWO",
+        "3" | "THREE" => "THREE",
+        _ => return None,
+    };
+    Some(side.to_string())
+}
+
+// Active and reactive power outputs, by equipment and side
+type OutputIndex<'a> = HashMap<(&'a str, Option<String>, Quantity), &'a str>;
+
+fn index_outputs(outputs: &[GameMasterOutput]) -> OutputIndex<'_> {
+    outputs
+        .iter()
+        .filter_map(|output| {
+            let quantity = component_quantity(output.component_type.as_deref())?;
+            let equipment_id = output.equipment_id.as_deref()?;
+            let side = normalize_side(output.side.as_deref());
+            Some(((equipment_id, side, quantity), output.dynawo_id.as_str()))
+        })
+        .collect()
+}
+
+/// Match the equipment ends of the topology with the power outputs.
+///
+/// At the substation level, branches with both ends in the substation are left
+/// out: only the flows crossing its boundary and its injections are summed.
+pub fn build_model(
+    config: &BalanceConfig,
+    buses: &[BusTerminals],
+    outputs: &[GameMasterOutput],
+) -> BalanceModel {
+    let index = index_outputs(outputs);
+    let substation_of: HashMap<&str, &str> = buses
+        .iter()
+        .map(|bus| (bus.bus_id.as_str(), bus.substation_id.as_str()))
+        .collect();
+
+    // Terminals of every node, keyed by node id
+    let mut nodes: BTreeMap<&str, (&str, Vec<&Terminal>)> = BTreeMap::new();
+    for bus in buses {
+        let node_id = match config.level {
+            BalanceLevel::Bus => bus.bus_id.as_str(),
+            BalanceLevel::Substation => bus.substation_id.as_str(),
+        };
+        let (_, terminals) = nodes
+            .entry(node_id)
+            .or_insert((bus.substation_id.as_str(), Vec::new()));
+        terminals.extend(bus.terminals.iter().filter(|terminal| {
+            config.level == BalanceLevel::Bus
+                || terminal
+                    .remote_bus_id
+                    .as_deref()
+                    .and_then(|remote| substation_of.get(remote))
+                    .is_none_or(|&remote| remote != bus.substation_id)
+        }));
+    }
+
+    let mut model = BalanceModel {
+        nodes: nodes.len(),
+        ..BalanceModel::default()
+    };
+    for (node_id, (substation_id, terminals)) in nodes {
+        if terminals.is_empty() {
+            continue;
+        }
+        for quantity in [Quantity::Active, Quantity::Reactive] {
+            match node_terms(config, &index, &terminals, quantity) {
+                Some(terms) if !terms.is_empty() => model.balances.push(NodeBalance {
+                    node_id: node_id.to_string(),
+                    substation_id: substation_id.to_string(),
+                    quantity,
+                    terms,
+                }),
+                Some(_) => {}
+                None => model.unobservable += 1,
+            }
+        }
+    }
+    model
+}
+
+// Terms of a node, or `None` when an end carrying the quantity is not measured
+fn node_terms(
+    config: &BalanceConfig,
+    index: &OutputIndex<'_>,
+    terminals: &[&Terminal],
+    quantity: Quantity,
+) -> Option<Vec<Term>> {
+    let mut terms = Vec::with_capacity(terminals.len());
+    for terminal in terminals {
+        let carries = match (terminal.injection, quantity) {
+            (Some(kind), Quantity::Active) => kind.carries_active_power(),
+            _ => true,
+        };
+        if !carries {
+            continue;
+        }
+
+        let side = normalize_side(terminal.side.as_deref());
+        let key = (terminal.equipment_id.as_str(), side.clone(), quantity);
+        let telemetry_id = *index.get(&key)?;
+
+        let opposite_id = match side.as_deref() {
+            Some(side) if terminal.injection.is_none() => {
+                let opposite = if side == "ONE" { "TWO" } else { "ONE" };
+                let key = (
+                    terminal.equipment_id.as_str(),
+                    Some(opposite.to_string()),
+                    quantity,
+                );
+                index.get(&key).map(|id| id.to_string())
+            }
+            _ => None,
+        };
+        let produces = matches!(
+            terminal.injection,
+            Some(InjectionKind::Generator | InjectionKind::Battery)
+        );
+
+        terms.push(Term {
+            telemetry_id: telemetry_id.to_string(),
+            equipment_id: terminal.equipment_id.clone(),
+            side,
+            sign: if produces && config.generation_positive {
+                -1.0
+            } else {
+                1.0
+            },
+            opposite_id,
+        });
+    }
+    Some(terms)
+}
@@ -0,0 +1,410 @@
+use super::errors::{PowsyblError, PowsyblResult};
+use super::networks::fetch_network_list;
+use super::send_zmq_request;
+
+use crate::state::AppState;
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Pool, Sqlite};
+use std::collections::{HashMap, VecDeque};
+use tauri::State;
+
+/// Number of responses kept in memory, the least recently used entries are
+/// still served from SQLite
+const MAX_MEMORY_ENTRIES: usize = 256;
+
+/// Number of responses kept in SQLite, the oldest being dropped first
+const MAX_DISK_ENTRIES: i64 = 10_000;
+
+/// Age after which a response is dropped from SQLite, in seconds
+const MAX_DISK_AGE: i64 = 7 * 24 * 3600;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    method: String,
+    params: String,
+    network_version: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub memory_hits: u64,
+    pub disk_hits: u64,
+    pub misses: u64,
+    pub bypassed: u64,
+    pub memory_entries: usize,
+    pub disk_entries: i64,
+}
+
+/// Cache of sidecar responses, in memory and in SQLite, keyed by method,
+/// parameters and the version hash of the network they were computed on
+pub struct QueryCache {
+    pool: Pool<Sqlite>,
+    memory: HashMap<CacheKey, (String, Value)>,
+    order: VecDeque<CacheKey>,
+    stats: CacheStats,
+}
+
+pub type QueryCacheState = tokio::sync::Mutex<QueryCache>;
+
+impl QueryCache {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self {
+            pool,
+            memory: HashMap::new(),
+            order: VecDeque::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    async fn get(&mut self, key: &CacheKey) -> PowsyblResult<Option<Value>> {
+        if let Some((_, value)) = self.memory.get(key) {
+            let value = value.clone();
+            self.stats.memory_hits += 1;
+            self.touch(key);
+            return Ok(Some(value));
+        }
+
+        let row = sqlx::query_as::<_, (String, String)>(
+            "SELECT network_id, response FROM sidecar_cache
+             WHERE method = ? AND params = ? AND network_version = ? AND created_at >= ?",
+        )
+        .bind(&key.method)
+        .bind(&key.params)
+        .bind(&key.network_version)
+        .bind(now_secs() - MAX_DISK_AGE)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some((network_id, response)) => {
+                let value: Value = serde_json::from_str(&response)?;
+                self.stats.disk_hits += 1;
+                self.remember(key.clone(), network_id, value.clone());
+                Ok(Some(value))
+            }
+            None => {
+                self.stats.misses += 1;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn put(&mut self, key: CacheKey, network_id: &str, value: &Value) -> PowsyblResult<()> {
+        let created_at = now_secs();
+
+        sqlx::query(
+            "INSERT INTO sidecar_cache
+             (method, params, network_version, network_id, response, created_at)
+             VALUES (?, ?, ?, ?, json(?), ?)
+             ON CONFLICT(method, params, network_version)
+             DO UPDATE SET response = excluded.response, created_at = excluded.created_at",
+        )
+        .bind(&key.method)
+        .bind(&key.params)
+        .bind(&key.network_version)
+        .bind(network_id)
+        .bind(value.to_string())
+        .bind(created_at)
+        .execute(&self.pool)
+        .await?;
+
+        // Expired and oldest responses are dropped as new ones are stored
+        sqlx::query(
+            "DELETE FROM sidecar_cache WHERE created_at < ? OR rowid NOT IN
+             (SELECT rowid FROM sidecar_cache ORDER BY created_at DESC, rowid DESC LIMIT ?)",
+        )
+        .bind(created_at - MAX_DISK_AGE)
+        .bind(MAX_DISK_ENTRIES)
+        .execute(&self.pool)
+        .await?;
+
+        self.remember(key, network_id.to_string(), value.clone());
+        Ok(())
+    }
+
+    // Move an entry to the back of the eviction order
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(position) = self.order.iter().position(|other| other == key) {
+            self.order.remove(position);
+            self.order.push_back(key.clone());
+        }
+    }
+
+    fn remember(&mut self, key: CacheKey, network_id: String, value: Value) {
+        if self
+            .memory
+            .insert(key.clone(), (network_id, value))
+            .is_none()
+        {
+            self.order.push_back(key);
+        } else {
+            self.touch(&key);
+        }
+
+        while self.order.len() > MAX_MEMORY_ENTRIES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.memory.remove(&oldest);
+            }
+        }
+    }
+
+    /// Drop every cached response computed on a network
+    pub async fn invalidate_network(&mut self, network_id: &str) -> PowsyblResult<()> {
+        self.memory.retain(|_, (id, _)| id != network_id);
+        let memory = &self.memory;
+        self.order.retain(|key| memory.contains_key(key));
+
+        sqlx::query("DELETE FROM sidecar_cache WHERE network_id = ?")
+            .bind(network_id)
+            .execute(&self.pool)
+            .await?;
+
+        debug!("Query cache invalidated for network '{}'", network_id);
+        Ok(())
+    }
+
+    pub async fn clear(&mut self) -> PowsyblResult<()> {
+        self.memory.clear();
+        self.order.clear();
+        self.stats = CacheStats::default();
+
+        sqlx::query("DELETE FROM sidecar_cache")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn stats(&self) -> PowsyblResult<CacheStats> {
+        let (disk_entries,) = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM sidecar_cache")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(CacheStats {
+            memory_entries: self.memory.len(),
+            disk_entries,
+            ..self.stats.clone()
+        })
+    }
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn network_version(
+    state: &State<'_, AppState>,
+    network_id: Option<&str>,
+) -> PowsyblResult<Option<(String, String)>> {
+    let app_state = state.read().map_err(|_| PowsyblError::LockError)?;
+    let network_id = app_state
+        .powsybl
+        .resolve_network_id(network_id.map(str::to_string));
+
+    Ok(app_state
+        .powsybl
+        .network(network_id.as_deref())
+        .and_then(|network| {
+            let version = network.info.version.clone()?;
+            Some((network.info.id.clone(), version))
+        }))
+}
+
+/// Send a ZMQ request through the query cache.
+///
+/// The response is only cached when the version of the targeted network is known,
+/// so a result can never be served for another revision of the network.
+pub(crate) async fn cached_zmq_request(
+    cache: &State<'_, QueryCacheState>,
+    state: &State<'_, AppState>,
+    method: &str,
+    params: Value,
+    network_id: Option<&str>,
+) -> PowsyblResult<Value> {
+    let mut version = network_version(state, network_id)?;
+    if version.is_none() {
+        // The network may have been loaded by the sidecar on startup
+        match fetch_network_list().await {
+            Ok(list) => {
+                state
+                    .write()
+                    .map_err(|_| PowsyblError::LockError)?
+                    .powsybl
+                    .sync_networks(&list);
+                version = network_version(state, network_id)?;
+            }
+            Err(e) => warn!("Failed to refresh the network list: {}", e),
+        }
+    }
+
+    let Some((network_id, network_version)) = version else {
+        cache.lock().await.stats.bypassed += 1;
+        return send_zmq_request(method, Some(params)).await;
+    };
+
+    let key = CacheKey {
+        method: method.to_string(),
+        params: params.to_string(),
+        network_version,
+    };
+
+    if let Some(value) = cache.lock().await.get(&key).await? {
+        debug!("Query cache hit for '{}'", method);
+        return Ok(value);
+    }
+
+    let value = send_zmq_request(method, Some(params)).await?;
+    cache.lock().await.put(key, &network_id, &value).await?;
+
+    Ok(value)
+}
+
+/// Get the hit and miss counters of the sidecar query cache
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_query_cache_stats(cache: State<'_, QueryCacheState>) -> PowsyblResult<CacheStats> {
+    cache.lock().await.stats().await
+}
+
+/// Drop every cached sidecar response
+#[tauri::command(rename_all = "snake_case")]
+pub async fn clear_query_cache(cache: State<'_, QueryCacheState>) -> PowsyblResult<CacheStats> {
+    let mut cache = cache.lock().await;
+    cache.clear().await?;
+    cache.stats().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    // A single connection, as every connection to `:memory:` opens its own database
+    async fn pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::raw_sql(include_str!(
+            "../../../migrations/20250602_create_sidecar_cache_table.sql"
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool
+    }
+
+    fn key(params: usize) -> CacheKey {
+        CacheKey {
+            method: "get_substations".to_string(),
+            params: params.to_string(),
+            network_version: "v1".to_string(),
+        }
+    }
+
+    async fn disk_entries(pool: &Pool<Sqlite>) -> i64 {
+        sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM sidecar_cache")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            .0
+    }
+
+    #[tokio::test]
+    async fn memory_hits_are_evicted_last() {
+        let mut cache = QueryCache::new(pool().await);
+        for params in 0..MAX_MEMORY_ENTRIES {
+            cache.remember(key(params), "n1".to_string(), json!(params));
+        }
+
+        assert_eq!(cache.get(&key(0)).await.unwrap(), Some(json!(0)));
+        cache.remember(key(MAX_MEMORY_ENTRIES), "n1".to_string(), json!(0));
+
+        assert_eq!(cache.memory.len(), MAX_MEMORY_ENTRIES);
+        assert!(cache.memory.contains_key(&key(0)));
+        assert!(!cache.memory.contains_key(&key(1)));
+        assert_eq!(cache.stats.memory_hits, 1);
+    }
+
+    #[tokio::test]
+    async fn responses_are_served_from_sqlite() {
+        let pool = pool().await;
+        QueryCache::new(pool.clone())
+            .put(key(1), "n1", &json!({"id": "S1"}))
+            .await
+            .unwrap();
+
+        let mut cache = QueryCache::new(pool);
+        assert_eq!(cache.get(&key(1)).await.unwrap(), Some(json!({"id": "S1"})));
+        assert_eq!(cache.get(&key(2)).await.unwrap(), None);
+        assert_eq!((cache.stats.disk_hits, cache.stats.misses), (1, 1));
+
+        // Served from memory once read
+        cache.get(&key(1)).await.unwrap();
+        assert_eq!(cache.stats.memory_hits, 1);
+    }
+
+    #[tokio::test]
+    async fn expired_responses_are_neither_served_nor_kept() {
+        let pool = pool().await;
+        sqlx::query(
+            "INSERT INTO sidecar_cache
+             (method, params, network_version, network_id, response, created_at)
+             VALUES ('get_substations', '1', 'v1', 'n1', '[]', ?)",
+        )
+        .bind(now_secs() - MAX_DISK_AGE - 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let mut cache = QueryCache::new(pool.clone());
+        assert_eq!(cache.get(&key(1)).await.unwrap(), None);
+
+        cache.put(key(2), "n1", &json!([])).await.unwrap();
+        assert_eq!(disk_entries(&pool).await, 1);
+    }
+
+    #[tokio::test]
+    async fn oldest_responses_are_dropped_past_the_disk_bound() {
+        let pool = pool().await;
+        sqlx::query(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?)
+             INSERT INTO sidecar_cache
+             (method, params, network_version, network_id, response, created_at)
+             SELECT 'get_substations', 'old' || i, 'v1', 'n1', '[]', ? FROM n",
+        )
+        .bind(MAX_DISK_ENTRIES)
+        .bind(now_secs() - 60)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let mut cache = QueryCache::new(pool.clone());
+        cache.put(key(1), "n1", &json!([])).await.unwrap();
+
+        assert_eq!(disk_entries(&pool).await, MAX_DISK_ENTRIES);
+        assert_eq!(cache.get(&key(1)).await.unwrap(), Some(json!([])));
+    }
+
+    #[tokio::test]
+    async fn invalidation_drops_the_responses_of_a_network() {
+        let pool = pool().await;
+        let mut cache = QueryCache::new(pool.clone());
+        cache.put(key(1), "n1", &json!(1)).await.unwrap();
+        cache.put(key(2), "n2", &json!(2)).await.unwrap();
+
+        cache.invalidate_network("n1").await.unwrap();
+
+        assert_eq!(disk_entries(&pool).await, 1);
+        assert_eq!(cache.order.len(), 1);
+        assert_eq!(cache.get(&key(1)).await.unwrap(), None);
+        assert_eq!(cache.get(&key(2)).await.unwrap(), Some(json!(2)));
+    }
+}
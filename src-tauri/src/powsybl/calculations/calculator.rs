@@ -0,0 +1,421 @@
+use super::super::comparison::{NetworkElement, NetworkElements};
+use super::super::entities::TelemetryData;
+use super::expression::Expression;
+use super::CalculationConfig;
+use crate::shared::entities::dynawo::GameMasterOutput;
+
+use log::{trace, warn};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// A derived point, compiled
+#[derive(Debug)]
+struct Calculation {
+    config: CalculationConfig,
+    expression: Expression,
+    inputs: Vec<String>,
+}
+
+/// Calculations loaded from the configuration, in evaluation order
+#[derive(Debug, Default)]
+pub struct CalculationSet {
+    calculations: Vec<Calculation>,
+    /// Outputs describing the derived points, like the game master ones
+    outputs: Vec<GameMasterOutput>,
+    /// Every id read by a calculation
+    inputs: HashSet<String>,
+}
+
+impl CalculationSet {
+    /// Parse and order the calculations, replacing the network attributes by their
+    /// values. Every problem found is reported, one per line.
+    pub fn compile(
+        configs: Vec<CalculationConfig>,
+        outputs: &[GameMasterOutput],
+        network: Option<&NetworkElements>,
+    ) -> Result<Self, String> {
+        let raw_outputs: HashMap<&str, &GameMasterOutput> = outputs
+            .iter()
+            .map(|output| (output.dynawo_id.as_str(), output))
+            .collect();
+        let elements: HashMap<&str, &NetworkElement> = network
+            .map(|network| {
+                network
+                    .elements
+                    .iter()
+                    .map(|element| (element.id.as_str(), element))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut problems = Vec::new();
+        let mut ids = HashSet::new();
+        let mut calculations = Vec::new();
+        for config in configs {
+            if config.id.trim().is_empty() {
+                problems.push(format!("Calculation '{}': empty id", config.expression));
+                continue;
+            }
+            if !ids.insert(config.id.clone()) {
+                problems.push(format!("Calculation '{}': duplicate id", config.id));
+                continue;
+            }
+            if raw_outputs.contains_key(config.id.as_str()) {
+                problems.push(format!(
+                    "Calculation '{}': id already used by a game master output",
+                    config.id
+                ));
+                continue;
+            }
+
+            let mut expression = match Expression::parse(&config.expression) {
+                Ok(expression) => expression,
+                Err(e) => {
+                    problems.push(format!("Calculation '{}': {}", config.id, e));
+                    continue;
+                }
+            };
+            let resolved = expression.resolve_attributes(&|element, attribute| {
+                attribute_value(network.is_some(), &elements, element, attribute)
+            });
+            if let Err(e) = resolved {
+                problems.push(format!("Calculation '{}': {}", config.id, e));
+                continue;
+            }
+
+            let inputs = expression.variables();
+            calculations.push(Calculation {
+                config,
+                expression,
+                inputs,
+            });
+        }
+
+        // Ids that are neither outputs nor calculations may still be published
+        if !raw_outputs.is_empty() {
+            for calculation in &calculations {
+                for input in &calculation.inputs {
+                    if !raw_outputs.contains_key(input.as_str()) && !ids.contains(input) {
+                        warn!(
+                            "Calculation '{}' reads '{}', which is not a game master output",
+                            calculation.config.id, input
+                        );
+                    }
+                }
+            }
+        }
+
+        let calculations = match evaluation_order(calculations) {
+            Ok(calculations) => calculations,
+            Err(cycle) => {
+                problems.push(format!(
+                    "Calculations depend on each other: {}",
+                    cycle.join(", ")
+                ));
+                Vec::new()
+            }
+        };
+        if !problems.is_empty() {
+            return Err(problems.join("\n"));
+        }
+
+        // Topics are resolved in order, so that calculations over calculations
+        // inherit the topics of their own inputs
+        let mut topics: HashMap<String, String> = HashMap::new();
+        let mut derived_outputs = Vec::with_capacity(calculations.len());
+        for calculation in &calculations {
+            let input_topics: BTreeSet<&str> = calculation
+                .inputs
+                .iter()
+                .map(|input| match raw_outputs.get(input.as_str()) {
+                    Some(output) => output.topic.as_str(),
+                    None => topics.get(input).map(String::as_str).unwrap_or_default(),
+                })
+                .collect();
+            // An empty topic subscribes to every message
+            let topic = match input_topics.len() {
+                1 => input_topics
+                    .into_iter()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                _ => String::new(),
+            };
+            topics.insert(calculation.config.id.clone(), topic.clone());
+
+            let config = &calculation.config;
+            derived_outputs.push(GameMasterOutput {
+                id: config.id.clone(),
+                dynawo_id: config.id.clone(),
+                topic,
+                graphical_id: config.graphical_id.clone(),
+                equipment_id: config.equipment_id.clone(),
+                side: config.side.clone(),
+                component_type: config.component_type.clone(),
+                unit: config.unit.clone(),
+            });
+        }
+
+        Ok(Self {
+            inputs: calculations
+                .iter()
+                .flat_map(|calculation| calculation.inputs.iter().cloned())
+                .collect(),
+            calculations,
+            outputs: derived_outputs,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calculations.is_empty()
+    }
+
+    pub fn outputs(&self) -> &[GameMasterOutput] {
+        &self.outputs
+    }
+
+    /// Definitions of the calculations, in evaluation order
+    pub fn configs(&self) -> Vec<CalculationConfig> {
+        self.calculations
+            .iter()
+            .map(|calculation| calculation.config.clone())
+            .collect()
+    }
+}
+
+fn attribute_value(
+    network_loaded: bool,
+    elements: &HashMap<&str, &NetworkElement>,
+    element: &str,
+    attribute: &str,
+) -> Result<f64, String> {
+    if !network_loaded {
+        return Err("network attributes are unavailable".to_string());
+    }
+    let found = elements
+        .get(element)
+        .ok_or_else(|| format!("unknown network element '{}'", element))?;
+    found
+        .attributes
+        .get(attribute)
+        .and_then(|value| value.as_f64())
+        .ok_or_else(|| format!("'{}' has no numeric attribute '{}'", element, attribute))
+}
+
+// Order the calculations after the ones they read, or return the ids of the
+// calculations caught in a cycle
+fn evaluation_order(calculations: Vec<Calculation>) -> Result<Vec<Calculation>, Vec<String>> {
+    let ids: HashSet<String> = calculations
+        .iter()
+        .map(|calculation| calculation.config.id.clone())
+        .collect();
+
+    let mut pending = calculations;
+    let mut ordered: Vec<Calculation> = Vec::with_capacity(pending.len());
+    let mut done: HashSet<String> = HashSet::new();
+    loop {
+        let (ready, blocked): (Vec<Calculation>, Vec<Calculation>) =
+            pending.into_iter().partition(|calculation| {
+                calculation
+                    .inputs
+                    .iter()
+                    .all(|input| !ids.contains(input) || done.contains(input))
+            });
+        if ready.is_empty() {
+            if blocked.is_empty() {
+                return Ok(ordered);
+            }
+            return Err(blocked
+                .into_iter()
+                .map(|calculation| calculation.config.id)
+                .collect());
+        }
+        done.extend(
+            ready
+                .iter()
+                .map(|calculation| calculation.config.id.clone()),
+        );
+        ordered.extend(ready);
+        pending = blocked;
+    }
+}
+
+/// Evaluation of a calculation set over a telemetry feed.
+///
+/// Messages only carry the values of their topic, so the last value of every
+/// input is kept: a derived point is computed whenever one of its inputs is
+/// received, once all of them are known. A point reading an invalid value is
+/// invalid, and one reading a value older than `max_age` is no longer computed,
+/// so the quality checks find it stale as well.
+#[derive(Debug, Default)]
+pub struct Calculator {
+    set: Arc<CalculationSet>,
+    /// Last valid value of every input and when it was received
+    values: HashMap<String, (f64, Instant)>,
+}
+
+impl Calculator {
+    pub fn new(set: Arc<CalculationSet>) -> Self {
+        Self {
+            set,
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn set(&self) -> &Arc<CalculationSet> {
+        &self.set
+    }
+
+    /// Add the derived points to a message
+    pub fn apply(&mut self, data: &mut TelemetryData, now: Instant, max_age: Duration) {
+        if self.set.is_empty() {
+            return;
+        }
+        // Invalid values are rejected after the calculations
+        let mut invalid = HashSet::new();
+        for (id, &value) in &data.values {
+            if !self.set.inputs.contains(id) {
+                continue;
+            }
+            if value.is_finite() {
+                self.values.insert(id.clone(), (value, now));
+            } else {
+                self.values.remove(id);
+                invalid.insert(id.clone());
+            }
+        }
+
+        for calculation in &self.set.calculations {
+            let received = calculation.inputs.is_empty()
+                || calculation
+                    .inputs
+                    .iter()
+                    .any(|input| data.values.contains_key(input));
+            if !received {
+                continue;
+            }
+
+            let id = &calculation.config.id;
+            if calculation
+                .inputs
+                .iter()
+                .any(|input| invalid.contains(input))
+            {
+                trace!("Calculation '{}' reads an invalid value", id);
+                data.values.insert(id.clone(), f64::NAN);
+                if self.set.inputs.contains(id) {
+                    self.values.remove(id);
+                    invalid.insert(id.clone());
+                }
+                continue;
+            }
+
+            let value = calculation.expression.eval(&|input| {
+                self.values
+                    .get(input)
+                    .filter(|(_, received_at)| now.duration_since(*received_at) <= max_age)
+                    .map(|&(value, _)| value)
+            });
+            match value {
+                Some(value) if value.is_finite() => {
+                    data.values.insert(id.clone(), value);
+                    if self.set.inputs.contains(id) {
+                        self.values.insert(id.clone(), (value, now));
+                    }
+                }
+                Some(value) => {
+                    trace!("Calculation '{}' is not finite: {}", id, value);
+                    self.values.remove(id);
+                }
+                // Some inputs were not received yet, or are stale
+                None => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_AGE: Duration = Duration::from_secs(10);
+
+    fn calculator(calculations: &[(&str, &str)]) -> Calculator {
+        let configs = calculations
+            .iter()
+            .map(|(id, expression)| CalculationConfig {
+                id: id.to_string(),
+                expression: expression.to_string(),
+                unit: None,
+                graphical_id: None,
+                equipment_id: None,
+                side: None,
+                component_type: None,
+            })
+            .collect();
+        let set = CalculationSet::compile(configs, &[], None).unwrap();
+        Calculator::new(Arc::new(set))
+    }
+
+    fn data(values: &[(&str, f64)]) -> TelemetryData {
+        TelemetryData {
+            values: values
+                .iter()
+                .map(|(id, value)| (id.to_string(), *value))
+                .collect(),
+            time: 0,
+        }
+    }
+
+    #[test]
+    fn computes_with_the_last_value_of_each_input() {
+        let start = Instant::now();
+        let mut calculator = calculator(&[("sum", "a + b"), ("double", "2 * sum")]);
+
+        let mut first = data(&[("a", 1.0)]);
+        calculator.apply(&mut first, start, MAX_AGE);
+        assert!(!first.values.contains_key("sum"));
+
+        let mut second = data(&[("b", 2.0)]);
+        calculator.apply(&mut second, start + Duration::from_secs(1), MAX_AGE);
+        assert_eq!(second.values["sum"], 3.0);
+        assert_eq!(second.values["double"], 6.0);
+    }
+
+    #[test]
+    fn invalidates_the_points_derived_from_an_invalid_value() {
+        let start = Instant::now();
+        let mut calculator = calculator(&[("sum", "a + b"), ("double", "2 * sum")]);
+        calculator.apply(&mut data(&[("a", 1.0), ("b", 2.0)]), start, MAX_AGE);
+
+        let mut invalid = data(&[("a", f64::NAN)]);
+        calculator.apply(&mut invalid, start, MAX_AGE);
+        assert!(invalid.values["sum"].is_nan());
+        assert!(invalid.values["double"].is_nan());
+
+        // The previous value of the input is not used again
+        let mut next = data(&[("b", 3.0)]);
+        calculator.apply(&mut next, start, MAX_AGE);
+        assert!(!next.values.contains_key("sum"));
+
+        let mut valid = data(&[("a", 2.0)]);
+        calculator.apply(&mut valid, start, MAX_AGE);
+        assert_eq!(valid.values["sum"], 5.0);
+    }
+
+    #[test]
+    fn stops_computing_with_stale_inputs() {
+        let start = Instant::now();
+        let mut calculator = calculator(&[("sum", "a + b")]);
+        calculator.apply(&mut data(&[("a", 1.0), ("b", 2.0)]), start, MAX_AGE);
+
+        let mut fresh = data(&[("b", 3.0)]);
+        calculator.apply(&mut fresh, start + MAX_AGE, MAX_AGE);
+        assert_eq!(fresh.values["sum"], 4.0);
+
+        let mut stale = data(&[("b", 4.0)]);
+        calculator.apply(&mut stale, start + MAX_AGE * 2, MAX_AGE);
+        assert!(!stale.values.contains_key("sum"));
+    }
+}
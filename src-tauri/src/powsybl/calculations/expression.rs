@@ -0,0 +1,542 @@
+use std::fmt;
+
+/// Arithmetic over telemetry values and network attributes.
+///
+/// Telemetry ids are written bare when they only hold ASCII letters, digits, `_`
+/// and `.`, or between braces otherwise (`{GEN-1 P}`). Network attributes are read
+/// with `attr("element id", "attribute")`. Operators are `+ - * / ^` and the
+/// `²` suffix, with the usual precedence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    Variable(String),
+    Attribute { element: String, attribute: String },
+    Negate(Box<Expression>),
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+    Call(Function, Vec<Expression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Abs,
+    Sqrt,
+    Exp,
+    Ln,
+    Log10,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Hypot,
+    Pow,
+    Min,
+    Max,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "abs" => Function::Abs,
+            "sqrt" => Function::Sqrt,
+            "exp" => Function::Exp,
+            "ln" => Function::Ln,
+            "log10" => Function::Log10,
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "asin" => Function::Asin,
+            "acos" => Function::Acos,
+            "atan" => Function::Atan,
+            "atan2" => Function::Atan2,
+            "hypot" => Function::Hypot,
+            "pow" => Function::Pow,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            _ => return None,
+        })
+    }
+
+    /// Accepted argument counts, `None` for any count from one
+    fn arity(&self) -> Option<usize> {
+        match self {
+            Function::Atan2 | Function::Hypot | Function::Pow => Some(2),
+            Function::Min | Function::Max => None,
+            _ => Some(1),
+        }
+    }
+
+    fn apply(&self, args: &[f64]) -> f64 {
+        match self {
+            Function::Abs => args[0].abs(),
+            Function::Sqrt => args[0].sqrt(),
+            Function::Exp => args[0].exp(),
+            Function::Ln => args[0].ln(),
+            Function::Log10 => args[0].log10(),
+            Function::Sin => args[0].sin(),
+            Function::Cos => args[0].cos(),
+            Function::Tan => args[0].tan(),
+            Function::Asin => args[0].asin(),
+            Function::Acos => args[0].acos(),
+            Function::Atan => args[0].atan(),
+            Function::Atan2 => args[0].atan2(args[1]),
+            Function::Hypot => args[0].hypot(args[1]),
+            Function::Pow => args[0].powf(args[1]),
+            Function::Min => args.iter().copied().fold(f64::INFINITY, f64::min),
+            Function::Max => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+/// A syntax error and the character offset it was found at
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at character {}", self.message, self.position + 1)
+    }
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            position: 0,
+        };
+        let expression = parser.expression()?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("Unexpected character"));
+        }
+        Ok(expression)
+    }
+
+    /// Telemetry ids read by the expression, without duplicates
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables = Vec::new();
+        self.visit(&mut |expression| {
+            if let Expression::Variable(id) = expression {
+                if !variables.contains(id) {
+                    variables.push(id.clone());
+                }
+            }
+        });
+        variables
+    }
+
+    pub fn has_attributes(&self) -> bool {
+        let mut found = false;
+        self.visit(&mut |expression| {
+            found |= matches!(expression, Expression::Attribute { .. });
+        });
+        found
+    }
+
+    fn visit(&self, f: &mut impl FnMut(&Expression)) {
+        f(self);
+        match self {
+            Expression::Negate(operand) => operand.visit(f),
+            Expression::Binary(_, left, right) => {
+                left.visit(f);
+                right.visit(f);
+            }
+            Expression::Call(_, args) => args.iter().for_each(|arg| arg.visit(f)),
+            _ => {}
+        }
+    }
+
+    /// Replace the network attributes by their values, which no longer change
+    pub fn resolve_attributes<E>(
+        &mut self,
+        resolve: &impl Fn(&str, &str) -> Result<f64, E>,
+    ) -> Result<(), E> {
+        match self {
+            Expression::Attribute { element, attribute } => {
+                *self = Expression::Number(resolve(element, attribute)?);
+            }
+            Expression::Negate(operand) => operand.resolve_attributes(resolve)?,
+            Expression::Binary(_, left, right) => {
+                left.resolve_attributes(resolve)?;
+                right.resolve_attributes(resolve)?;
+            }
+            Expression::Call(_, args) => {
+                for arg in args {
+                    arg.resolve_attributes(resolve)?;
+                }
+            }
+            Expression::Number(_) | Expression::Variable(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Value of the expression, `None` when a variable or an attribute is unknown
+    pub fn eval(&self, values: &impl Fn(&str) -> Option<f64>) -> Option<f64> {
+        Some(match self {
+            Expression::Number(value) => *value,
+            Expression::Variable(id) => values(id)?,
+            Expression::Attribute { .. } => return None,
+            Expression::Negate(operand) => -operand.eval(values)?,
+            Expression::Binary(op, left, right) => {
+                let (left, right) = (left.eval(values)?, right.eval(values)?);
+                match op {
+                    BinaryOp::Add => left + right,
+                    BinaryOp::Subtract => left - right,
+                    BinaryOp::Multiply => left * right,
+                    BinaryOp::Divide => left / right,
+                    BinaryOp::Power => left.powf(right),
+                }
+            }
+            Expression::Call(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(values))
+                    .collect::<Option<Vec<f64>>>()?;
+                function.apply(&args)
+            }
+        })
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> ExpressionError {
+        ExpressionError {
+            position: self.position,
+            message: message.to_string(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    // Consume the next non-blank character if it is the expected one
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression, ExpressionError> {
+        let mut left = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Subtract
+            } else {
+                return Ok(left);
+            };
+            left = Expression::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expression, ExpressionError> {
+        let mut left = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                BinaryOp::Multiply
+            } else if self.eat('/') {
+                BinaryOp::Divide
+            } else {
+                return Ok(left);
+            };
+            left = Expression::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression, ExpressionError> {
+        if self.eat('-') {
+            return Ok(Expression::Negate(Box::new(self.unary()?)));
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    // Right associative, and binding tighter than a leading minus: -x^2 is -(x^2)
+    fn power(&mut self) -> Result<Expression, ExpressionError> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            let exponent = self.unary()?;
+            return Ok(Expression::Binary(
+                BinaryOp::Power,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        if self.eat('²') {
+            return Ok(Expression::Binary(
+                BinaryOp::Power,
+                Box::new(base),
+                Box::new(Expression::Number(2.0)),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expression, ExpressionError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let expression = self.expression()?;
+                if !self.eat(')') {
+                    return Err(self.error("Expected ')'"));
+                }
+                Ok(expression)
+            }
+            Some('{') => {
+                self.position += 1;
+                let start = self.position;
+                while self.peek().is_some_and(|c| c != '}') {
+                    self.position += 1;
+                }
+                if self.peek().is_none() {
+                    return Err(self.error("Expected '}'"));
+                }
+                let id: String = self.chars[start..self.position].iter().collect();
+                self.position += 1;
+                if id.trim().is_empty() {
+                    return Err(self.error("Empty telemetry id"));
+                }
+                Ok(Expression::Variable(id.trim().to_string()))
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let start = self.position;
+                let name = self.identifier();
+                if !self.eat('(') {
+                    return Ok(Expression::Variable(name));
+                }
+                if name == "attr" {
+                    return self.attribute();
+                }
+                let function = Function::from_name(&name).ok_or_else(|| ExpressionError {
+                    position: start,
+                    message: format!("Unknown function '{}'", name),
+                })?;
+                let args = self.arguments()?;
+                match function.arity() {
+                    Some(arity) if arity != args.len() => Err(self.error(&format!(
+                        "'{}' takes {} argument{}",
+                        name,
+                        arity,
+                        if arity > 1 { "s" } else { "" }
+                    ))),
+                    None if args.is_empty() => {
+                        Err(self.error(&format!("'{}' takes at least one argument", name)))
+                    }
+                    _ => Ok(Expression::Call(function, args)),
+                }
+            }
+            Some(_) => Err(self.error("Unexpected character")),
+            None => Err(self.error("Unexpected end of expression")),
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn number(&mut self) -> Result<Expression, ExpressionError> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.position += 1;
+        }
+        // Exponent, as in 1e-3
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.position += 1;
+            }
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.position = mantissa_end;
+            }
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.position += 1;
+            }
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        text.parse()
+            .map(Expression::Number)
+            .map_err(|_| ExpressionError {
+                position: start,
+                message: format!("Invalid number '{}'", text),
+            })
+    }
+
+    // Arguments of a call, after its opening parenthesis
+    fn arguments(&mut self) -> Result<Vec<Expression>, ExpressionError> {
+        let mut args = Vec::new();
+        if self.eat(')') {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expression()?);
+            if self.eat(')') {
+                return Ok(args);
+            }
+            if !self.eat(',') {
+                return Err(self.error("Expected ',' or ')'"));
+            }
+        }
+    }
+
+    fn attribute(&mut self) -> Result<Expression, ExpressionError> {
+        let element = self.string()?;
+        if !self.eat(',') {
+            return Err(self.error("Expected ','"));
+        }
+        let attribute = self.string()?;
+        if !self.eat(')') {
+            return Err(self.error("Expected ')'"));
+        }
+        Ok(Expression::Attribute { element, attribute })
+    }
+
+    fn string(&mut self) -> Result<String, ExpressionError> {
+        self.skip_whitespace();
+        let Some(quote @ ('"' | '\'')) = self.peek() else {
+            return Err(self.error("Expected a quoted string"));
+        };
+        self.position += 1;
+        let start = self.position;
+        while self.peek().is_some_and(|c| c != quote) {
+            self.position += 1;
+        }
+        if self.peek().is_none() {
+            return Err(self.error("Unterminated string"));
+        }
+        let value = self.chars[start..self.position].iter().collect();
+        self.position += 1;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str) -> f64 {
+        let values = |id: &str| match id {
+            "x" => Some(3.0),
+            "GEN-1 P" => Some(10.0),
+            "load.p" => Some(-2.0),
+            _ => None,
+        };
+        Expression::parse(source)
+            .unwrap_or_else(|e| panic!("{}: {}", source, e))
+            .eval(&values)
+            .unwrap()
+    }
+
+    fn error(source: &str) -> ExpressionError {
+        Expression::parse(source).unwrap_err()
+    }
+
+    #[test]
+    fn applies_the_usual_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7.0);
+        assert_eq!(eval("(1 + 2) * 3"), 9.0);
+        assert_eq!(eval("10 - 4 - 3"), 3.0);
+        assert_eq!(eval("12 / 3 / 2"), 2.0);
+        assert_eq!(eval("2 * x ^ 2"), 18.0);
+    }
+
+    #[test]
+    fn powers_are_right_associative_and_bind_tighter_than_minus() {
+        assert_eq!(eval("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(eval("-x ^ 2"), -9.0);
+        assert_eq!(eval("(-x) ^ 2"), 9.0);
+        assert_eq!(eval("2 ^ -1"), 0.5);
+    }
+
+    #[test]
+    fn squares_with_the_superscript_suffix() {
+        assert_eq!(eval("x²"), 9.0);
+        assert_eq!(eval("2 * x² + 1"), 19.0);
+        assert_eq!(eval("-x²"), -9.0);
+        assert_eq!(eval("(x + 1)²"), 16.0);
+    }
+
+    #[test]
+    fn reads_braced_and_dotted_telemetry_ids() {
+        assert_eq!(eval("{GEN-1 P} + load.p"), 8.0);
+        assert_eq!(eval("{ GEN-1 P }"), 10.0);
+        assert_eq!(
+            Expression::parse("{GEN-1 P} * x + x").unwrap().variables(),
+            ["GEN-1 P", "x"]
+        );
+    }
+
+    #[test]
+    fn evaluates_functions_and_numbers() {
+        assert_eq!(eval("max(1, x, 2)"), 3.0);
+        assert_eq!(eval("hypot(3, 4)"), 5.0);
+        assert_eq!(eval("abs(load.p) + 1e-3 * 1E3"), 3.0);
+    }
+
+    #[test]
+    fn unknown_variables_have_no_value() {
+        let expression = Expression::parse("x + unknown").unwrap();
+        assert_eq!(expression.eval(&|_| None), None);
+    }
+
+    #[test]
+    fn reports_the_position_of_syntax_errors() {
+        assert_eq!(error("(1 + 2").message, "Expected ')'");
+        assert_eq!(error("{GEN-1 P").message, "Expected '}'");
+        assert_eq!(error("{ }").message, "Empty telemetry id");
+        assert_eq!(error("1 + ").message, "Unexpected end of expression");
+
+        let unknown = error("2 * foo(x)");
+        assert_eq!(unknown.message, "Unknown function 'foo'");
+        assert_eq!(unknown.position, 4);
+
+        let trailing = error("x y");
+        assert_eq!(trailing.message, "Unexpected character");
+        assert_eq!(trailing.position, 2);
+
+        assert_eq!(error("hypot(1)").message, "'hypot' takes 2 arguments");
+        assert_eq!(error("min()").message, "'min' takes at least one argument");
+    }
+}
@@ -0,0 +1,147 @@
+use super::comparison::fetch_network_elements;
+use super::errors::{PowsyblError, PowsyblResult};
+use super::quality::QualityMonitor;
+use super::resolve_network_id;
+
+use crate::{
+    database::DatabaseState,
+    settings::{
+        config::{describe_problems, validation},
+        get_setting,
+    },
+    state::AppState,
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+
+mod calculator;
+mod expression;
+
+pub use calculator::{CalculationSet, Calculator};
+pub use expression::ExpressionError;
+
+use expression::Expression;
+
+/// A derived point, defined in the `[[calculations]]` tables of the configuration
+/// file.
+///
+/// The expression reads telemetry ids and network attributes, e.g.
+/// `sqrt(GEN1_P² + GEN1_Q²)` or `100 * LINE1_I / attr("LINE1", "limit_current_one_permanent")`.
+/// The other fields describe the point like a game master output, so that it can
+/// be shown on diagrams and checked against limits.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalculationConfig {
+    pub id: String,
+    pub expression: String,
+    #[serde(default)]
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub graphical_id: Option<String>,
+    #[serde(default)]
+    pub equipment_id: Option<String>,
+    #[serde(default)]
+    pub side: Option<String>,
+    #[serde(default)]
+    pub component_type: Option<String>,
+}
+
+impl CalculationConfig {
+    /// Check the syntax of the expression, without resolving its inputs
+    pub fn parse_expression(&self) -> Result<(), ExpressionError> {
+        Expression::parse(&self.expression).map(|_| ())
+    }
+}
+
+// Path of the configuration file loaded last
+async fn configured_file(db_state: &State<'_, DatabaseState>) -> PowsyblResult<String> {
+    let state = db_state.lock().await;
+    get_setting(&state.pool, "config")
+        .await
+        .map_err(|e| PowsyblError::CalculationError(e.to_string()))?
+        .and_then(|config| config["config_file"].as_str().map(str::to_string))
+        .ok_or_else(|| PowsyblError::CalculationError("No configuration file loaded".to_string()))
+}
+
+/// Load the calculations of a configuration file, the loaded one by default, and
+/// start computing them on every telemetry feed.
+///
+/// Network attributes are read from the given network, the active one by default.
+/// Nothing is replaced when a calculation is invalid; the error lists every problem.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn load_calculations(
+    state: State<'_, AppState>,
+    db_state: State<'_, DatabaseState>,
+    quality: State<'_, QualityMonitor>,
+    config_path: Option<String>,
+    network_id: Option<String>,
+) -> PowsyblResult<Vec<CalculationConfig>> {
+    let config_path = match config_path {
+        Some(path) => path,
+        None => configured_file(&db_state).await?,
+    };
+    let content = tokio::fs::read_to_string(&config_path).await?;
+    let calculations = validation::parse(&content)
+        .map_err(|problems| {
+            PowsyblError::CalculationError(describe_problems(&config_path, &problems))
+        })?
+        .calculations();
+
+    // The sidecar is only needed for network attributes
+    let mut network = None;
+    let reads_network = calculations.iter().any(|calculation| {
+        Expression::parse(&calculation.expression).is_ok_and(|e| e.has_attributes())
+    });
+    if reads_network {
+        let network_id = resolve_network_id(&state, network_id)?;
+        network = Some(fetch_network_elements(None, network_id.as_deref()).await?);
+    }
+
+    let outputs = state
+        .read()
+        .map_err(|_| PowsyblError::LockError)?
+        .settings
+        .game_master_outputs
+        .clone()
+        .unwrap_or_default();
+    let set = CalculationSet::compile(calculations, &outputs, network.as_ref())
+        .map_err(PowsyblError::CalculationError)?;
+    let configs = set.configs();
+    quality.set_outputs(outputs.iter().chain(set.outputs()));
+
+    state
+        .write()
+        .map_err(|_| PowsyblError::LockError)?
+        .powsybl
+        .calculations = Arc::new(set);
+
+    info!("Loaded {} calculations from {}", configs.len(), config_path);
+    Ok(configs)
+}
+
+/// Loaded calculations, in evaluation order
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_calculations(state: State<'_, AppState>) -> PowsyblResult<Vec<CalculationConfig>> {
+    Ok(state
+        .read()
+        .map_err(|_| PowsyblError::LockError)?
+        .powsybl
+        .calculations
+        .configs())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn clear_calculations(
+    state: State<'_, AppState>,
+    quality: State<'_, QualityMonitor>,
+) -> PowsyblResult<()> {
+    let mut state = state.write().map_err(|_| PowsyblError::LockError)?;
+    state.powsybl.calculations = Arc::default();
+    if let Some(outputs) = &state.settings.game_master_outputs {
+        quality.set_outputs(outputs);
+    }
+    info!("Calculations cleared");
+    Ok(())
+}
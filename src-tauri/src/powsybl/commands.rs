@@ -1,3 +1,26 @@
+pub use super::alarms::{
+    acknowledge_alarms, clear_alarm_history, get_active_alarms, get_alarm_engine_status,
+    get_alarm_history, start_alarm_engine, stop_alarm_engine,
+};
+pub use super::balance::{
+    get_balance_report, get_balance_status, start_balance_checks, stop_balance_checks,
+};
+pub use super::cache::{clear_query_cache, get_query_cache_stats};
+pub use super::calculations::{clear_calculations, get_calculations, load_calculations};
+pub use super::comparison::*;
 pub use super::diagrams::*;
+pub use super::historian::{
+    clear_historian, export_historian, get_historian_status, query_historian,
+    query_historian_trend, start_historian, stop_historian,
+};
+pub use super::networks::*;
+pub use super::quality::{
+    get_measurement_quality, get_quality_config, get_quality_summary, set_quality_config,
+};
 pub use super::substations::*;
+pub use super::switching::{
+    clear_switch_events, get_switch_events, get_switch_log_status, start_switch_log,
+    stop_switch_log,
+};
+pub use super::topology::*;
 pub use super::voltage_levels::*;
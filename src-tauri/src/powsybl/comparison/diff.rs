@@ -0,0 +1,308 @@
+use super::super::errors::{PowsyblError, PowsyblResult};
+use super::entities::{
+    AttributeChange, ChangeKind, ComparisonSummary, ElementChange, NetworkComparison,
+    NetworkElement, NetworkElements,
+};
+
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Relative tolerance under which two numeric attributes are considered equal
+const NUMERIC_TOLERANCE: f64 = 1e-9;
+
+pub fn diff_networks(
+    reference_label: &str,
+    reference: &NetworkElements,
+    candidate_label: &str,
+    candidate: &NetworkElements,
+) -> NetworkComparison {
+    let reference_elements = index_elements(reference);
+    let candidate_elements = index_elements(candidate);
+
+    let mut summary = ComparisonSummary::default();
+    let mut changes = Vec::new();
+
+    for (key, element) in &reference_elements {
+        match candidate_elements.get(key) {
+            None => {
+                summary.removed += 1;
+                changes.push(ElementChange {
+                    id: element.id.clone(),
+                    element_type: element.element_type.clone(),
+                    change: ChangeKind::Removed,
+                    attributes: Vec::new(),
+                });
+            }
+            Some(other) => {
+                let attributes = compare_attributes(element, other);
+                if attributes.is_empty() {
+                    summary.unchanged += 1;
+                } else {
+                    summary.modified += 1;
+                    changes.push(ElementChange {
+                        id: element.id.clone(),
+                        element_type: element.element_type.clone(),
+                        change: ChangeKind::Modified,
+                        attributes,
+                    });
+                }
+            }
+        }
+    }
+
+    for (key, element) in &candidate_elements {
+        if !reference_elements.contains_key(key) {
+            summary.added += 1;
+            changes.push(ElementChange {
+                id: element.id.clone(),
+                element_type: element.element_type.clone(),
+                change: ChangeKind::Added,
+                attributes: Vec::new(),
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| {
+        a.element_type
+            .cmp(&b.element_type)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    NetworkComparison {
+        reference: reference_label.to_string(),
+        candidate: candidate_label.to_string(),
+        summary,
+        changes,
+    }
+}
+
+fn index_elements(elements: &NetworkElements) -> BTreeMap<(&str, &str), &NetworkElement> {
+    elements
+        .elements
+        .iter()
+        .map(|element| {
+            (
+                (element.element_type.as_str(), element.id.as_str()),
+                element,
+            )
+        })
+        .collect()
+}
+
+fn compare_attributes(
+    reference: &NetworkElement,
+    candidate: &NetworkElement,
+) -> Vec<AttributeChange> {
+    let names: BTreeSet<&String> = reference
+        .attributes
+        .keys()
+        .chain(candidate.attributes.keys())
+        .collect();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let before = reference.attributes.get(name).unwrap_or(&Value::Null);
+            let after = candidate.attributes.get(name).unwrap_or(&Value::Null);
+
+            (!values_equal(before, after)).then(|| AttributeChange {
+                attribute: name.clone(),
+                reference: before.clone(),
+                candidate: after.clone(),
+            })
+        })
+        .collect()
+}
+
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => {
+            let scale = x.abs().max(y.abs()).max(1.0);
+            (x - y).abs() <= NUMERIC_TOLERANCE * scale
+        }
+        _ => a == b,
+    }
+}
+
+/// Flatten a comparison into one CSV row per changed attribute
+pub fn comparison_to_csv(comparison: &NetworkComparison) -> PowsyblResult<Vec<u8>> {
+    let mut csv = csv::Writer::from_writer(Vec::new());
+    csv.write_record([
+        "change",
+        "element_type",
+        "element_id",
+        "attribute",
+        "reference",
+        "candidate",
+    ])
+    .map_err(csv_error)?;
+
+    for change in &comparison.changes {
+        let kind = match change.change {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+        };
+
+        if change.attributes.is_empty() {
+            csv.write_record([kind, &change.element_type, &change.id, "", "", ""])
+                .map_err(csv_error)?;
+            continue;
+        }
+
+        for attribute in &change.attributes {
+            let reference = value_to_field(&attribute.reference);
+            let candidate = value_to_field(&attribute.candidate);
+            csv.write_record([
+                kind,
+                &change.element_type,
+                &change.id,
+                &attribute.attribute,
+                &reference,
+                &candidate,
+            ])
+            .map_err(csv_error)?;
+        }
+    }
+
+    csv.into_inner()
+        .map_err(|e| PowsyblError::ExportError(e.to_string()))
+}
+
+fn csv_error(e: csv::Error) -> PowsyblError {
+    PowsyblError::ExportError(e.to_string())
+}
+
+fn value_to_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn elements(value: Value) -> NetworkElements {
+        serde_json::from_value(json!({ "elements": value })).unwrap()
+    }
+
+    #[test]
+    fn values_equal_within_relative_tolerance() {
+        assert!(values_equal(&json!(400.0), &json!(400)));
+        assert!(values_equal(&json!(1e6), &json!(1e6 + 1e-4)));
+        assert!(values_equal(&json!(0.0), &json!(1e-10)));
+        assert!(!values_equal(&json!(1e6), &json!(1e6 + 1.0)));
+        assert!(!values_equal(&json!(0.0), &json!(1e-8)));
+    }
+
+    #[test]
+    fn values_equal_compares_other_values_exactly() {
+        assert!(values_equal(&json!("VL1"), &json!("VL1")));
+        assert!(!values_equal(&json!("VL1"), &json!("VL2")));
+        assert!(!values_equal(&json!("1"), &json!(1)));
+        assert!(!values_equal(&json!(true), &Value::Null));
+        assert!(values_equal(&Value::Null, &Value::Null));
+    }
+
+    #[test]
+    fn diff_networks_lists_every_change_by_type_and_id() {
+        let reference = elements(json!([
+            {"id": "L1", "element_type": "LINE", "attributes": {"r": 1.0, "x": 10.0}},
+            {"id": "L2", "element_type": "LINE", "attributes": {"r": 2.0}},
+            {"id": "G1", "element_type": "GENERATOR", "attributes": {"target_p": 100.0}},
+            {"id": "X", "element_type": "LOAD"},
+        ]));
+        let candidate = elements(json!([
+            {"id": "L1", "element_type": "LINE", "attributes": {"r": 1.0, "x": 12.0, "b": 0.1}},
+            {"id": "G1", "element_type": "GENERATOR", "attributes": {"target_p": 100}},
+            {"id": "X", "element_type": "GENERATOR"},
+            {"id": "L3", "element_type": "LINE"},
+        ]));
+
+        let comparison = diff_networks("before.xiidm", &reference, "after.xiidm", &candidate);
+
+        assert_eq!(comparison.reference, "before.xiidm");
+        assert_eq!(comparison.candidate, "after.xiidm");
+        let summary = &comparison.summary;
+        assert_eq!(
+            (
+                summary.added,
+                summary.removed,
+                summary.modified,
+                summary.unchanged
+            ),
+            (2, 2, 1, 1)
+        );
+
+        // An id reused by another type of element is a different element
+        let changes: Vec<(&str, &str, ChangeKind)> = comparison
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    change.element_type.as_str(),
+                    change.id.as_str(),
+                    change.change,
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("GENERATOR", "X", ChangeKind::Added),
+                ("LINE", "L1", ChangeKind::Modified),
+                ("LINE", "L2", ChangeKind::Removed),
+                ("LINE", "L3", ChangeKind::Added),
+                ("LOAD", "X", ChangeKind::Removed),
+            ]
+        );
+
+        // Missing attributes compare as null
+        let attributes: Vec<(&str, &Value, &Value)> = comparison.changes[1]
+            .attributes
+            .iter()
+            .map(|change| {
+                (
+                    change.attribute.as_str(),
+                    &change.reference,
+                    &change.candidate,
+                )
+            })
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("b", &Value::Null, &json!(0.1)),
+                ("x", &json!(10.0), &json!(12.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn comparison_to_csv_writes_a_row_per_attribute() {
+        let comparison = diff_networks(
+            "a",
+            &elements(json!([
+                {"id": "L1", "element_type": "LINE", "attributes": {"name": "A, \"B\"", "x": 1.0}},
+            ])),
+            "b",
+            &elements(json!([
+                {"id": "L1", "element_type": "LINE", "attributes": {"name": "C", "x": 2.0}},
+                {"id": "L2", "element_type": "LINE"},
+            ])),
+        );
+
+        let csv = String::from_utf8(comparison_to_csv(&comparison).unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "change,element_type,element_id,attribute,reference,candidate\n\
+             modified,LINE,L1,name,\"A, \"\"B\"\"\",C\n\
+             modified,LINE,L1,x,1.0,2.0\n\
+             added,LINE,L2,,,\n"
+        );
+    }
+}
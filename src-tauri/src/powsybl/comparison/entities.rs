@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Comparable elements of a network as returned by the sidecar
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkElements {
+    pub elements: Vec<NetworkElement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkElement {
+    pub id: String,
+    pub element_type: String,
+    #[serde(default)]
+    pub attributes: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Difference of a single attribute between the reference and the candidate network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributeChange {
+    pub attribute: String,
+    pub reference: Value,
+    pub candidate: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementChange {
+    pub id: String,
+    pub element_type: String,
+    pub change: ChangeKind,
+    #[serde(default)]
+    pub attributes: Vec<AttributeChange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComparisonSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub unchanged: usize,
+}
+
+/// Result of comparing a candidate network against a reference network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkComparison {
+    pub reference: String,
+    pub candidate: String,
+    pub summary: ComparisonSummary,
+    pub changes: Vec<ElementChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComparisonFormat {
+    Json,
+    Csv,
+}
@@ -0,0 +1,91 @@
+use super::entities::FetchStatus;
+use super::errors::{PowsyblError, PowsyblResult};
+use super::{encode_file, resolve_network_id, send_zmq_request};
+
+use crate::state::AppState;
+
+use serde_json::json;
+use tauri::State;
+
+mod diff;
+mod entities;
+
+pub use entities::{ComparisonFormat, NetworkComparison};
+
+pub(super) use entities::{NetworkElement, NetworkElements};
+
+use diff::{comparison_to_csv, diff_networks};
+
+const LOADED_NETWORK_LABEL: &str = "loaded network";
+
+/// Fetch the comparable elements of an IIDM file, or of a loaded network when no path is given
+pub(super) async fn fetch_network_elements(
+    path: Option<&str>,
+    network_id: Option<&str>,
+) -> PowsyblResult<NetworkElements> {
+    let params = match path {
+        Some(path) => {
+            let (encoded, filename) = encode_file(path)?;
+            json!({
+                "file_data": encoded,
+                "filename": filename
+            })
+        }
+        None => json!({
+            "network_id": network_id
+        }),
+    };
+
+    let result = send_zmq_request("get_network_elements", Some(params)).await?;
+    serde_json::from_value(result).map_err(|e| PowsyblError::JsonParseError(e.to_string()))
+}
+
+/// Compare a candidate IIDM file against a reference file, or against a loaded network
+#[tauri::command(rename_all = "snake_case")]
+pub async fn compare_networks(
+    state: State<'_, AppState>,
+    candidate_path: String,
+    reference_path: Option<String>,
+    network_id: Option<String>,
+) -> PowsyblResult<NetworkComparison> {
+    let network_id = resolve_network_id(&state, network_id)?;
+    let reference =
+        fetch_network_elements(reference_path.as_deref(), network_id.as_deref()).await?;
+    let candidate = fetch_network_elements(Some(&candidate_path), None).await?;
+
+    let reference_label = reference_path
+        .as_deref()
+        .or(network_id.as_deref())
+        .unwrap_or(LOADED_NETWORK_LABEL);
+
+    Ok(diff_networks(
+        reference_label,
+        &reference,
+        &candidate_path,
+        &candidate,
+    ))
+}
+
+/// Write a network comparison to a JSON or CSV file
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_network_comparison(
+    comparison: NetworkComparison,
+    format: ComparisonFormat,
+    output_path: String,
+) -> PowsyblResult<FetchStatus> {
+    let content = match format {
+        ComparisonFormat::Json => serde_json::to_vec_pretty(&comparison)?,
+        ComparisonFormat::Csv => comparison_to_csv(&comparison)?,
+    };
+
+    tokio::fs::write(&output_path, content).await?;
+
+    Ok(FetchStatus {
+        success: true,
+        message: format!(
+            "Exported {} changes to {}",
+            comparison.changes.len(),
+            output_path
+        ),
+    })
+}
@@ -0,0 +1,432 @@
+use super::super::errors::{PowsyblError, PowsyblResult};
+use super::sld_metadata::{ComponentType, FeederInfoType, SldMetadata, SvgParams};
+use super::sld_topology::SldTopology;
+
+use quick_xml::events::{BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Per-unit voltage under which a node is coloured as low
+const LOW_VOLTAGE_PU: f64 = 0.95;
+
+/// Per-unit voltage above which a node is coloured as high
+const HIGH_VOLTAGE_PU: f64 = 1.05;
+
+/// Live values displayed on a diagram
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelemetrySnapshot {
+    /// Measurement of each feeder info, by feeder info id (the graphical id)
+    #[serde(default)]
+    pub values: HashMap<String, f64>,
+
+    /// Open state of each switch, by node id or equipment id
+    #[serde(default)]
+    pub switches: HashMap<String, bool>,
+
+    /// Per-unit voltage, by node id or voltage level id
+    #[serde(default)]
+    pub voltages: HashMap<String, f64>,
+
+    /// Live state of external feeders, by equipment id or node id
+    #[serde(default)]
+    pub sources: HashMap<String, bool>,
+}
+
+/// Changes applied to a single SVG element
+#[derive(Debug, Default)]
+struct ElementAnnotation {
+    equipment_id: Option<String>,
+    classes: Vec<&'static str>,
+    label: Option<String>,
+    arrow_out: Option<bool>,
+}
+
+/// Inject equipment ids, state classes and formatted measurements into an SLD SVG.
+///
+/// Elements are matched on the ids of `metadata.nodes` and `metadata.feeder_infos`.
+/// Feeder values are formatted with the precisions and units of `metadata.svg_params`
+/// and written into the first `<text>` of the feeder info. Values missing from the
+/// snapshot leave the label untouched and add the `argus-value-missing` class.
+/// Nodes and wires also get the `argus-energized`, `argus-dead` or `argus-earthed`
+/// class from the live topology.
+pub fn annotate_svg(
+    svg: &str,
+    metadata: &SldMetadata,
+    snapshot: &TelemetrySnapshot,
+) -> PowsyblResult<String> {
+    let annotations = build_annotations(metadata, snapshot);
+    let prefix = metadata.svg_params.prefix_id.as_str();
+
+    let mut reader = Reader::from_str(svg);
+    let mut writer = Writer::new(Vec::with_capacity(svg.len()));
+
+    let mut depth = 0usize;
+    // Depth of the feeder info being rewritten and its formatted value
+    let mut label: Option<(usize, &str)> = None;
+    let mut in_label_text = false;
+    let mut label_written = false;
+
+    loop {
+        let event = reader.read_event().map_err(svg_error)?;
+        match event {
+            Event::Start(element) => {
+                depth += 1;
+                let annotation = find_annotation(&annotations, &element, prefix)?;
+
+                if let Some(text) = annotation.and_then(|a| a.label.as_deref()) {
+                    label = Some((depth, text));
+                    label_written = false;
+                }
+                if label.is_some() && !label_written && element.name().as_ref() == b"text" {
+                    in_label_text = true;
+                }
+
+                let element = match annotation {
+                    Some(annotation) => annotate_element(&element, annotation)?,
+                    None => element,
+                };
+                writer.write_event(Event::Start(element))?;
+            }
+            Event::Empty(element) => {
+                let element = match find_annotation(&annotations, &element, prefix)? {
+                    Some(annotation) => annotate_element(&element, annotation)?,
+                    None => element,
+                };
+                writer.write_event(Event::Empty(element))?;
+            }
+            // The original label value is replaced
+            Event::Text(_) if in_label_text => {
+                if let (false, Some((_, value))) = (label_written, label) {
+                    writer.write_event(Event::Text(BytesText::new(value)))?;
+                    label_written = true;
+                }
+            }
+            Event::End(element) => {
+                if in_label_text && element.name().as_ref() == b"text" {
+                    if let (false, Some((_, value))) = (label_written, label) {
+                        writer.write_event(Event::Text(BytesText::new(value)))?;
+                    }
+                    in_label_text = false;
+                    label_written = true;
+                }
+                if matches!(label, Some((label_depth, _)) if label_depth == depth) {
+                    label = None;
+                }
+                depth = depth.saturating_sub(1);
+                writer.write_event(Event::End(element))?;
+            }
+            Event::Eof => break,
+            other => writer.write_event(other)?,
+        }
+    }
+
+    String::from_utf8(writer.into_inner())
+        .map_err(|e| PowsyblError::SvgError(e.utf8_error().to_string()))
+}
+
+fn build_annotations(
+    metadata: &SldMetadata,
+    snapshot: &TelemetrySnapshot,
+) -> HashMap<String, ElementAnnotation> {
+    let mut annotations: HashMap<String, ElementAnnotation> = HashMap::new();
+
+    for node in &metadata.nodes {
+        let annotation = annotations.entry(node.id.clone()).or_default();
+        annotation.equipment_id = node.equipment_id.clone();
+
+        let is_switch = node
+            .component_type
+            .as_ref()
+            .is_some_and(ComponentType::is_switch);
+        if is_switch {
+            let open = node
+                .equipment_id
+                .as_ref()
+                .and_then(|id| snapshot.switches.get(id))
+                .or_else(|| snapshot.switches.get(&node.id))
+                .copied()
+                .unwrap_or(node.open);
+            annotation.classes.push(if open {
+                "argus-switch-open"
+            } else {
+                "argus-switch-closed"
+            });
+        }
+
+        let voltage = snapshot
+            .voltages
+            .get(&node.id)
+            .or_else(|| snapshot.voltages.get(&node.vid));
+        if let Some(&voltage) = voltage {
+            annotation.classes.push(voltage_class(voltage));
+        }
+    }
+
+    for feeder in &metadata.feeder_infos {
+        let annotation = annotations.entry(feeder.id.clone()).or_default();
+        annotation.equipment_id = Some(feeder.equipment_id.clone());
+
+        match snapshot.values.get(&feeder.id) {
+            Some(&value) => {
+                annotation.label = Some(format_value(
+                    &metadata.svg_params,
+                    &feeder.component_type,
+                    value,
+                ));
+                if !matches!(feeder.component_type, FeederInfoType::Unknown(_)) {
+                    annotation.arrow_out = Some(value > 0.0);
+                }
+            }
+            None => annotation.classes.push("argus-value-missing"),
+        }
+    }
+
+    let states = SldTopology::build(metadata).evaluate(&snapshot.switches, &snapshot.sources);
+    for (id, state) in states {
+        annotations
+            .entry(id)
+            .or_default()
+            .classes
+            .push(state.css_class());
+    }
+
+    annotations
+}
+
+fn voltage_class(voltage_pu: f64) -> &'static str {
+    if voltage_pu < LOW_VOLTAGE_PU {
+        "argus-voltage-low"
+    } else if voltage_pu > HIGH_VOLTAGE_PU {
+        "argus-voltage-high"
+    } else {
+        "argus-voltage-normal"
+    }
+}
+
+/// Format a feeder value with the precision and unit of its kind
+pub fn format_value(svg_params: &SvgParams, component_type: &FeederInfoType, value: f64) -> String {
+    let (precision, unit) = match component_type {
+        FeederInfoType::ArrowReactive => (
+            svg_params.power_value_precision,
+            &svg_params.reactive_power_unit,
+        ),
+        FeederInfoType::ArrowCurrent => {
+            (svg_params.current_value_precision, &svg_params.current_unit)
+        }
+        _ => (
+            svg_params.power_value_precision,
+            &svg_params.active_power_unit,
+        ),
+    };
+
+    let precision = precision.max(0) as usize;
+    if unit.is_empty() {
+        format!("{:.*}", precision, value)
+    } else {
+        format!("{:.*} {}", precision, value, unit)
+    }
+}
+
+fn find_annotation<'a>(
+    annotations: &'a HashMap<String, ElementAnnotation>,
+    element: &BytesStart,
+    prefix: &str,
+) -> PowsyblResult<Option<&'a ElementAnnotation>> {
+    let Some(id) = element.try_get_attribute("id").map_err(svg_error)? else {
+        return Ok(None);
+    };
+    let id = id.unescape_value().map_err(svg_error)?;
+
+    Ok(annotations.get(id.as_ref()).or_else(|| {
+        id.strip_prefix(prefix)
+            .filter(|_| !prefix.is_empty())
+            .and_then(|id| annotations.get(id))
+    }))
+}
+
+fn annotate_element(
+    element: &BytesStart,
+    annotation: &ElementAnnotation,
+) -> PowsyblResult<BytesStart<'static>> {
+    let name = std::str::from_utf8(element.name().as_ref())?.to_string();
+    let mut annotated = BytesStart::new(name);
+    let mut classes: Vec<String> = Vec::new();
+
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(svg_error)?;
+        match attribute.key.as_ref() {
+            b"class" => {
+                let value = attribute.unescape_value().map_err(svg_error)?;
+                classes.extend(value.split_whitespace().map(str::to_string));
+            }
+            b"data-equipment-id" => {}
+            _ => annotated.push_attribute(attribute),
+        }
+    }
+
+    if let Some(arrow_out) = annotation.arrow_out {
+        classes.retain(|class| class != "sld-in" && class != "sld-out");
+        classes.push(if arrow_out { "sld-out" } else { "sld-in" }.to_string());
+    }
+    for class in &annotation.classes {
+        if !classes.iter().any(|c| c == class) {
+            classes.push(class.to_string());
+        }
+    }
+
+    if !classes.is_empty() {
+        annotated.push_attribute(("class", classes.join(" ").as_str()));
+    }
+    if let Some(equipment_id) = &annotation.equipment_id {
+        annotated.push_attribute(("data-equipment-id", equipment_id.as_str()));
+    }
+
+    Ok(annotated)
+}
+
+fn svg_error(error: impl std::fmt::Display) -> PowsyblError {
+    PowsyblError::SvgError(error.to_string())
+}
+
+/// Annotate a single line diagram SVG with a telemetry snapshot
+#[tauri::command(rename_all = "snake_case")]
+pub fn annotate_single_line_diagram(
+    svg: String,
+    metadata: SldMetadata,
+    snapshot: TelemetrySnapshot,
+) -> PowsyblResult<String> {
+    annotate_svg(&svg, &metadata, &snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P1_SVG: &str = include_str!("../../../../tests/diagrams/P1_diagram.svg");
+    const P1_METADATA: &str = include_str!("../../../../tests/diagrams/P1_metadata.json");
+
+    fn p1_metadata() -> SldMetadata {
+        serde_json::from_str(P1_METADATA).unwrap()
+    }
+
+    /// Opening tag of the element with the given id
+    fn tag<'a>(svg: &'a str, id: &str) -> &'a str {
+        let at = svg.find(&format!("id=\"{}\"", id)).unwrap();
+        let start = svg[..at].rfind('<').unwrap();
+        let end = at + svg[at..].find('>').unwrap();
+        &svg[start..=end]
+    }
+
+    /// Text of the first `<text>` after the element with the given id
+    fn label<'a>(svg: &'a str, id: &str) -> &'a str {
+        let at = svg.find(&format!("id=\"{}\"", id)).unwrap();
+        let text = at + svg[at..].find("<text").unwrap();
+        let start = text + svg[text..].find('>').unwrap() + 1;
+        let end = start + svg[start..].find("</text>").unwrap();
+        &svg[start..end]
+    }
+
+    fn classes(tag: &str) -> Vec<&str> {
+        let start = tag.find("class=\"").unwrap() + "class=\"".len();
+        let end = start + tag[start..].find('"').unwrap();
+        tag[start..end].split_whitespace().collect()
+    }
+
+    #[test]
+    fn replaces_feeder_labels_with_formatted_values() {
+        let snapshot = TelemetrySnapshot {
+            values: HashMap::from([
+                ("idGEN_ARROW_REACTIVE".to_string(), 42.4),
+                ("idGEN_ARROW_CURRENT".to_string(), -1250.0),
+            ]),
+            ..Default::default()
+        };
+
+        let svg = annotate_svg(P1_SVG, &p1_metadata(), &snapshot).unwrap();
+
+        assert_eq!(label(&svg, "idGEN_ARROW_REACTIVE"), "42");
+        assert_eq!(label(&svg, "idGEN_ARROW_CURRENT"), "-1250");
+        // The polygons before the text are kept
+        assert_eq!(svg.matches("class=\"sld-arrow-out\"").count(), 15);
+    }
+
+    #[test]
+    fn missing_values_keep_their_label() {
+        let svg = annotate_svg(P1_SVG, &p1_metadata(), &TelemetrySnapshot::default()).unwrap();
+
+        let id = "idNGEN_95_NHV1_95_TWO_ARROW_CURRENT";
+        assert_eq!(label(&svg, id), "916");
+        assert!(classes(tag(&svg, id)).contains(&"argus-value-missing"));
+    }
+
+    #[test]
+    fn merges_classes_with_the_existing_ones() {
+        let snapshot = TelemetrySnapshot {
+            values: HashMap::from([("idGEN_ARROW_REACTIVE".to_string(), 42.0)]),
+            voltages: HashMap::from([("VLGEN".to_string(), 1.1)]),
+            ..Default::default()
+        };
+
+        let svg = annotate_svg(P1_SVG, &p1_metadata(), &snapshot).unwrap();
+
+        let arrow = tag(&svg, "idGEN_ARROW_REACTIVE");
+        assert_eq!(
+            classes(arrow),
+            ["sld-reactive-power", "sld-feeder-info", "sld-out"]
+        );
+        assert!(arrow.contains("data-equipment-id=\"GEN\""));
+        assert!(arrow.contains("transform=\"translate(250.0,121.0)\""));
+
+        let generator = classes(tag(&svg, "idGEN"));
+        assert_eq!(
+            generator[..3],
+            ["sld-generator", "sld-top-feeder", "sld-vl0to30-0"]
+        );
+        assert_eq!(
+            generator
+                .iter()
+                .filter(|&&class| class == "argus-voltage-high")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn matches_ids_with_the_diagram_prefix() {
+        let metadata: SldMetadata = serde_json::from_value(serde_json::json!({
+            "components": [],
+            "feederInfos": [],
+            "nodes": [{
+                "id": "idBRK",
+                "componentType": "BREAKER",
+                "equipmentId": "BRK",
+                "open": false,
+                "vid": "VL",
+                "vlabel": false
+            }],
+            "svgParams": { "prefixId": "sld1-" },
+            "wires": []
+        }))
+        .unwrap();
+        let svg = concat!(
+            r#"<svg><g id="sld1-idBRK" class="sld-breaker argus-switch-open">"#,
+            r#"<rect/></g><g id="sld1-other" class="sld-breaker"/></svg>"#
+        );
+        let snapshot = TelemetrySnapshot {
+            switches: HashMap::from([("BRK".to_string(), true)]),
+            ..Default::default()
+        };
+
+        let svg = annotate_svg(svg, &metadata, &snapshot).unwrap();
+
+        let breaker = tag(&svg, "sld1-idBRK");
+        assert_eq!(classes(breaker)[..2], ["sld-breaker", "argus-switch-open"]);
+        assert_eq!(breaker.matches("argus-switch-open").count(), 1);
+        assert!(breaker.contains("data-equipment-id=\"BRK\""));
+        assert_eq!(
+            tag(&svg, "sld1-other"),
+            r#"<g id="sld1-other" class="sld-breaker"/>"#
+        );
+    }
+}
@@ -1,97 +1,640 @@
 use super::errors::SubscriptionResult;
-use crate::{errors::SubscriptionError, powsybl::entities::TelemetryCurves};
-use log::{debug, error, info, trace};
-use tauri::ipc::Channel;
+use super::sld_topology::TopologyFeed;
+use super::telemetry_codec::TelemetryDecoder;
+use super::telemetry_mapping::TelemetryMapping;
+use crate::{
+    errors::SubscriptionError,
+    powsybl::alarms::AlarmFeed,
+    powsybl::balance::BalanceFeed,
+    powsybl::calculations::Calculator,
+    powsybl::entities::{FeederValues, Quality, TelemetryCurves, TelemetryData},
+    powsybl::historian::HistorianRecorder,
+    powsybl::quality::QualityMonitor,
+    powsybl::switching::SwitchFeed,
+    sessions::{
+        state::{next_replayed, TelemetryFeed},
+        SessionMessage, SessionRecorder,
+    },
+    state::AppState,
+};
+use log::{debug, error, info, trace, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::{ipc::Channel, AppHandle, Emitter, Manager};
 use tokio::sync::broadcast;
 use zeromq::{Socket, SocketRecv};
 
+/// Event carrying the status changes of diagram subscriptions
+pub const SUBSCRIPTION_STATUS_EVENT: &str = "subscription-status";
+
+/// Interval at which the quality of the measurements of a diagram is checked, so
+/// measurements turning stale are pushed while the feed is silent
+const QUALITY_PUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reason of the stale status while indexed values arrive before their dictionary
+const WAITING_DICTIONARY: &str =
+    "Waiting for the id dictionary, re-sent periodically by the publisher";
+
 #[derive(Debug, Clone)]
 pub struct ZmqConfig {
     pub url: String,
+    /// Silence after which a subscription is reported as stale
+    pub stale_timeout: Duration,
+    /// Silence after which the socket is recreated
+    pub reconnect_timeout: Duration,
+    /// Delay before the first reconnection, doubled on each failure
+    pub retry_delay: Duration,
+    pub max_retry_delay: Duration,
+    /// Consecutive failures after which the subscription gives up
+    pub max_failures: u32,
+    /// Topic of the publisher heartbeats, which only keep the subscription alive
+    pub heartbeat_topic: String,
 }
 
 impl Default for ZmqConfig {
     fn default() -> Self {
         Self {
             url: "tcp://127.0.0.1:5556".to_string(),
+            stale_timeout: Duration::from_secs(10),
+            reconnect_timeout: Duration::from_secs(30),
+            retry_delay: Duration::from_millis(500),
+            max_retry_delay: Duration::from_secs(30),
+            max_failures: 10,
+            heartbeat_topic: "heartbeat".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriptionStatus {
+    /// The socket is connected and telemetry flows
+    Connected,
+    /// The publisher has been silent for longer than the stale timeout
+    Stale,
+    /// The socket failed; the subscription retries until `max_failures`
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionStatusEvent {
+    pub diagram_id: String,
+    pub status: SubscriptionStatus,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Where the telemetry received by a diagram subscription is sent
+#[derive(Clone)]
+pub enum TelemetrySink {
+    /// The values of the given telemetry ids, keyed by their graphical id
+    Filtered {
+        ids: HashMap<String, String>,
+        topics: Vec<String>,
+        channel: Channel<TelemetryCurves>,
+    },
+    /// The mapped measurements, tagged with their kind and unit
+    Mapped {
+        mapping: TelemetryMapping,
+        channel: Channel<FeederValues>,
+    },
+    /// The switch positions of a diagram, turned into node states
+    Topology(TopologyFeed),
+    /// Every value, stored by the historian
+    Historian(HistorianRecorder),
+    /// Every value, checked against its limits by the alarm engine
+    Alarms(AlarmFeed),
+    /// The power measurements summed by the balance checks
+    Balance(BalanceFeed),
+    /// The switch states, logged on every change
+    Switching(SwitchFeed),
+    /// Every message, recorded as received to a session file
+    Session(SessionRecorder),
+}
+
+impl TelemetrySink {
+    /// ZMQ topic prefixes to subscribe to
+    fn topics(&self) -> Vec<String> {
+        match self {
+            TelemetrySink::Filtered { topics, .. } => topics.clone(),
+            TelemetrySink::Mapped { mapping, .. } => mapping.topics(),
+            TelemetrySink::Topology(feed) => feed.topics(),
+            TelemetrySink::Historian(_)
+            | TelemetrySink::Alarms(_)
+            | TelemetrySink::Balance(_)
+            | TelemetrySink::Switching(_)
+            | TelemetrySink::Session(_) => vec![String::new()],
+        }
+    }
+
+    /// Whether the sink reads replayed sessions in place of the live feed; a
+    /// recording always reads the live one
+    fn follows_feed(&self) -> bool {
+        !matches!(self, TelemetrySink::Session(_))
+    }
+
+    /// Forward the values of a message belonging to the diagram, if any, with the
+    /// quality of the measurements of the diagram that are not good
+    fn send(&self, telemetry: TelemetryCurves, quality: &QualityMonitor) -> SubscriptionResult<()> {
+        match self {
+            TelemetrySink::Filtered { ids, channel, .. } => {
+                let TelemetryData { values, time } = telemetry.curves;
+                let values: HashMap<String, f64> = values
+                    .into_iter()
+                    .filter_map(|(id, value)| Some((ids.get(&id)?.clone(), value)))
+                    .collect();
+                if values.is_empty() {
+                    return Ok(());
+                }
+                channel.send(TelemetryCurves {
+                    curves: TelemetryData { values, time },
+                    quality: self.degraded(quality).unwrap_or_default(),
+                })
+            }
+            TelemetrySink::Mapped { mapping, channel } => {
+                let values = mapping.apply(&telemetry.curves, quality);
+                if values.values.is_empty() {
+                    return Ok(());
+                }
+                channel.send(values)
+            }
+            TelemetrySink::Topology(feed) => return feed.send(&telemetry.curves),
+            TelemetrySink::Historian(recorder) => return recorder.record(telemetry.curves),
+            TelemetrySink::Alarms(feed) => return feed.send(telemetry.curves),
+            TelemetrySink::Balance(feed) => {
+                feed.send(telemetry.curves);
+                return Ok(());
+            }
+            TelemetrySink::Switching(feed) => return feed.send(telemetry.curves),
+            // Recorded before decoding
+            TelemetrySink::Session(_) => return Ok(()),
+        }
+        .map_err(|e| SubscriptionError::ChannelSendError(e.to_string()))
+    }
+
+    /// Quality of the measurements of the diagram that are not good, keyed as
+    /// their values, for the sinks forwarding values to the diagram
+    fn degraded(&self, quality: &QualityMonitor) -> Option<HashMap<String, Quality>> {
+        match self {
+            TelemetrySink::Filtered { ids, .. } => Some(
+                quality
+                    .degraded(ids.keys().map(String::as_str))
+                    .into_iter()
+                    .filter_map(|(id, quality)| Some((ids.get(&id)?.clone(), quality)))
+                    .collect(),
+            ),
+            TelemetrySink::Mapped { mapping, .. } => Some(mapping.degraded(quality)),
+            _ => None,
+        }
+    }
+
+    /// Forward the quality of the measurements alone
+    fn send_quality(&self, quality: HashMap<String, Quality>, time: u64) -> SubscriptionResult<()> {
+        match self {
+            TelemetrySink::Filtered { channel, .. } => channel.send(TelemetryCurves {
+                curves: TelemetryData {
+                    values: HashMap::new(),
+                    time,
+                },
+                quality,
+            }),
+            TelemetrySink::Mapped { channel, .. } => channel.send(FeederValues {
+                values: Vec::new(),
+                time,
+                quality,
+            }),
+            _ => return Ok(()),
+        }
+        .map_err(|e| SubscriptionError::ChannelSendError(e.to_string()))
+    }
+}
+
+// A consumer of the subscription, with the status and quality last pushed to it
+struct Consumer {
+    sink: TelemetrySink,
+    topics: Vec<String>,
+    status: Option<SubscriptionStatus>,
+    quality: Option<HashMap<String, Quality>>,
+}
+
+impl Consumer {
+    // Filtered by topic prefix, as a socket of its own would
+    fn subscribed(&self, topic: &[u8]) -> bool {
+        self.topics
+            .iter()
+            .any(|prefix| topic.starts_with(prefix.as_bytes()))
+    }
+}
+
+/// Consumers of the telemetry subscription, by subscription id.
+///
+/// Shared by the subscription task, which fans every message out to them, and the
+/// commands adding and removing them. The registry outlives the task, so the
+/// consumers are kept when the subscription is restarted.
+#[derive(Clone, Default)]
+pub struct TelemetrySinks {
+    consumers: Arc<Mutex<HashMap<String, Consumer>>>,
+}
+
+impl std::fmt::Debug for TelemetrySinks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.consumers().keys()).finish()
+    }
+}
+
+impl TelemetrySinks {
+    fn consumers(&self) -> MutexGuard<'_, HashMap<String, Consumer>> {
+        self.consumers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Add a consumer, unless one is already registered under the same id
+    pub fn insert(&self, id: String, sink: TelemetrySink) -> bool {
+        let mut consumers = self.consumers();
+        if consumers.contains_key(&id) {
+            return false;
         }
+        let consumer = Consumer {
+            topics: sink.topics(),
+            sink,
+            status: None,
+            quality: None,
+        };
+        consumers.insert(id, consumer);
+        true
+    }
+
+    /// Remove a consumer, returning whether it was registered
+    pub fn remove(&self, id: &str) -> bool {
+        self.consumers().remove(id).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.consumers().is_empty()
+    }
+
+    fn clear(&self) {
+        self.consumers().clear();
     }
 }
 
+// Drop the consumers whose channel is closed, as nobody listens to them anymore
+fn remove_closed(consumers: &mut HashMap<String, Consumer>, closed: Vec<String>) {
+    for id in closed {
+        info!("Removing subscription {}, its channel is closed", id);
+        consumers.remove(&id);
+    }
+}
+
+// Next message of the socket, waiting forever while it is not connected
+async fn next_message(
+    socket: &mut Option<zeromq::SubSocket>,
+) -> Result<zeromq::ZmqMessage, zeromq::ZmqError> {
+    match socket {
+        Some(socket) => socket.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// The single SUB socket of the application, fanning the telemetry out to the
+/// diagrams and the monitoring features.
+///
+/// Every message is decoded, completed with the derived points and assessed by the
+/// quality monitor once, then forwarded to the consumers subscribed to its topic;
+/// sessions being recorded receive the raw frames. The socket is recreated after
+/// receive errors and long silences, and every status change is emitted to each
+/// consumer as a [`SUBSCRIPTION_STATUS_EVENT`]. While a session is replayed, the
+/// replayed messages are forwarded in place of the live ones, which are still
+/// recorded. Indexed values received before their id dictionary are dropped, and
+/// the feed is reported as stale until the publisher sends the dictionary again.
 pub struct ZmqSubscription {
-    feeder_id: String,
     config: ZmqConfig,
-    channel: Channel<TelemetryCurves>,
+    sinks: TelemetrySinks,
+    app_handle: AppHandle,
+    status: Option<SubscriptionStatus>,
+    live_decoder: TelemetryDecoder,
+    replay_decoder: TelemetryDecoder,
+    /// Whether indexed values were dropped since the feed has no dictionary
+    waiting_dictionary: bool,
+    calculator: Calculator,
+    quality: QualityMonitor,
+    /// Time of the last message, sent with the quality pushed on the timer
+    time: u64,
 }
 
 impl ZmqSubscription {
-    pub fn new(feeder_id: String, config: ZmqConfig, channel: Channel<TelemetryCurves>) -> Self {
+    pub fn new(config: ZmqConfig, sinks: TelemetrySinks, app_handle: AppHandle) -> Self {
+        let telemetry_feed = app_handle.state::<TelemetryFeed>();
         Self {
-            feeder_id,
             config,
-            channel,
+            sinks,
+            quality: app_handle.state::<QualityMonitor>().inner().clone(),
+            live_decoder: TelemetryDecoder::new(telemetry_feed.dictionary(false)),
+            replay_decoder: TelemetryDecoder::new(telemetry_feed.dictionary(true)),
+            app_handle,
+            status: None,
+            waiting_dictionary: false,
+            calculator: Calculator::default(),
+            time: 0,
         }
     }
 
-    pub async fn start(&self, mut shutdown_rx: broadcast::Receiver<()>) -> SubscriptionResult<()> {
-        info!(
-            "Starting ZMQ monitoring for feeder {} on {}",
-            self.feeder_id, self.config.url
-        );
+    /// Receive and forward the telemetry until shut down, every consumer is gone,
+    /// or the socket failed `max_failures` times in a row outside of a replay
+    pub async fn start(
+        mut self,
+        mut shutdown_rx: broadcast::Receiver<()>,
+    ) -> SubscriptionResult<()> {
+        info!("Starting the telemetry subscription on {}", self.config.url);
+
+        let app_handle = self.app_handle.clone();
+        let telemetry_feed = app_handle.state::<TelemetryFeed>();
+        let mut feed = telemetry_feed.watch();
+        let mut replay = feed.replay();
 
-        let mut socket = self.create_and_connect_socket().await?;
+        let mut socket: Option<zeromq::SubSocket> = None;
+        let mut failures = 0u32;
+        let mut reconnect_at = tokio::time::Instant::now();
+        let mut last_message = Instant::now();
+        let mut stale_at = tokio::time::Instant::now() + self.config.stale_timeout;
+        let mut quality_push = tokio::time::interval(QUALITY_PUSH_INTERVAL);
+        quality_push.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
-        loop {
-            tokio::select! {
-                _ = shutdown_rx.recv() => {
-                    info!("Shutdown signal received for feeder {}", self.feeder_id);
-                    break;
+        let result = loop {
+            if self.sinks.is_empty() {
+                info!("No consumer left for the telemetry subscription");
+                break Ok(());
+            }
+
+            let error = tokio::select! {
+                _ = shutdown_rx.recv() => break Ok(()),
+                _ = feed.changed() => {
+                    replay = feed.replay();
+                    info!(
+                        "Telemetry feed changed, {}",
+                        if replay.is_some() { "replaying a session" } else { "back to live" }
+                    );
+                    // Values and ids of the previous feed no longer apply
+                    self.replay_decoder = TelemetryDecoder::new(telemetry_feed.dictionary(true));
+                    self.waiting_dictionary = false;
+                    self.calculator = Calculator::default();
+                    if replay.is_some() {
+                        self.report(SubscriptionStatus::Connected, None);
+                    }
+                    None
                 }
-                result = socket.recv() => {
-                    match result {
-                        Ok(message) => {
-                            self.process_message(message).await?;
+                _ = tokio::time::sleep_until(reconnect_at), if socket.is_none() => {
+                    match self.connect().await {
+                        Ok(connected) => {
+                            socket = Some(connected);
+                            last_message = Instant::now();
+                            stale_at = tokio::time::Instant::now() + self.config.stale_timeout;
+                            // A socket recreated after a silence stays stale until telemetry flows
+                            if self.status != Some(SubscriptionStatus::Stale) {
+                                self.report(SubscriptionStatus::Connected, None);
+                            }
+                            None
                         }
-                        Err(e) => {
-                            error!("ZMQ receive error: {:?}", e);
-                            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+                        Err(e) => Some(e),
+                    }
+                }
+                result = next_message(&mut socket) => match result {
+                    Ok(message) => {
+                        last_message = Instant::now();
+                        stale_at = tokio::time::Instant::now() + self.config.stale_timeout;
+                        failures = 0;
+
+                        let frames: Vec<&[u8]> =
+                            message.iter().map(|frame| frame.as_ref()).collect();
+                        self.record(&frames);
+                        if replay.is_none() {
+                            self.forward(&frames, false);
+                            self.report_flowing();
                         }
+                        None
                     }
+                    Err(e) => {
+                        socket = None;
+                        Some(e.into())
+                    }
+                },
+                message = next_replayed(&mut replay) => {
+                    if let SessionMessage::Zmq { frames } = message.as_ref() {
+                        let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+                        self.forward(&frames, true);
+                        self.report_flowing();
+                    }
+                    None
                 }
+                // Replays may be paused, so only the live feed turns stale
+                _ = tokio::time::sleep_until(stale_at), if socket.is_some() => {
+                    if replay.is_none() {
+                        self.report(SubscriptionStatus::Stale, None);
+                    }
+                    if last_message.elapsed() >= self.config.reconnect_timeout {
+                        info!("No telemetry received, recreating socket");
+                        socket = None;
+                        reconnect_at = tokio::time::Instant::now();
+                    }
+                    stale_at = tokio::time::Instant::now() + self.config.stale_timeout;
+                    None
+                }
+                _ = quality_push.tick() => {
+                    self.push_quality();
+                    None
+                }
+            };
+
+            if let Some(e) = error {
+                failures += 1;
+                error!(
+                    "ZMQ error ({}/{}): {:?}",
+                    failures, self.config.max_failures, e
+                );
+                self.report(SubscriptionStatus::Failed, Some(e.to_string()));
+
+                // A replay goes on whatever the state of the live feed
+                if failures >= self.config.max_failures && replay.is_none() {
+                    break Err(e);
+                }
+                let delay = self
+                    .config
+                    .retry_delay
+                    .saturating_mul(2u32.saturating_pow(failures.min(32) - 1))
+                    .min(self.config.max_retry_delay);
+                reconnect_at = tokio::time::Instant::now() + delay;
             }
-        }
+        };
 
-        info!("ZMQ monitoring finished for feeder {}", self.feeder_id);
-        Ok(())
+        // The consumers of a subscription that gave up are forgotten, so that they
+        // start a new one when they subscribe again
+        if result.is_err() {
+            self.sinks.clear();
+        }
+        info!("Telemetry subscription finished");
+        result
     }
 
-    async fn create_and_connect_socket(&self) -> SubscriptionResult<zeromq::SubSocket> {
+    async fn connect(&self) -> SubscriptionResult<zeromq::SubSocket> {
         let mut socket = zeromq::SubSocket::new();
         socket.connect(&self.config.url).await?;
+        // Consumers filter the messages by topic themselves
         socket.subscribe("").await?;
         Ok(socket)
     }
 
-    async fn process_message(&self, message: zeromq::ZmqMessage) -> SubscriptionResult<()> {
-        debug!("ZMQ message received for feeder {}", self.feeder_id);
+    // Record the live frames into the sessions being recorded
+    fn record(&self, frames: &[&[u8]]) {
+        let mut consumers = self.sinks.consumers();
+        let mut closed = Vec::new();
+        for (id, consumer) in consumers.iter() {
+            let TelemetrySink::Session(recorder) = &consumer.sink else {
+                continue;
+            };
+            match recorder.record_zmq(frames) {
+                Err(SubscriptionError::ChannelSendError(_)) => closed.push(id.clone()),
+                Err(e) => warn!("Failed to record telemetry message: {}", e),
+                Ok(()) => {}
+            }
+        }
+        remove_closed(&mut consumers, closed);
+    }
+
+    // Decode a message of the feed in use, and forward its values to the consumers
+    // following the feed that are subscribed to its topic
+    fn forward(&mut self, frames: &[&[u8]], replayed: bool) {
+        trace!("ZMQ message received ({} frames)", frames.len());
+
+        let decoder = if replayed {
+            &mut self.replay_decoder
+        } else {
+            &mut self.live_decoder
+        };
+        let decoded = decoder.decode(frames.iter().copied());
+        let waiting_dictionary = !decoder.has_dictionary();
+        if self.waiting_dictionary && !waiting_dictionary {
+            info!("Id dictionary received, indexed telemetry is resolved again");
+        }
+        self.waiting_dictionary &= waiting_dictionary;
+
+        // Heartbeats and id dictionaries carry no values
+        let mut telemetry = match decoded {
+            Ok(Some(telemetry)) => telemetry,
+            Ok(None) => return,
+            Err(SubscriptionError::MissingDictionary) => {
+                if !self.waiting_dictionary {
+                    warn!("Dropping indexed telemetry until the id dictionary is received");
+                }
+                self.waiting_dictionary = true;
+                return;
+            }
+            Err(e) => {
+                warn!("Ignoring telemetry message: {}", e);
+                return;
+            }
+        };
+        trace!("  Number of curves: {}", telemetry.curves.values.len());
+
+        if let Err(e) = self.calculate(&mut telemetry.curves) {
+            warn!("Ignoring telemetry message: {}", e);
+            return;
+        }
+        self.quality.assess(&mut telemetry.curves);
+        self.time = telemetry.curves.time;
+
+        let topic = frames.first().copied().unwrap_or_default();
+        let mut consumers = self.sinks.consumers();
+        let mut closed = Vec::new();
+        for (id, consumer) in consumers.iter() {
+            if !consumer.sink.follows_feed() || !consumer.subscribed(topic) {
+                continue;
+            }
+            match consumer.sink.send(telemetry.clone(), &self.quality) {
+                Err(SubscriptionError::ChannelSendError(_)) => closed.push(id.clone()),
+                Err(e) => warn!("Failed to forward telemetry to {}: {}", id, e),
+                Ok(()) => {}
+            }
+        }
+        remove_closed(&mut consumers, closed);
+    }
 
-        if let Some(frame) = message.get(0) {
-            let data = String::from_utf8(frame.to_vec())
-                .unwrap_or_else(|_| String::from("[non-UTF8 data]"));
+    // Push the quality of each consumer when it changed since its last push
+    fn push_quality(&self) {
+        let mut consumers = self.sinks.consumers();
+        let mut closed = Vec::new();
+        for (id, consumer) in consumers.iter_mut() {
+            let Some(quality) = consumer.sink.degraded(&self.quality) else {
+                continue;
+            };
+            // Nothing to report until a measurement is not good
+            let unchanged = match &consumer.quality {
+                Some(pushed) => pushed == &quality,
+                None => quality.is_empty(),
+            };
+            if unchanged {
+                continue;
+            }
+            match consumer.sink.send_quality(quality.clone(), self.time) {
+                Err(SubscriptionError::ChannelSendError(_)) => closed.push(id.clone()),
+                Err(e) => warn!("Failed to push quality to {}: {}", id, e),
+                Ok(()) => consumer.quality = Some(quality),
+            }
+        }
+        remove_closed(&mut consumers, closed);
+    }
 
-            trace!("  JSON data size: {} characters", data.len());
+    // Report telemetry flowing, which is stale while it cannot be resolved
+    fn report_flowing(&mut self) {
+        if self.waiting_dictionary {
+            self.report(
+                SubscriptionStatus::Stale,
+                Some(WAITING_DICTIONARY.to_string()),
+            );
+        } else {
+            self.report(SubscriptionStatus::Connected, None);
+        }
+    }
 
-            let telemetry: TelemetryCurves = serde_json::from_str(&data)?;
-            debug!("  Successfully deserialized to TelemetryCurves");
-            debug!("  Number of curves: {}", telemetry.curves.values.len());
+    // Emit status changes only, to every consumer that has not seen them yet;
+    // failures are always emitted, with their reason
+    fn report(&mut self, status: SubscriptionStatus, message: Option<String>) {
+        self.status = Some(status);
 
-            self.channel
-                .send(telemetry.clone())
-                .map_err(|e| SubscriptionError::ChannelSendError(e.to_string()))?;
+        let mut consumers = self.sinks.consumers();
+        for (id, consumer) in consumers.iter_mut() {
+            if consumer.status == Some(status) && status != SubscriptionStatus::Failed {
+                continue;
+            }
+            consumer.status = Some(status);
+            debug!("Subscription {} is {:?}", id, status);
 
-            debug!("  Data successfully sent via channel");
+            let event = SubscriptionStatusEvent {
+                diagram_id: id.clone(),
+                status,
+                message: message.clone(),
+            };
+            if let Err(e) = self.app_handle.emit(SUBSCRIPTION_STATUS_EVENT, event) {
+                warn!("Failed to emit subscription status: {}", e);
+            }
         }
+    }
 
+    // Add the derived points of the loaded calculations, starting over with the
+    // last values when the calculations are reloaded
+    fn calculate(&mut self, data: &mut TelemetryData) -> SubscriptionResult<()> {
+        let set = match self.app_handle.state::<AppState>().read() {
+            Ok(state) => state.powsybl.calculations.clone(),
+            Err(_) => return Err(SubscriptionError::StateLockError),
+        };
+        if !Arc::ptr_eq(self.calculator.set(), &set) {
+            self.calculator = Calculator::new(set);
+        }
+        self.calculator
+            .apply(data, Instant::now(), self.quality.stale_timeout());
         Ok(())
     }
 }
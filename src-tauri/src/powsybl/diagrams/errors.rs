@@ -12,6 +12,12 @@ pub enum SubscriptionError {
     #[error("Failed to parse telemetry data: {0}")]
     ParseError(#[from] serde_json::Error),
 
+    #[error("Failed to decode binary telemetry data: {0}")]
+    DecodeError(String),
+
+    #[error("Indexed telemetry received before the id dictionary")]
+    MissingDictionary,
+
     #[error("Task already exists for feeder: {0}")]
     TaskAlreadyExists(String),
 
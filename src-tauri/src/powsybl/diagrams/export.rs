@@ -0,0 +1,381 @@
+use super::super::cache::QueryCacheState;
+use super::super::entities::FetchStatus;
+use super::super::errors::{PowsyblError, PowsyblResult};
+use super::annotation::{annotate_svg, TelemetrySnapshot};
+use super::single_line::fetch_single_line_diagram;
+use crate::state::AppState;
+
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use resvg::tiny_skia::{Color, Pixmap, Transform};
+use resvg::usvg::{fontdb, PostProcessingSteps, Tree, TreeParsing, TreePostProc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::Path;
+use tauri::State;
+
+/// Upper bound of the PNG scale factor, to keep pixmaps in a reasonable size
+const MAX_SCALE: f32 = 8.0;
+
+/// Upper bound of the PNG pixel count, about 256 MB of RGBA pixmap
+const MAX_PIXELS: u64 = 64 * 1024 * 1024;
+
+/// System fonts, loaded once and shared by every export
+static FONTS: Lazy<fontdb::Database> = Lazy::new(|| {
+    let mut fonts = fontdb::Database::new();
+    fonts.load_system_fonts();
+    debug!("Loaded {} font faces for diagram export", fonts.len());
+
+    // powsybl styles use generic families, whose defaults (Times New Roman, Arial...)
+    // are often missing on Linux, so fall back on any installed family
+    if let Some(family) = installed_family(&fonts, &["Times New Roman"], "Serif") {
+        fonts.set_serif_family(family);
+    }
+    if let Some(family) = installed_family(&fonts, &["Arial", "Helvetica"], "Sans") {
+        fonts.set_sans_serif_family(family);
+    }
+    if let Some(family) = installed_family(&fonts, &["Courier New"], "Mono") {
+        fonts.set_monospace_family(family);
+    }
+    fonts
+});
+
+// Family to use for a generic family, or None when its default is installed
+fn installed_family(fonts: &fontdb::Database, defaults: &[&str], hint: &str) -> Option<String> {
+    let families: Vec<&str> = fonts
+        .faces()
+        .flat_map(|face| face.families.iter().map(|(name, _)| name.as_str()))
+        .collect();
+
+    if let Some(family) = defaults.iter().find(|d| families.contains(d)) {
+        return Some(family.to_string());
+    }
+    families
+        .iter()
+        .find(|family| family.contains(hint))
+        .or(families.first())
+        .map(|family| family.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Png,
+    Pdf,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+/// Rewrite the `<style>` of a powsybl SVG without CSS custom properties, which the
+/// renderer does not support.
+///
+/// Every `var(--name, fallback)` is replaced by its fallback, and for every class
+/// defining `--name` (such as the base voltage classes) a rule with the resolved
+/// value is added, scoped to the elements of that class and their descendants.
+fn resolve_css_variables(svg: &str) -> Cow<'_, str> {
+    let (Some(start), Some(end)) = (svg.find("<style"), svg.find("</style>")) else {
+        return Cow::Borrowed(svg);
+    };
+    let Some(content_start) = svg[start..end].find('>').map(|i| start + i + 1) else {
+        return Cow::Borrowed(svg);
+    };
+
+    let content = &svg[content_start..end];
+    let (css, cdata) = match content
+        .trim()
+        .strip_prefix("<![CDATA[")
+        .and_then(|css| css.strip_suffix("]]>"))
+    {
+        Some(css) => (css, true),
+        None => (content, false),
+    };
+    if !css.contains("var(") {
+        return Cow::Borrowed(svg);
+    }
+
+    let css = strip_css_comments(css);
+    let rules: Vec<(&str, &str)> = css
+        .split('}')
+        .filter_map(|rule| {
+            let (selector, body) = rule.split_once('{')?;
+            Some((selector.trim(), body.trim()))
+        })
+        .collect();
+
+    // Classes defining a custom property: (selector, property name, value)
+    let definitions: Vec<(&str, &str, &str)> = rules
+        .iter()
+        .flat_map(|&(selector, body)| {
+            body.split(';').filter_map(move |declaration| {
+                let (name, value) = declaration.split_once(':')?;
+                let name = name.trim();
+                name.starts_with("--")
+                    .then(|| (selector, name, value.trim()))
+            })
+        })
+        .collect();
+
+    let mut resolved = String::with_capacity(css.len() * 2);
+    for &(selector, body) in &rules {
+        resolved.push_str(&format!(
+            "{} {{{}}}\n",
+            selector,
+            replace_css_variables(body, |_| None)
+        ));
+    }
+    for &(scope, name, value) in &definitions {
+        for &(selector, body) in rules.iter().filter(|(_, body)| body.contains(name)) {
+            let body = replace_css_variables(body, |var| (var == name).then_some(value));
+            for selector in selector.split(',').map(str::trim) {
+                resolved.push_str(&format!(
+                    "{scope} {selector}, {scope}{selector} {{{body}}}\n"
+                ));
+            }
+        }
+    }
+
+    let resolved = if cdata {
+        format!("<![CDATA[\n{}]]>", resolved)
+    } else {
+        resolved
+    };
+
+    Cow::Owned(format!(
+        "{}{}{}",
+        &svg[..content_start],
+        resolved,
+        &svg[end..]
+    ))
+}
+
+fn strip_css_comments(css: &str) -> String {
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+// Replace every `var(--name, fallback)` of a declaration block,
+// dropping the declarations that cannot be resolved
+fn replace_css_variables<'a>(body: &str, lookup: impl Fn(&str) -> Option<&'a str>) -> String {
+    body.split(';')
+        .filter_map(|declaration| {
+            let mut declaration = declaration.trim().to_string();
+            if declaration.starts_with("--") {
+                return None;
+            }
+            while let Some(start) = declaration.find("var(") {
+                let end = start + declaration[start..].find(')')?;
+                let inner = &declaration[start + 4..end];
+                let (name, fallback) = match inner.split_once(',') {
+                    Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+                    None => (inner.trim(), None),
+                };
+                let value = lookup(name).or(fallback)?.to_string();
+                declaration.replace_range(start..=end, &value);
+            }
+            (!declaration.is_empty()).then_some(declaration)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Render an SVG document to PNG or PDF.
+///
+/// Rendering is done in pure Rust and needs no display. `scale` multiplies the
+/// native size of the SVG for PNG output; PDF output is vector and ignores it.
+/// PNG images larger than `MAX_PIXELS` are refused with an export error.
+pub fn render_svg(svg: &str, format: ExportFormat, scale: f32) -> PowsyblResult<Vec<u8>> {
+    let svg = resolve_css_variables(svg);
+    let mut tree = Tree::from_str(&svg, &resvg::usvg::Options::default())
+        .map_err(|e| PowsyblError::RenderError(e.to_string()))?;
+    tree.postprocess(PostProcessingSteps::default(), &FONTS);
+
+    match format {
+        ExportFormat::Png => {
+            let scale = scale.clamp(f32::EPSILON, MAX_SCALE);
+            let width = (tree.size.width() * scale).ceil() as u32;
+            let height = (tree.size.height() * scale).ceil() as u32;
+            if u64::from(width) * u64::from(height) > MAX_PIXELS {
+                return Err(PowsyblError::ExportError(format!(
+                    "Image of {}x{} pixels exceeds the limit of {} pixels, lower the scale",
+                    width, height, MAX_PIXELS
+                )));
+            }
+
+            let mut pixmap = Pixmap::new(width, height).ok_or_else(|| {
+                PowsyblError::RenderError(format!("Invalid image size {}x{}", width, height))
+            })?;
+            // Reports and tickets expect an opaque image
+            pixmap.fill(Color::WHITE);
+            resvg::render(
+                &tree,
+                Transform::from_scale(scale, scale),
+                &mut pixmap.as_mut(),
+            );
+
+            pixmap
+                .encode_png()
+                .map_err(|e| PowsyblError::RenderError(e.to_string()))
+        }
+        ExportFormat::Pdf => Ok(svg2pdf::convert_tree(&tree, svg2pdf::Options::default())),
+    }
+}
+
+// Helper function to fetch, annotate and render a single line diagram
+async fn render_single_line_diagram(
+    state: &State<'_, AppState>,
+    cache: &State<'_, QueryCacheState>,
+    line_id: &str,
+    network_id: Option<String>,
+    format: ExportFormat,
+    scale: f32,
+    snapshot: Option<&TelemetrySnapshot>,
+) -> PowsyblResult<Vec<u8>> {
+    let diagram = fetch_single_line_diagram(state, cache, line_id, network_id).await?;
+    let svg = match snapshot {
+        Some(snapshot) => annotate_svg(&diagram.svg, &diagram.metadata, snapshot)?,
+        None => diagram.svg,
+    };
+
+    tokio::task::spawn_blocking(move || render_svg(&svg, format, scale))
+        .await
+        .map_err(|e| PowsyblError::RenderError(e.to_string()))?
+}
+
+// Keep voltage level ids usable as file names
+fn file_stem(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// File stem of an id, suffixed when another id of the batch maps to the same one
+fn unique_file_stem(stems: &mut HashSet<String>, id: &str) -> String {
+    let stem = file_stem(id);
+    let mut unique = stem.clone();
+    let mut index = 2;
+    while !stems.insert(unique.clone()) {
+        unique = format!("{}_{}", stem, index);
+        index += 1;
+    }
+    unique
+}
+
+/// Export a single line diagram, optionally annotated with live values, to a PNG or PDF file
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_single_line_diagram(
+    state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
+    line_id: String,
+    format: ExportFormat,
+    output_path: String,
+    scale: Option<f32>,
+    snapshot: Option<TelemetrySnapshot>,
+    network_id: Option<String>,
+) -> PowsyblResult<FetchStatus> {
+    let content = render_single_line_diagram(
+        &state,
+        &cache,
+        &line_id,
+        network_id,
+        format,
+        scale.unwrap_or(1.0),
+        snapshot.as_ref(),
+    )
+    .await?;
+
+    tokio::fs::write(&output_path, content).await?;
+
+    Ok(FetchStatus {
+        success: true,
+        message: format!("Exported diagram of {} to {}", line_id, output_path),
+    })
+}
+
+/// Export the single line diagrams of several voltage levels into a directory.
+///
+/// Files are named after the voltage level ids, suffixed when two ids map to the
+/// same name. A diagram that cannot be rendered or written does not stop the
+/// others, and is reported with its error.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_single_line_diagrams(
+    state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
+    voltage_level_ids: Vec<String>,
+    format: ExportFormat,
+    output_dir: String,
+    scale: Option<f32>,
+    snapshot: Option<TelemetrySnapshot>,
+    network_id: Option<String>,
+) -> PowsyblResult<FetchStatus> {
+    let output_dir = Path::new(&output_dir);
+    tokio::fs::create_dir_all(output_dir).await?;
+
+    let mut stems = HashSet::new();
+    let mut failed = Vec::new();
+    for voltage_level_id in &voltage_level_ids {
+        let path = output_dir.join(format!(
+            "{}.{}",
+            unique_file_stem(&mut stems, voltage_level_id),
+            format.extension()
+        ));
+        let result = render_single_line_diagram(
+            &state,
+            &cache,
+            voltage_level_id,
+            network_id.clone(),
+            format,
+            scale.unwrap_or(1.0),
+            snapshot.as_ref(),
+        )
+        .await;
+
+        let written = match result {
+            Ok(content) => tokio::fs::write(&path, content)
+                .await
+                .map_err(PowsyblError::from),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            warn!("Failed to export diagram of {}: {}", voltage_level_id, e);
+            failed.push(format!("{} ({})", voltage_level_id, e));
+        }
+    }
+
+    let exported = voltage_level_ids.len() - failed.len();
+    Ok(FetchStatus {
+        success: failed.is_empty(),
+        message: if failed.is_empty() {
+            format!("Exported {} diagrams to {}", exported, output_dir.display())
+        } else {
+            format!(
+                "Exported {} diagrams to {}, failed: {}",
+                exported,
+                output_dir.display(),
+                failed.join(", ")
+            )
+        },
+    })
+}
@@ -1,46 +1,73 @@
-use std::io::Read;
+use crate::{database::DatabaseState, settings::get_setting, state::AppState};
 
-use crate::{database::DatabaseState, settings::get_setting};
-
-use super::{entities::FetchStatus, send_zmq_request, PowsyblResult};
+use super::{
+    cache::QueryCacheState, entities::FetchStatus, errors::PowsyblError,
+    networks::upload_network_file, PowsyblResult,
+};
 
+mod annotation;
 mod entities;
+mod export;
+mod network_area;
 mod single_line;
+mod zone;
 
 pub mod errors;
+pub mod nad_metadata;
 pub mod sld_metadata;
 pub mod sld_subscriptions;
+pub mod sld_topology;
+pub mod telemetry_codec;
+pub mod telemetry_mapping;
+
+pub(crate) use entities::TelemetrySink;
 
-use base64::Engine;
+pub use annotation::{annotate_single_line_diagram, annotate_svg, TelemetrySnapshot};
+pub use export::{
+    export_single_line_diagram, export_single_line_diagrams, render_svg, ExportFormat,
+};
+pub use network_area::{
+    get_network_area_diagram, subscribe_network_area_diagram, unsubscribe_network_area_diagram,
+};
 pub use single_line::{
     get_single_line_diagram, get_single_line_diagram_metadata,
     get_single_line_diagram_with_metadata,
 };
 pub use sld_subscriptions::{subscribe_single_line_diagram, unsubscribe_single_line_diagram};
+pub use sld_topology::{
+    get_single_line_diagram_states, subscribe_single_line_diagram_states,
+    unsubscribe_single_line_diagram_states, NodeState, SldTopology,
+};
+pub use zone::{
+    get_zone_single_line_diagram, subscribe_zone_single_line_diagram,
+    unsubscribe_zone_single_line_diagram,
+};
 
 use tauri::State;
 
 #[tauri::command(rename_all = "snake_case")]
-pub async fn upload_iidm(state: State<'_, DatabaseState>) -> PowsyblResult<FetchStatus> {
+pub async fn upload_iidm(
+    state: State<'_, DatabaseState>,
+    app_state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
+    label: Option<String>,
+) -> PowsyblResult<FetchStatus> {
     let state = state.lock().await;
     if let Some(config) = get_setting(&state.pool, "iidm").await.unwrap() {
         let path = config["iidm_path"].as_str().unwrap();
-        let mut file = std::fs::File::open(path).unwrap();
-        let mut file_data = Vec::new();
-        file.read_to_end(&mut file_data).unwrap();
-
-        let encoded = base64::engine::general_purpose::STANDARD.encode(&file_data);
-
-        let params = serde_json::json!({
-            "file_data": encoded,
-            "filename": path
-        });
-
-        send_zmq_request("upload_iidm", Some(params)).await?;
+        // The configured file replaces the network it was uploaded as
+        let network_id = app_state
+            .read()
+            .map_err(|_| PowsyblError::LockError)?
+            .powsybl
+            .active_network
+            .clone();
+        let network =
+            upload_network_file(&app_state, &cache, path, label, network_id, true).await?;
 
         return Ok(FetchStatus {
             success: true,
-            message: "".to_string(),
+            message: network.id,
         });
     }
 
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+// Network area diagram metadata, as written by powsybl-diagram.
+// Unknown fields are ignored so newer powsybl versions keep deserializing.
+
+/// Voltage level node of the diagram
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NadNode {
+    pub svg_id: String,
+    pub equipment_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<f64>,
+
+    #[serde(default)]
+    pub fictitious: bool,
+}
+
+/// Bus drawn inside a voltage level node
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NadBusNode {
+    pub svg_id: String,
+    pub equipment_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nb_neighbours: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<i32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vl_node: Option<String>,
+}
+
+/// Branch (or injection) drawn between two nodes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NadEdge {
+    pub svg_id: String,
+    pub equipment_id: String,
+    pub node1: String,
+    pub node2: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bus_node1: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bus_node2: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "type")]
+    pub edge_type: Option<String>,
+}
+
+/// Label box attached to a voltage level node
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NadTextNode {
+    pub svg_id: String,
+    pub equipment_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vl_node: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shift_x: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shift_y: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_shift_x: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_shift_y: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NadMetadata {
+    #[serde(default)]
+    pub nodes: Vec<NadNode>,
+
+    #[serde(default)]
+    pub bus_nodes: Vec<NadBusNode>,
+
+    #[serde(default)]
+    pub edges: Vec<NadEdge>,
+
+    #[serde(default)]
+    pub text_nodes: Vec<NadTextNode>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout_parameters: Option<serde_json::Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub svg_parameters: Option<serde_json::Value>,
+}
+
+impl NadMetadata {
+    /// Edges linking two voltage levels, on which live flows are displayed
+    pub fn get_flow_edges(&self) -> Vec<NadEdge> {
+        self.edges
+            .iter()
+            .filter(|edge| !edge.equipment_id.is_empty() && edge.node1 != edge.node2)
+            .cloned()
+            .collect()
+    }
+
+    /// Voltage level node an edge side is attached to
+    pub fn edge_node(&self, edge: &NadEdge, side: u8) -> Option<&NadNode> {
+        let svg_id = if side == 1 { &edge.node1 } else { &edge.node2 };
+        self.nodes.iter().find(|node| &node.svg_id == svg_id)
+    }
+}
@@ -0,0 +1,131 @@
+use super::super::cache::{cached_zmq_request, QueryCacheState};
+use super::super::entities::{SldSubscriptionResponse, TelemetryCurves};
+use super::super::errors::{PowsyblError, PowsyblResult};
+use super::super::resolve_network_id;
+use super::entities::TelemetrySink;
+use super::nad_metadata::NadMetadata;
+use super::sld_subscriptions::{
+    diagram_id, game_master_outputs, subscribe_diagram, unsubscribe_diagram,
+};
+use super::telemetry_mapping::{outputs_for_ids, subscription_topics};
+use crate::state::AppState;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use tauri::{ipc::Channel, AppHandle, State};
+
+/// Network area diagram SVG and metadata
+#[derive(Serialize, Deserialize)]
+pub struct NadDiagramResult {
+    svg: String,
+    metadata: NadMetadata,
+}
+
+/// Gets a network area diagram around one or more voltage levels using ZMQ
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_network_area_diagram(
+    state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
+    voltage_level_ids: Vec<String>,
+    depth: Option<u32>,
+    network_id: Option<String>,
+) -> PowsyblResult<NadDiagramResult> {
+    let network_id = resolve_network_id(&state, network_id)?;
+
+    // Create params for ZMQ request
+    let params = json!({
+        "voltage_level_ids": voltage_level_ids,
+        "depth": depth.unwrap_or(0),
+        "network_id": network_id
+    });
+
+    let result = cached_zmq_request(
+        &cache,
+        &state,
+        "get_network_area_diagram",
+        params,
+        network_id.as_deref(),
+    )
+    .await?;
+
+    // Parse response
+    let svg = result
+        .get("svg")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| PowsyblError::JsonParseError("Missing 'svg' field".to_string()))?
+        .to_string();
+
+    let metadata_value = result
+        .get("metadata")
+        .ok_or_else(|| PowsyblError::JsonParseError("Missing 'metadata' field".to_string()))?;
+
+    let metadata: NadMetadata = serde_json::from_value(metadata_value.clone())
+        .map_err(|e| PowsyblError::JsonParseError(e.to_string()))?;
+
+    Ok(NadDiagramResult { svg, metadata })
+}
+
+fn network_area_diagram_id(nad_metadata: &NadMetadata) -> String {
+    diagram_id(
+        "nad",
+        nad_metadata
+            .nodes
+            .iter()
+            .map(|node| node.equipment_id.clone()),
+    )
+}
+
+/// Subscribe to the live flows of every edge of a network area diagram
+#[tauri::command(rename_all = "snake_case")]
+pub async fn subscribe_network_area_diagram(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    nad_metadata: NadMetadata,
+    on_event: Channel<TelemetryCurves>,
+) -> PowsyblResult<SldSubscriptionResponse> {
+    let svg_ids: HashSet<String> = nad_metadata
+        .get_flow_edges()
+        .into_iter()
+        .map(|edge| edge.svg_id)
+        .collect();
+    info!("Flow edges found: {}", svg_ids.len());
+
+    // The telemetry is keyed by dynawo id, the diagram by svg id
+    let outputs = game_master_outputs(&state)?;
+    let outputs = outputs_for_ids(&outputs, &svg_ids);
+    let sink = TelemetrySink::Filtered {
+        topics: subscription_topics(outputs.iter().map(|output| Some(output.topic.as_str()))),
+        ids: outputs
+            .into_iter()
+            .filter_map(|output| Some((output.dynawo_id.clone(), output.graphical_id.clone()?)))
+            .collect(),
+        channel: on_event,
+    };
+    subscribe_diagram(
+        app_handle,
+        &state,
+        network_area_diagram_id(&nad_metadata),
+        sink,
+    )
+    .await?;
+
+    Ok(SldSubscriptionResponse {
+        status: "connected".to_string(),
+    })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn unsubscribe_network_area_diagram(
+    state: State<'_, AppState>,
+    nad_metadata: NadMetadata,
+) -> PowsyblResult<SldSubscriptionResponse> {
+    info!("Stopping network area diagram subscription");
+
+    unsubscribe_diagram(&state, &network_area_diagram_id(&nad_metadata));
+
+    Ok(SldSubscriptionResponse {
+        status: "disconnected".to_string(),
+    })
+}
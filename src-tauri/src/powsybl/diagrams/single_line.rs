@@ -1,5 +1,6 @@
+use super::super::cache::{cached_zmq_request, QueryCacheState};
 use super::super::errors::{PowsyblError, PowsyblResult};
-use super::send_zmq_request;
+use super::super::resolve_network_id;
 use super::sld_metadata::SldMetadata;
 use crate::state::AppState;
 
@@ -10,24 +11,35 @@ use tauri::State;
 /// Metadata and SVG result structure
 #[derive(Serialize, Deserialize)]
 pub struct DiagramResult {
-    svg: String,
-    metadata: SldMetadata,
+    pub(super) svg: String,
+    pub(super) metadata: SldMetadata,
 }
 
-/// Gets a single line diagram with metadata for a specific line ID using ZMQ
-#[tauri::command(rename_all = "snake_case")]
-pub async fn get_single_line_diagram_with_metadata(
-    _state: State<'_, AppState>,
-    line_id: String,
+// Helper function to fetch a single line diagram with its metadata
+pub(super) async fn fetch_single_line_diagram(
+    state: &State<'_, AppState>,
+    cache: &State<'_, QueryCacheState>,
+    line_id: &str,
+    network_id: Option<String>,
 ) -> PowsyblResult<DiagramResult> {
+    let network_id = resolve_network_id(state, network_id)?;
+
     // Create params for ZMQ request
     let params = json!({
         "id": line_id,
-        "format": "json"
+        "format": "json",
+        "network_id": network_id
     });
 
     // Send ZMQ request to get diagram with metadata
-    let result = send_zmq_request("get_single_line_diagram", Some(params)).await?;
+    let result = cached_zmq_request(
+        cache,
+        state,
+        "get_single_line_diagram",
+        params,
+        network_id.as_deref(),
+    )
+    .await?;
 
     // Parse response
     let svg = result
@@ -40,26 +52,48 @@ pub async fn get_single_line_diagram_with_metadata(
         .get("metadata")
         .ok_or_else(|| PowsyblError::JsonParseError("Missing 'metadata' field".to_string()))?;
 
-    let metadata: SldMetadata = serde_json::from_value(metadata_value.clone())
-        .map_err(|e| PowsyblError::JsonParseError(e.to_string()))?;
+    let metadata = SldMetadata::from_value(metadata_value.clone())?;
 
     Ok(DiagramResult { svg, metadata })
 }
 
+/// Gets a single line diagram with metadata for a specific line ID using ZMQ
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_single_line_diagram_with_metadata(
+    state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
+    line_id: String,
+    network_id: Option<String>,
+) -> PowsyblResult<DiagramResult> {
+    fetch_single_line_diagram(&state, &cache, &line_id, network_id).await
+}
+
 /// Gets only the SVG diagram for a specific line ID using ZMQ
 #[tauri::command(rename_all = "snake_case")]
 pub async fn get_single_line_diagram(
-    _state: State<'_, AppState>,
+    state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
     line_id: String,
+    network_id: Option<String>,
 ) -> PowsyblResult<Vec<u8>> {
+    let network_id = resolve_network_id(&state, network_id)?;
+
     // Create params for ZMQ request
     let params = json!({
         "id": line_id,
-        "format": "svg"
+        "format": "svg",
+        "network_id": network_id
     });
 
     // Send ZMQ request to get diagram SVG
-    let result = send_zmq_request("get_single_line_diagram", Some(params)).await?;
+    let result = cached_zmq_request(
+        &cache,
+        &state,
+        "get_single_line_diagram",
+        params,
+        network_id.as_deref(),
+    )
+    .await?;
 
     // Extract SVG content
     let svg = if let Some(content_type) = result.get("content_type") {
@@ -86,20 +120,29 @@ pub async fn get_single_line_diagram(
 /// Gets only the diagram metadata for a specific line ID using ZMQ
 #[tauri::command(rename_all = "snake_case")]
 pub async fn get_single_line_diagram_metadata(
-    _state: State<'_, AppState>,
+    state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
     line_id: String,
+    network_id: Option<String>,
 ) -> PowsyblResult<SldMetadata> {
+    let network_id = resolve_network_id(&state, network_id)?;
+
     // Create params for ZMQ request
     let params = json!({
-        "id": line_id
+        "id": line_id,
+        "network_id": network_id
     });
 
     // Send ZMQ request to get diagram metadata
-    let result = send_zmq_request("get_single_line_diagram_metadata", Some(params)).await?;
+    let result = cached_zmq_request(
+        &cache,
+        &state,
+        "get_single_line_diagram_metadata",
+        params,
+        network_id.as_deref(),
+    )
+    .await?;
 
     // Parse metadata
-    let metadata: SldMetadata =
-        serde_json::from_value(result).map_err(|e| PowsyblError::JsonParseError(e.to_string()))?;
-
-    Ok(metadata)
+    SldMetadata::from_value(result)
 }
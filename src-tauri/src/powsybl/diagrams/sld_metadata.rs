@@ -1,4 +1,108 @@
+use crate::powsybl::errors::{PowsyblError, PowsyblResult};
+
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+// Declare a PowSyBl name enum, (de)serialized from its SCREAMING_SNAKE_CASE name.
+// Names unknown to this version are kept in `Unknown` so newer PowSyBl releases still parse.
+macro_rules! powsybl_name_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $value:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(from = "String", into = "String")]
+        pub enum $name {
+            $($variant,)*
+            Unknown(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                match value.as_str() {
+                    $($value => $name::$variant,)*
+                    _ => $name::Unknown(value),
+                }
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.as_str().to_string()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+powsybl_name_enum!(
+    /// PowSyBl single line diagram component types
+    ComponentType {
+        ArrowActive => "ARROW_ACTIVE",
+        ArrowReactive => "ARROW_REACTIVE",
+        ArrowCurrent => "ARROW_CURRENT",
+        Battery => "BATTERY",
+        Breaker => "BREAKER",
+        BusConnection => "BUS_CONNECTION",
+        BusbarSection => "BUSBAR_SECTION",
+        Capacitor => "CAPACITOR",
+        DanglingLine => "DANGLING_LINE",
+        Disconnector => "DISCONNECTOR",
+        Generator => "GENERATOR",
+        Ground => "GROUND",
+        GroundDisconnection => "GROUND_DISCONNECTION",
+        Inductor => "INDUCTOR",
+        LccConverterStation => "LCC_CONVERTER_STATION",
+        Line => "LINE",
+        Load => "LOAD",
+        LoadBreakSwitch => "LOAD_BREAK_SWITCH",
+        Node => "NODE",
+        PhaseShiftTransformer => "PHASE_SHIFT_TRANSFORMER",
+        PhaseShiftTransformerLeg => "PHASE_SHIFT_TRANSFORMER_LEG",
+        StaticVarCompensator => "STATIC_VAR_COMPENSATOR",
+        ThreeWindingsTransformer => "THREE_WINDINGS_TRANSFORMER",
+        ThreeWindingsTransformerLeg => "THREE_WINDINGS_TRANSFORMER_LEG",
+        TieLine => "TIE_LINE",
+        TwoWindingsTransformer => "TWO_WINDINGS_TRANSFORMER",
+        TwoWindingsTransformerLeg => "TWO_WINDINGS_TRANSFORMER_LEG",
+        UnknownComponent => "UNKNOWN_COMPONENT",
+        VscConverterStation => "VSC_CONVERTER_STATION",
+    }
+);
+
+impl ComponentType {
+    pub fn is_switch(&self) -> bool {
+        matches!(
+            self,
+            ComponentType::Breaker
+                | ComponentType::Disconnector
+                | ComponentType::LoadBreakSwitch
+                | ComponentType::GroundDisconnection
+        )
+    }
+}
+
+powsybl_name_enum!(
+    /// Kind of value displayed by a feeder info
+    FeederInfoType {
+        ArrowActive => "ARROW_ACTIVE",
+        ArrowReactive => "ARROW_REACTIVE",
+        ArrowCurrent => "ARROW_CURRENT",
+    }
+);
 
 // Base structs
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -13,7 +117,7 @@ pub struct Size {
     pub height: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Padding {
     pub left: f64,
     pub top: f64,
@@ -68,7 +172,7 @@ pub struct Transformations {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Component {
     #[serde(rename = "type")]
-    pub component_type: String,
+    pub component_type: ComponentType,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "styleClass")]
@@ -95,7 +199,7 @@ pub struct Label {
 #[serde(rename_all = "camelCase")]
 pub struct FeederInfo {
     pub id: String,
-    pub component_type: String,
+    pub component_type: FeederInfoType,
     pub equipment_id: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -109,7 +213,7 @@ pub struct Node {
     pub id: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub component_type: Option<String>,
+    pub component_type: Option<ComponentType>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub equipment_id: Option<String>,
@@ -142,9 +246,45 @@ pub struct Wire {
     pub straight: bool,
 }
 
-// Layout parameters
+// Bus related structs
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BusInfo {
+    pub id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bus_node_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component_type: Option<ComponentType>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
+pub struct BusLegendInfo {
+    pub id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bus_node_id: Option<String>,
+}
+
+// Line drawn between two voltage levels of a multi-substation diagram
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LineMetadata {
+    pub id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id1: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id2: Option<String>,
+}
+
+// Layout parameters, defaulted field by field so that a PowSyBl release
+// renaming or dropping one still parses
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
 pub struct LayoutParams {
     pub adapt_cell_height_to_content: bool,
     pub busbars_alignment: String,
@@ -170,9 +310,9 @@ pub struct LayoutParams {
     pub zone_layout_snake_line_padding: f64,
 }
 
-// SVG parameters
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
+// SVG parameters, defaulted like the layout parameters
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
 pub struct SvgParams {
     pub active_power_unit: String,
     pub angle_label_shift: f64,
@@ -211,22 +351,87 @@ pub struct SvgParams {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SldMetadata {
-    pub bus_infos: Vec<serde_json::Value>,
-    pub bus_legend_infos: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub bus_infos: Vec<BusInfo>,
+    #[serde(default)]
+    pub bus_legend_infos: Vec<BusLegendInfo>,
     pub components: Vec<Component>,
     pub feeder_infos: Vec<FeederInfo>,
+    #[serde(default)]
     pub layout_params: LayoutParams,
-    pub lines: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub lines: Vec<LineMetadata>,
     pub nodes: Vec<Node>,
+    #[serde(default)]
     pub svg_params: SvgParams,
     pub wires: Vec<Wire>,
 }
 
 impl SldMetadata {
-    pub fn get_active_arrow_feeders(&self) -> Vec<FeederInfo> {
+    /// Parse metadata returned by the sidecar, reporting the path of the offending field
+    pub fn from_value(value: serde_json::Value) -> PowsyblResult<Self> {
+        let metadata: SldMetadata = serde_path_to_error::deserialize(value).map_err(|e| {
+            PowsyblError::SerdeJsonDetailedError {
+                path: e.path().to_string(),
+                message: e.inner().to_string(),
+                line: None,
+                column: None,
+            }
+        })?;
+
+        let problems = metadata.validate();
+        if !problems.is_empty() {
+            return Err(PowsyblError::InvalidMetadata(problems));
+        }
+        Ok(metadata)
+    }
+
+    /// Check that node ids are unique and that wires, bus infos and lines only
+    /// reference known nodes, listing every problem with the path of its field
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut node_ids = HashSet::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            if !node_ids.insert(node.id.as_str()) {
+                problems.push(format!("nodes[{}].id: duplicate node '{}'", index, node.id));
+            }
+        }
+
+        let mut check = |path: String, node_id: Option<&String>| {
+            if let Some(node_id) = node_id {
+                if !node_ids.contains(node_id.as_str()) {
+                    problems.push(format!("{}: unknown node '{}'", path, node_id));
+                }
+            }
+        };
+        for (index, wire) in self.wires.iter().enumerate() {
+            check(format!("wires[{}].nodeId1", index), Some(&wire.node_id1));
+            check(format!("wires[{}].nodeId2", index), Some(&wire.node_id2));
+        }
+        for (index, bus_info) in self.bus_infos.iter().enumerate() {
+            check(
+                format!("busInfos[{}].busNodeId", index),
+                bus_info.bus_node_id.as_ref(),
+            );
+        }
+        for (index, legend) in self.bus_legend_infos.iter().enumerate() {
+            check(
+                format!("busLegendInfos[{}].busNodeId", index),
+                legend.bus_node_id.as_ref(),
+            );
+        }
+        for (index, line) in self.lines.iter().enumerate() {
+            check(format!("lines[{}].nodeId1", index), line.node_id1.as_ref());
+            check(format!("lines[{}].nodeId2", index), line.node_id2.as_ref());
+        }
+
+        problems
+    }
+
+    pub fn get_feeder_infos(&self, types: &[FeederInfoType]) -> Vec<FeederInfo> {
         self.feeder_infos
             .iter()
-            .filter(|feeder| feeder.component_type == "ARROW_ACTIVE")
+            .filter(|feeder| types.contains(&feeder.component_type))
             .cloned()
             .collect()
     }
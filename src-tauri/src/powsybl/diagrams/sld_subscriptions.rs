@@ -1,82 +1,172 @@
-use super::super::entities::{SldSubscriptionResponse, TelemetryCurves};
+use super::super::entities::{FeederValues, SldSubscriptionResponse};
 use super::super::errors::PowsyblResult;
-use super::sld_metadata::SldMetadata;
+use super::sld_metadata::{FeederInfoType, SldMetadata};
+use super::telemetry_mapping::TelemetryMapping;
 
-use crate::powsybl::diagrams::entities::{ZmqConfig, ZmqSubscription};
+use crate::powsybl::diagrams::entities::{TelemetrySink, ZmqConfig, ZmqSubscription};
 use crate::powsybl::errors::PowsyblError;
-use crate::state::AppState;
+use crate::powsybl::state::PowsyblState;
+use crate::shared::entities::dynawo::GameMasterOutput;
+use crate::state::{AppState, AppStateInner};
 
 use log::{debug, error, info};
-use tauri::{ipc::Channel, State};
+use std::collections::BTreeSet;
+use tauri::{ipc::Channel, AppHandle, State};
 
-async fn create_subscription_task(
+/// Subscription id of a diagram, from its kind and the ids it is drawn around
+pub(super) fn diagram_id(kind: &str, ids: impl IntoIterator<Item = String>) -> String {
+    let ids: BTreeSet<String> = ids.into_iter().collect();
+    format!("{}:{}", kind, ids.into_iter().collect::<Vec<_>>().join(","))
+}
+
+/// Game master outputs, followed by the derived points of the loaded calculations
+pub(crate) fn game_master_outputs(
+    state: &State<'_, AppState>,
+) -> PowsyblResult<Vec<GameMasterOutput>> {
+    let state = state.read().map_err(|_| PowsyblError::LockError)?;
+    let mut outputs = state
+        .settings
+        .game_master_outputs
+        .clone()
+        .unwrap_or_default();
+    outputs.extend_from_slice(state.powsybl.calculations.outputs());
+    Ok(outputs)
+}
+
+/// Add a diagram, or any other consumer, to the telemetry subscription, starting
+/// it for the first one. A diagram already subscribed is kept as is: changing its
+/// selection takes an unsubscription first.
+pub(crate) async fn subscribe_diagram(
+    app_handle: AppHandle,
     state: &State<'_, AppState>,
-    feeder_id: String,
-    channel: Channel<TelemetryCurves>,
+    diagram_id: String,
+    sink: TelemetrySink,
 ) -> PowsyblResult<()> {
     let mut state_guard = state.write().map_err(|_| PowsyblError::LockError)?;
+    let zmq_config = zmq_config(&state_guard);
 
-    let zmq_config = ZmqConfig {
-        url: state_guard
+    let powsybl = &mut state_guard.powsybl;
+    if !powsybl.telemetry_sinks.insert(diagram_id.clone(), sink) {
+        debug!("Diagram {} is already subscribed, skipping", diagram_id);
+        return Ok(());
+    }
+    debug!("Diagram {} added to the telemetry subscription", diagram_id);
+
+    if !powsybl.telemetry_running() {
+        info!("Using ZMQ URL: {}", zmq_config.url);
+        spawn_subscription(powsybl, app_handle, zmq_config);
+    }
+
+    Ok(())
+}
+
+/// Connect the running telemetry subscription to the configured endpoint again,
+/// keeping its consumers, and return whether it was running
+pub(crate) fn restart_telemetry(
+    app_handle: AppHandle,
+    state: &State<'_, AppState>,
+) -> PowsyblResult<bool> {
+    let mut state_guard = state.write().map_err(|_| PowsyblError::LockError)?;
+    let zmq_config = zmq_config(&state_guard);
+
+    let powsybl = &mut state_guard.powsybl;
+    if !powsybl.stop_telemetry() || powsybl.telemetry_sinks.is_empty() {
+        return Ok(false);
+    }
+    info!("Using ZMQ URL: {}", zmq_config.url);
+    spawn_subscription(powsybl, app_handle, zmq_config);
+    Ok(true)
+}
+
+fn zmq_config(state: &AppStateInner) -> ZmqConfig {
+    ZmqConfig {
+        url: state
             .settings
             .zmq_url
             .clone()
             .unwrap_or_else(|| ZmqConfig::default().url),
-    };
+        ..ZmqConfig::default()
+    }
+}
 
-    info!("Using ZMQ URL: {}", zmq_config.url);
+fn spawn_subscription(powsybl: &mut PowsyblState, app_handle: AppHandle, zmq_config: ZmqConfig) {
+    let sinks = powsybl.telemetry_sinks.clone();
+    powsybl.spawn_telemetry(|shutdown_rx| {
+        let subscription = ZmqSubscription::new(zmq_config, sinks, app_handle);
 
-    state_guard
-        .powsybl
-        .spawn_task(feeder_id.clone(), |shutdown_rx| {
-            let subscription =
-                ZmqSubscription::new(feeder_id.clone(), zmq_config.clone(), channel.clone());
-
-            tokio::spawn(async move {
-                if let Err(e) = subscription.start(shutdown_rx).await {
-                    error!(
-                        "Subscription error for feeder {}: {:?}",
-                        feeder_id.clone(),
-                        e
-                    );
-                }
-            })
-        });
+        tokio::spawn(async move {
+            if let Err(e) = subscription.start(shutdown_rx).await {
+                error!("Telemetry subscription error: {:?}", e);
+            }
+        })
+    });
+}
 
-    Ok(())
+/// Remove a diagram from the telemetry subscription, which stops with its last
+/// consumer
+pub(crate) fn unsubscribe_diagram(state: &State<'_, AppState>, diagram_id: &str) {
+    debug!("Attempting to unsubscribe diagram: {}", diagram_id);
+
+    if let Ok(mut state_guard) = state.write() {
+        let powsybl = &mut state_guard.powsybl;
+        if !powsybl.telemetry_sinks.remove(diagram_id) {
+            debug!("No subscription found for diagram {}", diagram_id);
+            return;
+        }
+        info!("Diagram {} successfully unsubscribed", diagram_id);
+
+        if powsybl.telemetry_sinks.is_empty() && powsybl.stop_telemetry() {
+            info!("Telemetry subscription stopped, no consumer left");
+        }
+    }
 }
 
+fn single_line_diagram_id(sld_metadata: &SldMetadata) -> String {
+    diagram_id(
+        "sld",
+        sld_metadata.nodes.iter().map(|node| node.vid.clone()),
+    )
+}
+
+/// Subscribe to the live values of a single line diagram.
+///
+/// `feeder_types` selects the feeder infos to deliver (active power only by
+/// default) and `bus_infos` adds the voltage and angle of the bus infos. Values are
+/// tagged with their kind and unit, and status changes are emitted as
+/// `subscription-status` events.
 #[tauri::command(rename_all = "snake_case")]
 pub async fn subscribe_single_line_diagram(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     sld_metadata: SldMetadata,
-    on_event: Channel<TelemetryCurves>,
+    feeder_types: Option<Vec<FeederInfoType>>,
+    bus_infos: Option<bool>,
+    on_event: Channel<FeederValues>,
 ) -> PowsyblResult<SldSubscriptionResponse> {
     debug!(
         "subscribe_single_line_diagram called with SLD metadata: {:?}",
         sld_metadata
     );
 
-    let active_feeders = sld_metadata.get_active_arrow_feeders();
-    info!("Active feeders found: {}", active_feeders.len());
-
-    for feeder in active_feeders {
-        let feeder_id = feeder.id.clone();
-
-        // Check if task already exists
-        if state
-            .read()
-            .map_err(|_| PowsyblError::LockError)?
-            .powsybl
-            .has_task(&feeder_id)
-        {
-            debug!("Task already exists for feeder {}, skipping", feeder_id);
-            continue;
-        }
+    let feeder_types = feeder_types.unwrap_or_else(|| vec![FeederInfoType::ArrowActive]);
+    let mapping = TelemetryMapping::for_single_line_diagram(
+        &sld_metadata,
+        &feeder_types,
+        bus_infos.unwrap_or(false),
+        &game_master_outputs(&state)?,
+    );
 
-        // Create and spawn subscription task
-        create_subscription_task(&state, feeder_id, on_event.clone()).await?;
-    }
+    let sink = TelemetrySink::Mapped {
+        mapping,
+        channel: on_event,
+    };
+    subscribe_diagram(
+        app_handle,
+        &state,
+        single_line_diagram_id(&sld_metadata),
+        sink,
+    )
+    .await?;
 
     info!("subscribe_single_line_diagram completed successfully");
     Ok(SldSubscriptionResponse {
@@ -90,22 +180,8 @@ pub async fn unsubscribe_single_line_diagram(
     sld_metadata: SldMetadata,
 ) -> PowsyblResult<SldSubscriptionResponse> {
     info!("unsubscribe_single_line_diagram called");
-    let active_feeders = sld_metadata.get_active_arrow_feeders();
-    info!("Stopping tasks for {} active feeders", active_feeders.len());
-
-    for feeder in active_feeders {
-        let feeder_id = feeder.id.clone();
-        let task_id = feeder_id.clone();
-        debug!("Attempting to stop task for feeder: {}", feeder_id);
-
-        if let Ok(mut state_guard) = state.write() {
-            if state_guard.powsybl.stop_task(&task_id) {
-                info!("Task successfully stopped for feeder {}", feeder_id);
-            } else {
-                debug!("No task found for feeder {}", feeder_id);
-            }
-        }
-    }
+
+    unsubscribe_diagram(&state, &single_line_diagram_id(&sld_metadata));
 
     info!("unsubscribe_single_line_diagram completed");
     Ok(SldSubscriptionResponse {
@@ -0,0 +1,618 @@
+use super::super::entities::{SldSubscriptionResponse, TelemetryData};
+use super::super::errors::{PowsyblError, PowsyblResult};
+use super::super::switching::{SwitchConfig, SwitchLogState, SwitchState};
+use super::entities::TelemetrySink;
+use super::errors::{SubscriptionError, SubscriptionResult};
+use super::sld_metadata::{ComponentType, Node, SldMetadata};
+use super::sld_subscriptions::{
+    diagram_id, game_master_outputs, subscribe_diagram, unsubscribe_diagram,
+};
+use super::telemetry_mapping::subscription_topics;
+use crate::shared::entities::dynawo::GameMasterOutput;
+use crate::state::AppState;
+
+use log::info;
+use petgraph::graph::{NodeIndex, UnGraph};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tauri::{ipc::Channel, AppHandle, State};
+
+/// Electrical state of a diagram node or wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeState {
+    Energized,
+    Dead,
+    Earthed,
+}
+
+impl NodeState {
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            NodeState::Energized => "argus-energized",
+            NodeState::Dead => "argus-dead",
+            NodeState::Earthed => "argus-earthed",
+        }
+    }
+}
+
+/// Component types of the game master outputs telling whether a feeder carries a flow
+const FLOW_COMPONENT_TYPES: [&str; 3] = ["ARROW_ACTIVE", "ARROW_REACTIVE", "ARROW_CURRENT"];
+
+// Feeders bringing voltage from outside the diagram
+fn is_source(component_type: &ComponentType) -> bool {
+    matches!(
+        component_type,
+        ComponentType::Generator
+            | ComponentType::Battery
+            | ComponentType::Line
+            | ComponentType::TieLine
+            | ComponentType::DanglingLine
+            | ComponentType::TwoWindingsTransformer
+            | ComponentType::TwoWindingsTransformerLeg
+            | ComponentType::ThreeWindingsTransformer
+            | ComponentType::ThreeWindingsTransformerLeg
+            | ComponentType::PhaseShiftTransformer
+            | ComponentType::PhaseShiftTransformerLeg
+            | ComponentType::VscConverterStation
+            | ComponentType::LccConverterStation
+    )
+}
+
+#[derive(Debug, Clone)]
+struct TopologyNode {
+    id: String,
+    equipment_id: Option<String>,
+    component_type: Option<ComponentType>,
+    open: bool,
+}
+
+impl TopologyNode {
+    fn is_switch(&self) -> bool {
+        self.component_type
+            .as_ref()
+            .is_some_and(ComponentType::is_switch)
+    }
+
+    fn is_source(&self) -> bool {
+        self.component_type.as_ref().is_some_and(is_source)
+    }
+
+    fn is_earth(&self) -> bool {
+        matches!(
+            self.component_type,
+            Some(ComponentType::Ground | ComponentType::GroundDisconnection)
+        )
+    }
+
+    // Look a live value up by equipment id first, then by node id
+    fn lookup<'a, T>(&self, values: &'a HashMap<String, T>) -> Option<&'a T> {
+        self.equipment_id
+            .as_ref()
+            .and_then(|id| values.get(id))
+            .or_else(|| values.get(&self.id))
+    }
+}
+
+/// Connectivity graph of a single line diagram, built from its nodes and wires.
+///
+/// Switch positions and the state of external feeders are applied at evaluation
+/// time, so the same graph is reused for every telemetry update.
+#[derive(Debug, Default)]
+pub struct SldTopology {
+    graph: UnGraph<TopologyNode, String>,
+    node_index: HashMap<String, NodeIndex>,
+}
+
+impl SldTopology {
+    pub fn build(metadata: &SldMetadata) -> Self {
+        let mut topology = SldTopology::default();
+
+        for node in &metadata.nodes {
+            topology.add_node(node);
+        }
+
+        for wire in &metadata.wires {
+            let (Some(&a), Some(&b)) = (
+                topology.node_index.get(&wire.node_id1),
+                topology.node_index.get(&wire.node_id2),
+            ) else {
+                continue;
+            };
+            topology.graph.add_edge(a, b, wire.id.clone());
+        }
+
+        topology
+    }
+
+    fn add_node(&mut self, node: &Node) {
+        let index = self.graph.add_node(TopologyNode {
+            id: node.id.clone(),
+            equipment_id: node.equipment_id.clone(),
+            component_type: node.component_type.clone(),
+            open: node.open,
+        });
+        self.node_index.insert(node.id.clone(), index);
+    }
+
+    /// Work out the state of every node and wire.
+    ///
+    /// `switches` gives the live open state of switches and `sources` the live state
+    /// of external feeders, both keyed by equipment id or node id. Switches missing
+    /// from `switches` keep their position from the metadata, and feeders missing
+    /// from `sources` are considered energized. A part connected to a closed earthing
+    /// switch or a ground is earthed, whatever its sources.
+    pub fn evaluate(
+        &self,
+        switches: &HashMap<String, bool>,
+        sources: &HashMap<String, bool>,
+    ) -> HashMap<String, NodeState> {
+        let is_open =
+            |node: &TopologyNode| node.is_switch() && *node.lookup(switches).unwrap_or(&node.open);
+
+        let mut states: HashMap<NodeIndex, NodeState> = HashMap::new();
+        let mut visited = vec![false; self.graph.node_count()];
+
+        // Connected parts, without crossing open switches. Nodes without wires, such
+        // as voltage level labels, are not part of the topology
+        for start in self.graph.node_indices() {
+            if visited[start.index()]
+                || is_open(&self.graph[start])
+                || self.graph.neighbors(start).next().is_none()
+            {
+                continue;
+            }
+            visited[start.index()] = true;
+
+            let mut part = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(index) = queue.pop_front() {
+                for next in self.graph.neighbors(index) {
+                    if !visited[next.index()] && !is_open(&self.graph[next]) {
+                        visited[next.index()] = true;
+                        part.push(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+
+            let earthed = part.iter().any(|&index| self.graph[index].is_earth());
+            let energized = part.iter().any(|&index| {
+                let node = &self.graph[index];
+                node.is_source() && *node.lookup(sources).unwrap_or(&true)
+            });
+
+            let state = if earthed {
+                NodeState::Earthed
+            } else if energized {
+                NodeState::Energized
+            } else {
+                NodeState::Dead
+            };
+            states.extend(part.into_iter().map(|index| (index, state)));
+        }
+
+        // An open switch is live as soon as one of its sides is
+        for index in self.graph.node_indices() {
+            if !is_open(&self.graph[index]) {
+                continue;
+            }
+            let sides: Vec<NodeState> = self
+                .graph
+                .neighbors(index)
+                .filter_map(|next| states.get(&next).copied())
+                .collect();
+            let state = if sides.contains(&NodeState::Energized) {
+                NodeState::Energized
+            } else if sides.contains(&NodeState::Earthed) {
+                NodeState::Earthed
+            } else {
+                NodeState::Dead
+            };
+            states.insert(index, state);
+        }
+
+        let mut result: HashMap<String, NodeState> = states
+            .iter()
+            .map(|(&index, &state)| (self.graph[index].id.clone(), state))
+            .collect();
+
+        // Wires take the state of their closed end
+        for edge in self.graph.edge_indices() {
+            let Some((a, b)) = self.graph.edge_endpoints(edge) else {
+                continue;
+            };
+            let end = if is_open(&self.graph[a]) { b } else { a };
+            if let Some(&state) = states.get(&end) {
+                result.insert(self.graph[edge].clone(), state);
+            }
+        }
+
+        result
+    }
+}
+
+/// Work out which parts of a single line diagram are energized, dead or earthed
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_single_line_diagram_states(
+    sld_metadata: SldMetadata,
+    switches: Option<HashMap<String, bool>>,
+    sources: Option<HashMap<String, bool>>,
+) -> HashMap<String, NodeState> {
+    SldTopology::build(&sld_metadata)
+        .evaluate(&switches.unwrap_or_default(), &sources.unwrap_or_default())
+}
+
+/// Switch outputs giving the position of the switches of a diagram, with the
+/// equipment id, or node id, of their switch
+fn switch_outputs<'a>(
+    metadata: &SldMetadata,
+    outputs: &'a [GameMasterOutput],
+    config: &SwitchConfig,
+) -> Vec<(&'a GameMasterOutput, String)> {
+    metadata
+        .nodes
+        .iter()
+        .filter(|node| {
+            node.component_type
+                .as_ref()
+                .is_some_and(ComponentType::is_switch)
+        })
+        .filter_map(|node| {
+            let output = outputs.iter().find(|output| {
+                output
+                    .component_type
+                    .as_ref()
+                    .is_some_and(|c| config.component_types.contains(c))
+                    && (output.graphical_id.as_deref() == Some(node.id.as_str())
+                        || (node.equipment_id.is_some()
+                            && output.equipment_id == node.equipment_id))
+            })?;
+            let switch_id = node.equipment_id.clone().unwrap_or_else(|| node.id.clone());
+            Some((output, switch_id))
+        })
+        .collect()
+}
+
+/// Flow outputs of the external feeders of a diagram, with the equipment id of
+/// their feeder
+fn source_outputs<'a>(
+    metadata: &SldMetadata,
+    outputs: &'a [GameMasterOutput],
+) -> Vec<(&'a GameMasterOutput, String)> {
+    let feeders: HashSet<&str> = metadata
+        .nodes
+        .iter()
+        .filter(|node| node.component_type.as_ref().is_some_and(is_source))
+        .filter_map(|node| node.equipment_id.as_deref())
+        .collect();
+
+    outputs
+        .iter()
+        .filter(|output| {
+            output
+                .component_type
+                .as_deref()
+                .is_some_and(|c| FLOW_COMPONENT_TYPES.contains(&c))
+        })
+        .filter_map(|output| {
+            let equipment_id = output.equipment_id.as_deref()?;
+            feeders
+                .contains(equipment_id)
+                .then(|| (output, equipment_id.to_string()))
+        })
+        .collect()
+}
+
+// Switch positions and feeder flows received so far, and the states last pushed
+#[derive(Default)]
+struct LiveTopology {
+    positions: HashMap<String, bool>,
+    /// Whether each flow output of a feeder is above the tolerance, by telemetry id
+    flowing: HashMap<String, bool>,
+    states: Option<HashMap<String, NodeState>>,
+}
+
+/// Live topology of a single line diagram: the switch positions and feeder flows
+/// received from telemetry are applied to its graph, and the state of every node
+/// and wire is pushed whenever it changes.
+///
+/// A feeder is a live source while one of its flow outputs is above the switch
+/// tolerance. Feeders without flow outputs are considered live.
+#[derive(Clone)]
+pub struct TopologyFeed {
+    topology: Arc<SldTopology>,
+    /// Switch id of every switch output, by telemetry id
+    switches: Arc<HashMap<String, String>>,
+    /// Equipment id of the feeder of every flow output, by telemetry id
+    sources: Arc<HashMap<String, String>>,
+    config: SwitchConfig,
+    topics: Vec<String>,
+    live: Arc<Mutex<LiveTopology>>,
+    channel: Channel<HashMap<String, NodeState>>,
+}
+
+impl TopologyFeed {
+    /// ZMQ topic prefixes carrying the switch and flow outputs
+    pub fn topics(&self) -> Vec<String> {
+        self.topics.clone()
+    }
+
+    /// Apply the switch positions and feeder flows of a message, pushing the states
+    /// when a switch moved or a feeder started or stopped carrying a flow
+    pub fn send(&self, data: &TelemetryData) -> SubscriptionResult<()> {
+        let mut live = self
+            .live
+            .lock()
+            .map_err(|_| SubscriptionError::StateLockError)?;
+
+        let mut moved = false;
+        for (telemetry_id, switch_id) in self.switches.iter() {
+            let Some(&value) = data.values.get(telemetry_id) else {
+                continue;
+            };
+            // A switch between its positions is not relied upon to conduct
+            let open = self.config.state(value) != SwitchState::Closed;
+            moved |= live.positions.insert(switch_id.clone(), open) != Some(open);
+        }
+        for telemetry_id in self.sources.keys() {
+            let Some(&value) = data.values.get(telemetry_id) else {
+                continue;
+            };
+            let flowing = value.abs() > self.config.tolerance;
+            moved |= live.flowing.insert(telemetry_id.clone(), flowing) != Some(flowing);
+        }
+
+        if moved {
+            self.push_states(&mut live)?;
+        }
+        Ok(())
+    }
+
+    /// Push the states from the positions and flows received so far, and the
+    /// positions of the metadata for the other switches
+    pub fn push(&self) -> SubscriptionResult<()> {
+        let mut live = self
+            .live
+            .lock()
+            .map_err(|_| SubscriptionError::StateLockError)?;
+        self.push_states(&mut live)
+    }
+
+    fn push_states(&self, live: &mut LiveTopology) -> SubscriptionResult<()> {
+        let mut sources: HashMap<String, bool> = HashMap::new();
+        for (telemetry_id, feeder_id) in self.sources.iter() {
+            if let Some(&flowing) = live.flowing.get(telemetry_id) {
+                *sources.entry(feeder_id.clone()).or_default() |= flowing;
+            }
+        }
+
+        let states = self.topology.evaluate(&live.positions, &sources);
+        if live.states.as_ref() == Some(&states) {
+            return Ok(());
+        }
+        self.channel
+            .send(states.clone())
+            .map_err(|e| SubscriptionError::ChannelSendError(e.to_string()))?;
+        live.states = Some(states);
+        Ok(())
+    }
+}
+
+fn states_diagram_id(sld_metadata: &SldMetadata) -> String {
+    diagram_id(
+        "sld-states",
+        sld_metadata.nodes.iter().map(|node| node.vid.clone()),
+    )
+}
+
+/// Push the state of every node and wire of a single line diagram as its switches
+/// operate and its feeders are energized or lost.
+///
+/// Switch positions are read from the switch outputs of the game master outputs,
+/// with the open and closed values of the switch log configuration, and the state
+/// of the external feeders from their active, reactive and current outputs. The
+/// states from the current positions are pushed first, then on every change.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn subscribe_single_line_diagram_states(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    switching: State<'_, SwitchLogState>,
+    sld_metadata: SldMetadata,
+    on_event: Channel<HashMap<String, NodeState>>,
+) -> PowsyblResult<SldSubscriptionResponse> {
+    let config = switching.lock().await.status().config;
+    let outputs = game_master_outputs(&state)?;
+    let switches = switch_outputs(&sld_metadata, &outputs, &config);
+    let sources = source_outputs(&sld_metadata, &outputs);
+    info!(
+        "Diagram switches with a live position: {}, feeder flows: {}",
+        switches.len(),
+        sources.len()
+    );
+
+    let feed = TopologyFeed {
+        topology: Arc::new(SldTopology::build(&sld_metadata)),
+        topics: subscription_topics(
+            switches
+                .iter()
+                .chain(&sources)
+                .map(|(output, _)| Some(output.topic.as_str())),
+        ),
+        switches: Arc::new(
+            switches
+                .into_iter()
+                .map(|(output, switch_id)| (output.dynawo_id.clone(), switch_id))
+                .collect(),
+        ),
+        sources: Arc::new(
+            sources
+                .into_iter()
+                .map(|(output, feeder_id)| (output.dynawo_id.clone(), feeder_id))
+                .collect(),
+        ),
+        config,
+        live: Arc::default(),
+        channel: on_event,
+    };
+    feed.push()
+        .map_err(|e| PowsyblError::ApiError(e.to_string()))?;
+
+    // Without switch or flow outputs, the states never change
+    if !feed.switches.is_empty() || !feed.sources.is_empty() {
+        subscribe_diagram(
+            app_handle,
+            &state,
+            states_diagram_id(&sld_metadata),
+            TelemetrySink::Topology(feed),
+        )
+        .await?;
+    }
+
+    Ok(SldSubscriptionResponse {
+        status: "connected".to_string(),
+    })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn unsubscribe_single_line_diagram_states(
+    state: State<'_, AppState>,
+    sld_metadata: SldMetadata,
+) -> PowsyblResult<SldSubscriptionResponse> {
+    unsubscribe_diagram(&state, &states_diagram_id(&sld_metadata));
+
+    Ok(SldSubscriptionResponse {
+        status: "disconnected".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P1_METADATA: &str = include_str!("../../../../tests/diagrams/P1_metadata.json");
+    const ABRE_METADATA: &str = include_str!("../../../../tests/diagrams/ABRE_metadata.json");
+
+    const ABRE_RUBI: &str = ".ABRE 6 .RUBI 1";
+    const ABRE_PUJA: &str = ".ABRE 6 .PUJA 1";
+    const TR611_BREAKER: &str = ".ABRE 6_.ABRE6TR611 DJ_OC";
+    const TR611_DISCONNECTOR: &str = ".ABRE 6_.ABRE6TR611 SA.1_OC";
+
+    fn metadata(json: &str) -> SldMetadata {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn live(values: &[(&str, bool)]) -> HashMap<String, bool> {
+        values
+            .iter()
+            .map(|&(id, value)| (id.to_string(), value))
+            .collect()
+    }
+
+    #[test]
+    fn energizes_the_nodes_reached_by_a_live_feeder() {
+        let states = SldTopology::build(&metadata(P1_METADATA)).evaluate(&live(&[]), &live(&[]));
+
+        assert_eq!(states["idGEN"], NodeState::Energized);
+        assert_eq!(states["idNHV1"], NodeState::Energized);
+        assert_eq!(states["idNGEN"], NodeState::Energized);
+        // Voltage level labels have no wires
+        assert!(!states.contains_key("LABEL_VL_VLGEN"));
+    }
+
+    #[test]
+    fn one_live_feeder_is_enough() {
+        let topology = SldTopology::build(&metadata(P1_METADATA));
+
+        let states = topology.evaluate(
+            &live(&[]),
+            &live(&[("NHV1_NHV2_1", false), ("NHV1_NHV2_2", false)]),
+        );
+        assert_eq!(states["idNHV1"], NodeState::Energized);
+
+        let states = topology.evaluate(
+            &live(&[]),
+            &live(&[
+                ("GEN", false),
+                ("NGEN_NHV1", false),
+                ("NHV1_NHV2_1", false),
+                ("NHV1_NHV2_2", false),
+            ]),
+        );
+        assert!(states.values().all(|&state| state == NodeState::Dead));
+    }
+
+    #[test]
+    fn open_switches_isolate_their_feeder() {
+        let metadata = metadata(ABRE_METADATA);
+        let topology = SldTopology::build(&metadata);
+        let node_state = |states: &HashMap<String, NodeState>, equipment_id: &str| {
+            let node = metadata
+                .nodes
+                .iter()
+                .find(|node| node.equipment_id.as_deref() == Some(equipment_id))
+                .unwrap();
+            states[&node.id]
+        };
+
+        // The breaker and disconnector of TR611 are open in the metadata
+        let states = topology.evaluate(&live(&[]), &live(&[]));
+        assert_eq!(node_state(&states, ".ABRE6TR612"), NodeState::Energized);
+        assert_eq!(node_state(&states, ".ABRE6TR611"), NodeState::Dead);
+        // An open switch with a live side is live
+        assert_eq!(
+            node_state(&states, TR611_DISCONNECTOR),
+            NodeState::Energized
+        );
+
+        let states = topology.evaluate(
+            &live(&[(TR611_BREAKER, false), (TR611_DISCONNECTOR, false)]),
+            &live(&[]),
+        );
+        assert_eq!(node_state(&states, ".ABRE6TR611"), NodeState::Energized);
+    }
+
+    #[test]
+    fn lost_feeders_leave_the_substation_dead() {
+        let metadata = metadata(ABRE_METADATA);
+        let topology = SldTopology::build(&metadata);
+
+        let states = topology.evaluate(&live(&[]), &live(&[(ABRE_RUBI, false)]));
+        assert!(states.values().any(|&state| state == NodeState::Energized));
+
+        let states =
+            topology.evaluate(&live(&[]), &live(&[(ABRE_RUBI, false), (ABRE_PUJA, false)]));
+        assert!(states.values().all(|&state| state == NodeState::Dead));
+        // Wires are evaluated too
+        assert_eq!(states[&metadata.wires[0].id], NodeState::Dead);
+    }
+
+    #[test]
+    fn matches_the_flow_outputs_of_the_feeders() {
+        let output = |dynawo_id: &str, equipment_id: &str, component_type: &str| GameMasterOutput {
+            id: dynawo_id.to_string(),
+            dynawo_id: dynawo_id.to_string(),
+            topic: "topic".to_string(),
+            graphical_id: None,
+            equipment_id: Some(equipment_id.to_string()),
+            side: None,
+            component_type: Some(component_type.to_string()),
+            unit: None,
+        };
+        let outputs = [
+            output("rubi_i", ABRE_RUBI, "ARROW_CURRENT"),
+            output("puja_p", ABRE_PUJA, "ARROW_ACTIVE"),
+            output("tr612_p", ".ABRE6TR612", "ARROW_ACTIVE"),
+            output("rubi_breaker", ABRE_RUBI, "BREAKER"),
+        ];
+
+        let sources = source_outputs(&metadata(ABRE_METADATA), &outputs);
+
+        let sources: Vec<(&str, &str)> = sources
+            .iter()
+            .map(|(output, feeder_id)| (output.dynawo_id.as_str(), feeder_id.as_str()))
+            .collect();
+        assert_eq!(sources, [("rubi_i", ABRE_RUBI), ("puja_p", ABRE_PUJA)]);
+    }
+}
@@ -0,0 +1,531 @@
+use super::errors::{SubscriptionError, SubscriptionResult};
+use crate::powsybl::entities::{TelemetryCurves, TelemetryData};
+
+use serde::de::{DeserializeOwned, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Encoding of a telemetry payload, announced by a header frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryEncoding {
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+/// Content of a header frame: `<encoding>` for values or `<encoding>;dict` for an
+/// id dictionary, such as `msgpack` or `cbor;dict`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TelemetryHeader {
+    pub encoding: TelemetryEncoding,
+    pub dictionary: bool,
+}
+
+/// Longest header frame, so payloads are never mistaken for headers
+const MAX_HEADER_LEN: usize = 32;
+
+impl TelemetryHeader {
+    pub fn parse(frame: &[u8]) -> Option<Self> {
+        if frame.len() > MAX_HEADER_LEN {
+            return None;
+        }
+        let header = std::str::from_utf8(frame).ok()?.trim().to_ascii_lowercase();
+        let (encoding, dictionary) = match header.split_once(';') {
+            Some((encoding, "dict")) => (encoding, true),
+            Some(_) => return None,
+            None => (header.as_str(), false),
+        };
+
+        let encoding = match encoding {
+            "json" | "application/json" => TelemetryEncoding::Json,
+            "msgpack" | "application/msgpack" | "application/x-msgpack" => {
+                TelemetryEncoding::MessagePack
+            }
+            "cbor" | "application/cbor" => TelemetryEncoding::Cbor,
+            _ => return None,
+        };
+
+        Some(Self {
+            encoding,
+            dictionary,
+        })
+    }
+}
+
+impl std::fmt::Display for TelemetryHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let encoding = match self.encoding {
+            TelemetryEncoding::Json => "json",
+            TelemetryEncoding::MessagePack => "msgpack",
+            TelemetryEncoding::Cbor => "cbor",
+        };
+        if self.dictionary {
+            write!(f, "{};dict", encoding)
+        } else {
+            f.write_str(encoding)
+        }
+    }
+}
+
+/// Binary telemetry values, keyed by id or aligned with the id dictionary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinaryTelemetry {
+    pub time: u64,
+    pub values: BinaryValues,
+}
+
+/// A map of values by id, or an array of values in the order of the dictionary,
+/// where nil marks the ids without a value in this step
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum BinaryValues {
+    Named(HashMap<String, f64>),
+    Indexed(Vec<Option<f64>>),
+}
+
+// Dispatch on the encoded type directly, instead of buffering as `untagged` does
+impl<'de> Deserialize<'de> for BinaryValues {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ValuesVisitor;
+
+        impl<'de> Visitor<'de> for ValuesVisitor {
+            type Value = BinaryValues;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a map of values by id or an array of values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut values = HashMap::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some((id, value)) = map.next_entry()? {
+                    values.insert(id, value);
+                }
+                Ok(BinaryValues::Named(values))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(value) = seq.next_element()? {
+                    values.push(value);
+                }
+                Ok(BinaryValues::Indexed(values))
+            }
+        }
+
+        deserializer.deserialize_any(ValuesVisitor)
+    }
+}
+
+/// Last id dictionary received on a telemetry feed.
+///
+/// PUB/SUB never replays a message to a late subscriber, so publishers of indexed
+/// values must re-send their dictionary periodically, and not only when it
+/// changes. Until then, indexed values cannot be resolved. The dictionary is
+/// shared by the decoders of the feed, so a subscription restarted later resolves
+/// indexed values with the dictionary received before.
+#[derive(Debug, Clone, Default)]
+pub struct TelemetryDictionary {
+    ids: Arc<RwLock<Arc<Vec<String>>>>,
+}
+
+impl TelemetryDictionary {
+    // A dictionary is replaced in one assignment, so a poisoned lock is still usable
+    fn get(&self) -> Arc<Vec<String>> {
+        self.ids
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn set(&self, ids: Vec<String>) {
+        *self
+            .ids
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(ids);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.get().is_empty()
+    }
+
+    /// Forget the dictionary, when the feed starts over
+    pub fn clear(&self) {
+        self.set(Vec::new());
+    }
+
+    /// Frames of a dictionary message announcing the current ids, so a recording
+    /// started after the publisher sent them can still be replayed on its own
+    pub fn to_frames(&self) -> Option<Vec<Vec<u8>>> {
+        let ids = self.get();
+        if ids.is_empty() {
+            return None;
+        }
+        let header = TelemetryHeader {
+            encoding: TelemetryEncoding::Json,
+            dictionary: true,
+        };
+        let payload = serde_json::to_vec(ids.as_slice()).ok()?;
+        Some(vec![header.to_string().into_bytes(), payload])
+    }
+}
+
+/// Decoder of a telemetry feed.
+///
+/// Messages are `[topic?, header?, payload]`. Without a header the payload is
+/// JSON, as published so far. A dictionary message carries the list of ids, after
+/// which values can be sent as a bare array in the order of the dictionary.
+#[derive(Debug, Default)]
+pub struct TelemetryDecoder {
+    dictionary: TelemetryDictionary,
+}
+
+impl TelemetryDecoder {
+    /// Decoder sharing the dictionary of the other decoders of its feed
+    pub fn new(dictionary: TelemetryDictionary) -> Self {
+        Self { dictionary }
+    }
+
+    /// Whether indexed values can be resolved
+    pub fn has_dictionary(&self) -> bool {
+        !self.dictionary.is_empty()
+    }
+
+    /// Decode a message from its frames; heartbeats and dictionaries give `None`.
+    /// Indexed values received before any dictionary give a
+    /// [`SubscriptionError::MissingDictionary`].
+    pub fn decode<'a>(
+        &mut self,
+        frames: impl IntoIterator<Item = &'a [u8]>,
+    ) -> SubscriptionResult<Option<TelemetryCurves>> {
+        let frames: Vec<&[u8]> = frames.into_iter().collect();
+        let Some((&payload, rest)) = frames.split_last() else {
+            return Ok(None);
+        };
+
+        match rest.last().and_then(|frame| TelemetryHeader::parse(frame)) {
+            Some(header) if header.dictionary => {
+                self.dictionary
+                    .set(decode_binary(header.encoding, payload)?);
+                Ok(None)
+            }
+            Some(TelemetryHeader {
+                encoding: TelemetryEncoding::Json,
+                ..
+            }) => decode_json(payload),
+            Some(header) => {
+                let telemetry: BinaryTelemetry = decode_binary(header.encoding, payload)?;
+                self.resolve(telemetry).map(Some)
+            }
+            None => decode_json(payload),
+        }
+    }
+
+    fn resolve(&self, telemetry: BinaryTelemetry) -> SubscriptionResult<TelemetryCurves> {
+        let values = match telemetry.values {
+            BinaryValues::Named(values) => values,
+            BinaryValues::Indexed(values) => {
+                let dictionary = self.dictionary.get();
+                if dictionary.is_empty() {
+                    return Err(SubscriptionError::MissingDictionary);
+                }
+                if values.len() > dictionary.len() {
+                    return Err(SubscriptionError::DecodeError(format!(
+                        "{} values for a dictionary of {} ids",
+                        values.len(),
+                        dictionary.len()
+                    )));
+                }
+                // NaN is a value, left to the quality checks
+                dictionary
+                    .iter()
+                    .zip(values)
+                    .filter_map(|(id, value)| Some((id.clone(), value?)))
+                    .collect()
+            }
+        };
+
+        Ok(TelemetryCurves {
+            curves: TelemetryData {
+                values,
+                time: telemetry.time,
+            },
+            quality: HashMap::new(),
+        })
+    }
+}
+
+// Plain JSON payloads may still be prefixed by their topic in the same frame
+fn decode_json(payload: &[u8]) -> SubscriptionResult<Option<TelemetryCurves>> {
+    let Some(start) = payload.iter().position(|&byte| byte == b'{') else {
+        // Heartbeats carry no telemetry
+        return Ok(None);
+    };
+    Ok(Some(serde_json::from_slice(&payload[start..])?))
+}
+
+fn decode_binary<T: DeserializeOwned>(
+    encoding: TelemetryEncoding,
+    payload: &[u8],
+) -> SubscriptionResult<T> {
+    match encoding {
+        TelemetryEncoding::Json => Ok(serde_json::from_slice(payload)?),
+        TelemetryEncoding::MessagePack => rmp_serde::from_slice(payload)
+            .map_err(|e| SubscriptionError::DecodeError(e.to_string())),
+        TelemetryEncoding::Cbor => ciborium::from_reader(payload)
+            .map_err(|e| SubscriptionError::DecodeError(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(encoding: TelemetryEncoding, dictionary: bool) -> Vec<u8> {
+        TelemetryHeader {
+            encoding,
+            dictionary,
+        }
+        .to_string()
+        .into_bytes()
+    }
+
+    fn msgpack<T: Serialize>(value: &T) -> Vec<u8> {
+        rmp_serde::to_vec_named(value).unwrap()
+    }
+
+    fn cbor<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        ciborium::into_writer(value, &mut buffer).unwrap();
+        buffer
+    }
+
+    fn indexed(values: Vec<Option<f64>>) -> BinaryTelemetry {
+        BinaryTelemetry {
+            time: 7,
+            values: BinaryValues::Indexed(values),
+        }
+    }
+
+    fn ids() -> Vec<String> {
+        vec!["a".to_string(), "b".to_string(), "c".to_string()]
+    }
+
+    #[test]
+    fn parses_headers() {
+        let parse = |header: &str| TelemetryHeader::parse(header.as_bytes());
+
+        assert_eq!(
+            parse("msgpack"),
+            Some(TelemetryHeader {
+                encoding: TelemetryEncoding::MessagePack,
+                dictionary: false,
+            })
+        );
+        assert_eq!(
+            parse(" CBOR;dict\n"),
+            Some(TelemetryHeader {
+                encoding: TelemetryEncoding::Cbor,
+                dictionary: true,
+            })
+        );
+        assert_eq!(
+            parse("application/x-msgpack").map(|h| h.encoding),
+            Some(TelemetryEncoding::MessagePack)
+        );
+        assert_eq!(
+            parse("application/json").map(|h| h.encoding),
+            Some(TelemetryEncoding::Json)
+        );
+
+        assert_eq!(parse("msgpack;zstd"), None);
+        assert_eq!(parse("protobuf"), None);
+        assert_eq!(parse(r#"{"curves":{}}"#), None);
+        assert_eq!(TelemetryHeader::parse(&[0xff, 0xfe]), None);
+        // A payload starting like a header is too long to be one
+        assert_eq!(parse(&format!("json{}", " ".repeat(MAX_HEADER_LEN))), None);
+    }
+
+    #[test]
+    fn headers_round_trip() {
+        for encoding in [
+            TelemetryEncoding::Json,
+            TelemetryEncoding::MessagePack,
+            TelemetryEncoding::Cbor,
+        ] {
+            for dictionary in [false, true] {
+                let frame = header(encoding, dictionary);
+                assert_eq!(
+                    TelemetryHeader::parse(&frame),
+                    Some(TelemetryHeader {
+                        encoding,
+                        dictionary
+                    })
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_json_without_header() {
+        let mut decoder = TelemetryDecoder::default();
+
+        let telemetry = decoder
+            .decode([
+                b"topic".as_slice(),
+                br#"topic {"curves":{"values":{"a":1.5},"time":3}}"#,
+            ])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(telemetry.curves.time, 3);
+        assert_eq!(
+            telemetry.curves.values,
+            HashMap::from([("a".to_string(), 1.5)])
+        );
+        // Heartbeats carry no telemetry
+        assert!(decoder.decode([b"heartbeat".as_slice()]).unwrap().is_none());
+    }
+
+    #[test]
+    fn decodes_keyed_values() {
+        let telemetry = BinaryTelemetry {
+            time: 42,
+            values: BinaryValues::Named(HashMap::from([
+                ("a".to_string(), 1.0),
+                ("b".to_string(), f64::NAN),
+            ])),
+        };
+
+        for (encoding, payload) in [
+            (TelemetryEncoding::MessagePack, msgpack(&telemetry)),
+            (TelemetryEncoding::Cbor, cbor(&telemetry)),
+        ] {
+            let mut decoder = TelemetryDecoder::default();
+            let decoded = decoder
+                .decode([b"topic".as_slice(), &header(encoding, false), &payload])
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(decoded.curves.time, 42);
+            assert_eq!(decoded.curves.values["a"], 1.0);
+            assert!(decoded.curves.values["b"].is_nan());
+        }
+    }
+
+    #[test]
+    fn resolves_indexed_values_with_the_dictionary() {
+        for (encoding, encode) in [
+            (
+                TelemetryEncoding::MessagePack,
+                msgpack::<BinaryTelemetry> as fn(&BinaryTelemetry) -> Vec<u8>,
+            ),
+            (TelemetryEncoding::Cbor, cbor::<BinaryTelemetry>),
+        ] {
+            let dictionary = match encoding {
+                TelemetryEncoding::Cbor => cbor(&ids()),
+                _ => msgpack(&ids()),
+            };
+            let mut decoder = TelemetryDecoder::default();
+            assert!(decoder
+                .decode([header(encoding, true).as_slice(), &dictionary])
+                .unwrap()
+                .is_none());
+            assert!(decoder.has_dictionary());
+
+            let payload = encode(&indexed(vec![Some(1.0), None, Some(f64::NAN)]));
+            let decoded = decoder
+                .decode([header(encoding, false).as_slice(), &payload])
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(decoded.curves.time, 7);
+            assert_eq!(decoded.curves.values.len(), 2);
+            assert_eq!(decoded.curves.values["a"], 1.0);
+            // Nil marks a missing value, NaN is a value left to the quality checks
+            assert!(!decoded.curves.values.contains_key("b"));
+            assert!(decoded.curves.values["c"].is_nan());
+        }
+    }
+
+    #[test]
+    fn needs_a_dictionary_for_indexed_values() {
+        let mut decoder = TelemetryDecoder::default();
+        let data_header = header(TelemetryEncoding::MessagePack, false);
+        let payload = msgpack(&indexed(vec![Some(1.0)]));
+
+        assert!(!decoder.has_dictionary());
+        assert!(matches!(
+            decoder.decode([data_header.as_slice(), &payload]),
+            Err(SubscriptionError::MissingDictionary)
+        ));
+
+        let dict_header = header(TelemetryEncoding::MessagePack, true);
+        decoder
+            .decode([dict_header.as_slice(), &msgpack(&vec!["a"])])
+            .unwrap();
+        let too_many = msgpack(&indexed(vec![Some(1.0), Some(2.0)]));
+        assert!(matches!(
+            decoder.decode([data_header.as_slice(), &too_many]),
+            Err(SubscriptionError::DecodeError(_))
+        ));
+    }
+
+    #[test]
+    fn writes_the_dictionary_as_a_message() {
+        let dictionary = TelemetryDictionary::default();
+        assert!(dictionary.to_frames().is_none());
+        TelemetryDecoder::new(dictionary.clone())
+            .decode([
+                header(TelemetryEncoding::Cbor, true).as_slice(),
+                &cbor(&ids()),
+            ])
+            .unwrap();
+
+        let frames = dictionary.to_frames().unwrap();
+        let mut decoder = TelemetryDecoder::default();
+        assert!(decoder
+            .decode(frames.iter().map(Vec::as_slice))
+            .unwrap()
+            .is_none());
+        let decoded = decoder
+            .decode([
+                header(TelemetryEncoding::Cbor, false).as_slice(),
+                &cbor(&indexed(vec![Some(1.0), None, Some(3.0)])),
+            ])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            decoded.curves.values,
+            HashMap::from([("a".to_string(), 1.0), ("c".to_string(), 3.0)])
+        );
+    }
+
+    #[test]
+    fn shares_the_dictionary_of_the_feed() {
+        let dictionary = TelemetryDictionary::default();
+        let mut first = TelemetryDecoder::new(dictionary.clone());
+        let mut second = TelemetryDecoder::new(dictionary.clone());
+
+        first
+            .decode([
+                header(TelemetryEncoding::Cbor, true).as_slice(),
+                &cbor(&ids()),
+            ])
+            .unwrap();
+        let decoded = second
+            .decode([
+                header(TelemetryEncoding::Cbor, false).as_slice(),
+                &cbor(&indexed(vec![None, Some(2.0)])),
+            ])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            decoded.curves.values,
+            HashMap::from([("b".to_string(), 2.0)])
+        );
+
+        dictionary.clear();
+        assert!(!second.has_dictionary());
+    }
+}
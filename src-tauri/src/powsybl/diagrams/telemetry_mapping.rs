@@ -0,0 +1,248 @@
+use super::super::entities::{FeederValue, FeederValues, MeasurementKind, Quality, TelemetryData};
+use super::super::quality::QualityMonitor;
+use super::sld_metadata::{FeederInfoType, SldMetadata};
+use crate::shared::entities::dynawo::GameMasterOutput;
+
+use log::debug;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+impl MeasurementKind {
+    pub fn from_feeder_info_type(component_type: &FeederInfoType) -> Option<Self> {
+        match component_type {
+            FeederInfoType::ArrowActive => Some(MeasurementKind::ActivePower),
+            FeederInfoType::ArrowReactive => Some(MeasurementKind::ReactivePower),
+            FeederInfoType::ArrowCurrent => Some(MeasurementKind::Current),
+            FeederInfoType::Unknown(_) => None,
+        }
+    }
+
+    pub fn is_bus(&self) -> bool {
+        matches!(
+            self,
+            MeasurementKind::BusVoltage | MeasurementKind::BusAngle
+        )
+    }
+
+    /// Component type of the game master outputs carrying this kind
+    pub fn output_component_type(&self) -> &'static str {
+        match self {
+            MeasurementKind::ActivePower => "ARROW_ACTIVE",
+            MeasurementKind::ReactivePower => "ARROW_REACTIVE",
+            MeasurementKind::Current => "ARROW_CURRENT",
+            MeasurementKind::BusVoltage => "BUS_VOLTAGE",
+            MeasurementKind::BusAngle => "BUS_ANGLE",
+        }
+    }
+}
+
+/// A diagram measurement and the telemetry output it is read from
+#[derive(Debug, Clone)]
+pub struct MeasurementOutput {
+    pub graphical_id: String,
+    pub kind: MeasurementKind,
+    pub side: Option<String>,
+    pub unit: Option<String>,
+    pub telemetry_id: String,
+    pub topic: String,
+}
+
+/// Link between the measurements of a diagram and the telemetry values
+#[derive(Debug, Clone, Default)]
+pub struct TelemetryMapping {
+    outputs: Vec<MeasurementOutput>,
+}
+
+impl TelemetryMapping {
+    /// Map the feeder infos of the given types, and optionally the bus infos, of a
+    /// single line diagram to the game master outputs.
+    ///
+    /// An output is matched on the graphical id first, then on the equipment id, side
+    /// and component type. Measurements without a matching output are left out.
+    pub fn for_single_line_diagram(
+        metadata: &SldMetadata,
+        feeder_types: &[FeederInfoType],
+        bus_infos: bool,
+        outputs: &[GameMasterOutput],
+    ) -> Self {
+        let svg_params = &metadata.svg_params;
+        let mut mapping = TelemetryMapping::default();
+
+        for feeder in metadata.get_feeder_infos(feeder_types) {
+            let Some(kind) = MeasurementKind::from_feeder_info_type(&feeder.component_type) else {
+                continue;
+            };
+            let default_unit = match kind {
+                MeasurementKind::ReactivePower => &svg_params.reactive_power_unit,
+                MeasurementKind::Current => &svg_params.current_unit,
+                _ => &svg_params.active_power_unit,
+            };
+            mapping.push(
+                outputs,
+                &feeder.id,
+                Some(&feeder.equipment_id),
+                feeder.side.as_deref(),
+                kind,
+                default_unit,
+            );
+        }
+
+        if bus_infos {
+            for bus_info in &metadata.bus_infos {
+                // Bus infos display the values of the busbar section they are attached to
+                let equipment_id = bus_info
+                    .bus_node_id
+                    .as_ref()
+                    .and_then(|id| metadata.nodes.iter().find(|node| &node.id == id))
+                    .and_then(|node| node.equipment_id.as_ref());
+
+                for (kind, default_unit) in [
+                    (MeasurementKind::BusVoltage, "kV"),
+                    // Same unit as the range checked by the data quality monitor
+                    (MeasurementKind::BusAngle, "deg"),
+                ] {
+                    mapping.push(
+                        outputs,
+                        &bus_info.id,
+                        equipment_id,
+                        None,
+                        kind,
+                        default_unit,
+                    );
+                }
+            }
+        }
+
+        debug!("Mapped {} diagram measurements", mapping.outputs.len());
+        mapping
+    }
+
+    fn push(
+        &mut self,
+        outputs: &[GameMasterOutput],
+        graphical_id: &str,
+        equipment_id: Option<&String>,
+        side: Option<&str>,
+        kind: MeasurementKind,
+        default_unit: &str,
+    ) {
+        // A bus info has one graphical id for both its voltage and angle,
+        // so the component type is required to tell its outputs apart
+        let component_type = kind.output_component_type();
+        let Some(output) = outputs
+            .iter()
+            .find(|output| {
+                output.graphical_id.as_deref() == Some(graphical_id)
+                    && output
+                        .component_type
+                        .as_deref()
+                        .map_or(!kind.is_bus(), |c| c == component_type)
+            })
+            .or_else(|| {
+                outputs.iter().find(|output| {
+                    equipment_id.is_some()
+                        && output.equipment_id.as_ref() == equipment_id
+                        && output.side.as_deref() == side
+                        && output.component_type.as_deref() == Some(component_type)
+                })
+            })
+        else {
+            // Reading another value under this id would show it as the wrong kind
+            debug!("No telemetry output for {:?} of {}", kind, graphical_id);
+            return;
+        };
+
+        let unit = output
+            .unit
+            .clone()
+            .or_else(|| (!default_unit.is_empty()).then(|| default_unit.to_string()));
+
+        self.outputs.push(MeasurementOutput {
+            graphical_id: graphical_id.to_string(),
+            kind,
+            side: side.map(str::to_string),
+            unit,
+            telemetry_id: output.dynawo_id.clone(),
+            topic: output.topic.clone(),
+        });
+    }
+
+    /// ZMQ topic prefixes carrying the mapped measurements
+    pub fn topics(&self) -> Vec<String> {
+        subscription_topics(
+            self.outputs
+                .iter()
+                .map(|output| Some(output.topic.as_str())),
+        )
+    }
+
+    /// Telemetry ids read by the mapped measurements
+    pub fn telemetry_ids(&self) -> impl Iterator<Item = &str> {
+        self.outputs
+            .iter()
+            .map(|output| output.telemetry_id.as_str())
+    }
+
+    /// Quality of the mapped measurements that are not good, by graphical id
+    pub fn degraded(&self, quality: &QualityMonitor) -> HashMap<String, Quality> {
+        let quality = quality.degraded(self.telemetry_ids());
+        self.outputs
+            .iter()
+            .filter_map(|output| {
+                let &quality = quality.get(&output.telemetry_id)?;
+                Some((output.graphical_id.clone(), quality))
+            })
+            .collect()
+    }
+
+    /// Pick and tag the mapped measurements of a telemetry message, with the
+    /// quality of the ones that are not good
+    pub fn apply(&self, data: &TelemetryData, quality: &QualityMonitor) -> FeederValues {
+        FeederValues {
+            values: self
+                .outputs
+                .iter()
+                .filter_map(|output| {
+                    let &value = data.values.get(&output.telemetry_id)?;
+                    Some(FeederValue {
+                        id: output.graphical_id.clone(),
+                        kind: output.kind,
+                        value,
+                        unit: output.unit.clone(),
+                        side: output.side.clone(),
+                    })
+                })
+                .collect(),
+            time: data.time,
+            quality: self.degraded(quality),
+        }
+    }
+}
+
+/// Game master outputs drawn with one of the given graphical ids
+pub fn outputs_for_ids<'a>(
+    outputs: &'a [GameMasterOutput],
+    ids: &HashSet<String>,
+) -> Vec<&'a GameMasterOutput> {
+    outputs
+        .iter()
+        .filter(|output| {
+            output
+                .graphical_id
+                .as_ref()
+                .is_some_and(|id| ids.contains(id))
+        })
+        .collect()
+}
+
+/// ZMQ topic prefixes of the given outputs. As soon as one output has no known
+/// topic, every message is needed.
+pub fn subscription_topics<'a>(topics: impl Iterator<Item = Option<&'a str>>) -> Vec<String> {
+    let mut prefixes = BTreeSet::new();
+    for topic in topics {
+        match topic {
+            Some(topic) if !topic.is_empty() => prefixes.insert(topic.to_string()),
+            _ => return vec![String::new()],
+        };
+    }
+    prefixes.into_iter().collect()
+}
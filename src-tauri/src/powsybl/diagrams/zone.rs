@@ -0,0 +1,157 @@
+use super::super::cache::{cached_zmq_request, QueryCacheState};
+use super::super::entities::{FeederValues, SldSubscriptionResponse};
+use super::super::errors::{PowsyblError, PowsyblResult};
+use super::super::resolve_network_id;
+use super::entities::TelemetrySink;
+use super::sld_metadata::{FeederInfoType, SldMetadata};
+use super::sld_subscriptions::{
+    diagram_id, game_master_outputs, subscribe_diagram, unsubscribe_diagram,
+};
+use super::telemetry_mapping::TelemetryMapping;
+use crate::state::AppState;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{ipc::Channel, AppHandle, State};
+
+/// Location of a feeder of a zone diagram
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ZoneFeeder {
+    pub feeder_id: String,
+    pub equipment_id: String,
+    pub component_type: FeederInfoType,
+    pub side: Option<String>,
+    pub voltage_level_id: String,
+    pub substation_id: Option<String>,
+}
+
+/// Zone layout settings of the `LayoutParams`, the sidecar defaults when unset
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ZoneLayoutParams {
+    pub zone_layout_snake_line_padding: Option<f64>,
+}
+
+/// Zone diagram SVG, its merged metadata and the substation of every feeder
+#[derive(Serialize, Deserialize)]
+pub struct ZoneDiagramResult {
+    svg: String,
+    metadata: SldMetadata,
+    feeder_mapping: Vec<ZoneFeeder>,
+}
+
+fn zone_diagram_id(metadata: &SldMetadata) -> String {
+    diagram_id("zone", metadata.nodes.iter().map(|node| node.vid.clone()))
+}
+
+/// Gets a single line diagram spanning several substations using ZMQ.
+///
+/// `layout` overrides the zone layout parameters; the ones applied are reported
+/// in the `layout_params` of the metadata.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_zone_single_line_diagram(
+    state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
+    substation_ids: Vec<String>,
+    layout: Option<ZoneLayoutParams>,
+    network_id: Option<String>,
+) -> PowsyblResult<ZoneDiagramResult> {
+    let network_id = resolve_network_id(&state, network_id)?;
+    let layout = layout.unwrap_or_default();
+
+    // Create params for ZMQ request
+    let params = json!({
+        "substation_ids": substation_ids,
+        "layout": layout,
+        "network_id": network_id
+    });
+
+    let result = cached_zmq_request(
+        &cache,
+        &state,
+        "get_zone_single_line_diagram",
+        params,
+        network_id.as_deref(),
+    )
+    .await?;
+
+    let svg = result
+        .get("svg")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| PowsyblError::JsonParseError("Missing 'svg' field".to_string()))?
+        .to_string();
+
+    let metadata_value = result
+        .get("metadata")
+        .ok_or_else(|| PowsyblError::JsonParseError("Missing 'metadata' field".to_string()))?;
+    let metadata = SldMetadata::from_value(metadata_value.clone())?;
+    if let Some(padding) = layout.zone_layout_snake_line_padding {
+        let applied = metadata.layout_params.zone_layout_snake_line_padding;
+        if applied != padding {
+            warn!(
+                "Zone snake line padding {} not applied by the sidecar, {} used",
+                padding, applied
+            );
+        }
+    }
+
+    let feeder_mapping = match result.get("feeder_mapping") {
+        Some(mapping) => serde_json::from_value(mapping.clone())
+            .map_err(|e| PowsyblError::JsonParseError(e.to_string()))?,
+        None => Vec::new(),
+    };
+
+    Ok(ZoneDiagramResult {
+        svg,
+        metadata,
+        feeder_mapping,
+    })
+}
+
+/// Subscribe to the live values of every feeder of a zone diagram.
+///
+/// The feeder infos and bus infos are selected as for
+/// `subscribe_single_line_diagram`, active power only by default.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn subscribe_zone_single_line_diagram(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    metadata: SldMetadata,
+    feeder_types: Option<Vec<FeederInfoType>>,
+    bus_infos: Option<bool>,
+    on_event: Channel<FeederValues>,
+) -> PowsyblResult<SldSubscriptionResponse> {
+    info!("Zone feeders found: {}", metadata.feeder_infos.len());
+
+    let feeder_types = feeder_types.unwrap_or_else(|| vec![FeederInfoType::ArrowActive]);
+    let mapping = TelemetryMapping::for_single_line_diagram(
+        &metadata,
+        &feeder_types,
+        bus_infos.unwrap_or(false),
+        &game_master_outputs(&state)?,
+    );
+
+    let sink = TelemetrySink::Mapped {
+        mapping,
+        channel: on_event,
+    };
+    subscribe_diagram(app_handle, &state, zone_diagram_id(&metadata), sink).await?;
+
+    Ok(SldSubscriptionResponse {
+        status: "connected".to_string(),
+    })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn unsubscribe_zone_single_line_diagram(
+    state: State<'_, AppState>,
+    metadata: SldMetadata,
+) -> PowsyblResult<SldSubscriptionResponse> {
+    info!("Stopping zone diagram subscription");
+
+    unsubscribe_diagram(&state, &zone_diagram_id(&metadata));
+
+    Ok(SldSubscriptionResponse {
+        status: "disconnected".to_string(),
+    })
+}
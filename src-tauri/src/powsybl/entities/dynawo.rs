@@ -4,6 +4,10 @@ use std::collections::HashMap;
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct TelemetryCurves {
     pub curves: TelemetryData,
+
+    /// Quality of the measurements that are not good, by telemetry id
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub quality: HashMap<String, Quality>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -11,3 +15,54 @@ pub struct TelemetryData {
     pub values: HashMap<String, f64>,
     pub time: u64,
 }
+
+/// Quality of a measurement
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Quality {
+    #[default]
+    Good,
+    /// Unchanged for longer than the frozen timeout
+    Frozen,
+    /// Outside of the physical range of its unit
+    OutOfRange,
+    /// The last value received was NaN or infinite, and was rejected
+    Invalid,
+    /// Not updated within the stale timeout
+    Stale,
+}
+
+/// Kind of a measurement displayed on a diagram
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MeasurementKind {
+    ActivePower,
+    ReactivePower,
+    Current,
+    BusVoltage,
+    BusAngle,
+}
+
+/// Live value of a feeder info or bus info, by graphical id
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct FeederValue {
+    pub id: String,
+    pub kind: MeasurementKind,
+    pub value: f64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct FeederValues {
+    pub values: Vec<FeederValue>,
+    pub time: u64,
+
+    /// Quality of the measurements that are not good, by graphical id
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub quality: HashMap<String, Quality>,
+}
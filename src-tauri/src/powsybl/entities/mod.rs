@@ -1,5 +1,7 @@
 mod dynawo;
+mod networks;
 mod queries;
 
 pub use dynawo::*;
+pub use networks::*;
 pub use queries::*;
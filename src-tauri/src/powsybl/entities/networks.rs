@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// A network loaded in the sidecar
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkInfo {
+    pub id: String,
+    pub label: String,
+    #[serde(default)]
+    pub file_path: Option<String>,
+    /// Hash of the network file content, changes whenever the network is replaced
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub active: bool,
+}
+
+/// Networks loaded in the sidecar, as returned by `list_networks`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkList {
+    pub active_network_id: Option<String>,
+    pub networks: Vec<NetworkInfo>,
+}
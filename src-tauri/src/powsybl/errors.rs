@@ -28,6 +28,9 @@ pub enum PowsyblError {
         column: Option<usize>,
     },
 
+    #[error("Invalid diagram metadata: {}", .0.join("; "))]
+    InvalidMetadata(Vec<String>),
+
     #[error("UTF-8 error: {0}")]
     Utf8Error(#[from] std::str::Utf8Error),
 
@@ -42,6 +45,39 @@ pub enum PowsyblError {
 
     #[error("Sqlite error error: {0}")]
     Sqlite(#[from] sqlx::Error),
+
+    #[error("No network loaded")]
+    NoNetwork,
+
+    #[error("Network topology not loaded")]
+    TopologyNotLoaded,
+
+    #[error("Element not found: {0}")]
+    ElementNotFound(String),
+
+    #[error("SVG error: {0}")]
+    SvgError(String),
+
+    #[error("Render error: {0}")]
+    RenderError(String),
+
+    #[error("Historian error: {0}")]
+    HistorianError(String),
+
+    #[error("Export error: {0}")]
+    ExportError(String),
+
+    #[error("Alarm error: {0}")]
+    AlarmError(String),
+
+    #[error("Calculation error: {0}")]
+    CalculationError(String),
+
+    #[error("Balance error: {0}")]
+    BalanceError(String),
+
+    #[error("Switch log error: {0}")]
+    SwitchLogError(String),
 }
 
 // Implement Serialize for PowsyblError for Tauri command compatibility
@@ -0,0 +1,273 @@
+use super::super::errors::{PowsyblError, PowsyblResult};
+use super::store::HistorianSeries;
+use crate::shared::entities::dynawo::GameMasterOutput;
+
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, TimestampMillisecondArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TelemetryExportFormat {
+    Csv,
+    /// Arrow IPC file, also readable as Feather v2
+    #[serde(alias = "feather")]
+    Arrow,
+}
+
+/// Name given to the column of a series
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportIdKind {
+    /// The id of the telemetry feed
+    #[default]
+    Telemetry,
+    Graphical,
+    /// Equipment id, followed by the side and the component type of the output
+    Equipment,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CsvLayout {
+    /// One row per time, one column per series
+    #[default]
+    Wide,
+    /// One row per value: time, wall time, id, value and unit
+    Long,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvOptions {
+    pub layout: CsvLayout,
+    pub separator: char,
+    pub decimal_mark: char,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            layout: CsvLayout::Wide,
+            separator: ',',
+            decimal_mark: '.',
+        }
+    }
+}
+
+/// A recorded series and the game master output it was published for
+#[derive(Debug, Clone)]
+pub struct ExportColumn {
+    pub name: String,
+    pub series: HistorianSeries,
+    pub output: Option<GameMasterOutput>,
+}
+
+impl ExportColumn {
+    fn unit(&self) -> Option<&str> {
+        self.output
+            .as_ref()
+            .and_then(|output| output.unit.as_deref())
+    }
+
+    // Field metadata, read back by pandas and pyarrow as `field.metadata`
+    fn metadata(&self) -> HashMap<String, String> {
+        let mut metadata = HashMap::from([("telemetry_id".to_string(), self.series.id.clone())]);
+        if let Some(output) = &self.output {
+            for (key, value) in [
+                ("unit", &output.unit),
+                ("graphical_id", &output.graphical_id),
+                ("equipment_id", &output.equipment_id),
+                ("side", &output.side),
+                ("component_type", &output.component_type),
+            ] {
+                if let Some(value) = value {
+                    metadata.insert(key.to_string(), value.clone());
+                }
+            }
+        }
+        metadata
+    }
+}
+
+/// Name the columns of the series after the requested id kind.
+///
+/// Series without an output, or without the requested id, keep their telemetry id,
+/// as do the ones whose name is already taken.
+pub fn export_columns(
+    series: Vec<HistorianSeries>,
+    outputs: &[GameMasterOutput],
+    id_kind: ExportIdKind,
+) -> Vec<ExportColumn> {
+    let mut names = HashSet::new();
+
+    series
+        .into_iter()
+        .map(|series| {
+            let output = outputs
+                .iter()
+                .find(|output| output.dynawo_id == series.id)
+                .cloned();
+
+            let name = output
+                .as_ref()
+                .and_then(|output| match id_kind {
+                    ExportIdKind::Telemetry => None,
+                    ExportIdKind::Graphical => output.graphical_id.clone(),
+                    ExportIdKind::Equipment => output.equipment_id.as_ref().map(|equipment_id| {
+                        [
+                            Some(equipment_id),
+                            output.side.as_ref(),
+                            output.component_type.as_ref(),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join("_")
+                    }),
+                })
+                .filter(|name| !names.contains(name))
+                .unwrap_or_else(|| series.id.clone());
+            names.insert(name.clone());
+
+            ExportColumn {
+                name,
+                series,
+                output,
+            }
+        })
+        .collect()
+}
+
+// Values of every column per simulation time, with the wall time they were received at
+fn wide_rows(columns: &[ExportColumn]) -> BTreeMap<u64, (i64, Vec<Option<f64>>)> {
+    let mut rows: BTreeMap<u64, (i64, Vec<Option<f64>>)> = BTreeMap::new();
+    for (index, column) in columns.iter().enumerate() {
+        for point in &column.series.points {
+            let (_, values) = rows
+                .entry(point.time)
+                .or_insert_with(|| (point.wall_time, vec![None; columns.len()]));
+            values[index] = Some(point.value);
+        }
+    }
+    rows
+}
+
+pub fn write_csv<W: Write>(
+    writer: W,
+    columns: &[ExportColumn],
+    options: &CsvOptions,
+) -> PowsyblResult<()> {
+    if options.separator == options.decimal_mark {
+        return Err(PowsyblError::ExportError(format!(
+            "The separator and the decimal mark are both '{}'",
+            options.separator
+        )));
+    }
+    if !options.separator.is_ascii() {
+        return Err(PowsyblError::ExportError(format!(
+            "Unsupported separator '{}'",
+            options.separator
+        )));
+    }
+
+    let mut csv = csv::WriterBuilder::new()
+        .delimiter(options.separator as u8)
+        .from_writer(writer);
+    let number = |value: f64| {
+        let value = value.to_string();
+        match options.decimal_mark {
+            '.' => value,
+            mark => value.replace('.', &mark.to_string()),
+        }
+    };
+
+    match options.layout {
+        CsvLayout::Wide => {
+            let header = ["time", "wall_time"]
+                .into_iter()
+                .map(str::to_string)
+                .chain(columns.iter().map(|column| column.name.clone()));
+            csv.write_record(header).map_err(csv_error)?;
+
+            for (time, (wall_time, values)) in wide_rows(columns) {
+                let record = [time.to_string(), wall_time.to_string()].into_iter().chain(
+                    values
+                        .into_iter()
+                        .map(|value| value.map(number).unwrap_or_default()),
+                );
+                csv.write_record(record).map_err(csv_error)?;
+            }
+        }
+        CsvLayout::Long => {
+            csv.write_record(["time", "wall_time", "id", "value", "unit"])
+                .map_err(csv_error)?;
+
+            for column in columns {
+                let unit = column.unit().unwrap_or_default();
+                for point in &column.series.points {
+                    csv.write_record([
+                        point.time.to_string().as_str(),
+                        point.wall_time.to_string().as_str(),
+                        column.name.as_str(),
+                        number(point.value).as_str(),
+                        unit,
+                    ])
+                    .map_err(csv_error)?;
+                }
+            }
+        }
+    }
+
+    csv.flush()?;
+    Ok(())
+}
+
+/// Write a wide table to an Arrow IPC file, with the unit and ids of each series
+/// in the metadata of its column
+pub fn write_arrow<W: Write>(writer: W, columns: &[ExportColumn]) -> PowsyblResult<()> {
+    let rows = wide_rows(columns);
+
+    let mut fields = vec![
+        Field::new("time", DataType::UInt64, false),
+        Field::new(
+            "wall_time",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            false,
+        ),
+    ];
+    fields.extend(columns.iter().map(|column| {
+        Field::new(&column.name, DataType::Float64, true).with_metadata(column.metadata())
+    }));
+    let schema = Arc::new(Schema::new(fields));
+
+    let mut arrays: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(rows.keys().copied())),
+        Arc::new(TimestampMillisecondArray::from_iter_values(
+            rows.values().map(|(wall_time, _)| *wall_time),
+        )),
+    ];
+    for index in 0..columns.len() {
+        arrays.push(Arc::new(Float64Array::from_iter(
+            rows.values().map(|(_, values)| values[index]),
+        )));
+    }
+
+    let batch = RecordBatch::try_new(schema.clone(), arrays).map_err(arrow_error)?;
+    let mut file = arrow_ipc::writer::FileWriter::try_new(writer, &schema).map_err(arrow_error)?;
+    file.write(&batch).map_err(arrow_error)?;
+    file.finish().map_err(arrow_error)?;
+    Ok(())
+}
+
+fn csv_error(e: csv::Error) -> PowsyblError {
+    PowsyblError::ExportError(e.to_string())
+}
+
+fn arrow_error(e: arrow_schema::ArrowError) -> PowsyblError {
+    PowsyblError::ExportError(e.to_string())
+}
@@ -0,0 +1,335 @@
+use super::diagrams::sld_subscriptions::{
+    game_master_outputs, subscribe_diagram, unsubscribe_diagram,
+};
+use super::diagrams::TelemetrySink;
+use super::entities::FetchStatus;
+use super::errors::{PowsyblError, PowsyblResult};
+
+use crate::state::AppState;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+use tokio::task::JoinHandle;
+
+mod export;
+mod recorder;
+mod store;
+mod trend;
+
+pub use export::{CsvLayout, CsvOptions, ExportIdKind, TelemetryExportFormat};
+pub use recorder::{now_millis, HistorianRecorder, RecorderCounters};
+pub use store::{HistorianPoint, HistorianSeries, StoreStats};
+pub use trend::{TrendBucket, TrendQueryResult, TrendSeries};
+
+use recorder::{spawn_writer, RecorderStats};
+use store::HistorianStore;
+
+/// Subscription id of the historian feed, alongside the diagram ones
+const HISTORIAN_SUBSCRIPTION: &str = "historian";
+
+/// Time the writer is given to store its last batch when stopped
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A downsampled copy of the values, kept longer than the raw ones
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownsampleTier {
+    /// Bucket width, in simulation time units
+    pub interval: u64,
+    pub retention_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistorianConfig {
+    pub raw_retention_secs: u64,
+    pub tiers: Vec<DownsampleTier>,
+    /// Samples written per transaction
+    pub batch_size: usize,
+    pub flush_interval_ms: u64,
+    /// Frames waiting for the writer before new ones are dropped
+    pub queue_capacity: usize,
+}
+
+impl Default for HistorianConfig {
+    fn default() -> Self {
+        Self {
+            raw_retention_secs: 24 * 3600,
+            tiers: vec![
+                DownsampleTier {
+                    interval: 60,
+                    retention_secs: 7 * 24 * 3600,
+                },
+                DownsampleTier {
+                    interval: 3600,
+                    retention_secs: 90 * 24 * 3600,
+                },
+            ],
+            batch_size: 50_000,
+            flush_interval_ms: 1_000,
+            queue_capacity: 1_024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorianStatus {
+    pub running: bool,
+    pub config: HistorianConfig,
+    pub counters: RecorderCounters,
+    pub stored: StoreStats,
+}
+
+/// Recorder of every telemetry value received from the ZMQ feed, with its
+/// simulation and wall times, into SQLite
+pub struct Historian {
+    pool: Pool<Sqlite>,
+    config: HistorianConfig,
+    stats: Arc<RecorderStats>,
+    writer: Option<JoinHandle<()>>,
+}
+
+pub type HistorianState = tokio::sync::Mutex<Historian>;
+
+impl Historian {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self {
+            pool,
+            config: HistorianConfig::default(),
+            stats: Arc::new(RecorderStats::default()),
+            writer: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.writer
+            .as_ref()
+            .is_some_and(|writer| !writer.is_finished())
+    }
+
+    /// Start a writer with a new configuration and return its recorder
+    fn start(&mut self, config: HistorianConfig) -> HistorianRecorder {
+        self.config = config;
+        self.stats = Arc::new(RecorderStats::default());
+
+        let (recorder, writer) = spawn_writer(
+            HistorianStore::new(self.pool.clone()),
+            self.config.clone(),
+            self.stats.clone(),
+        );
+        self.writer = Some(writer);
+        recorder
+    }
+
+    /// Wait for the writer to store what it still holds
+    async fn stop(&mut self) {
+        let Some(writer) = self.writer.take() else {
+            return;
+        };
+        match tokio::time::timeout(STOP_TIMEOUT, writer).await {
+            Ok(Ok(())) => info!("Historian stopped"),
+            Ok(Err(e)) => warn!("Historian writer failed: {}", e),
+            Err(_) => warn!("Timeout while waiting for the historian writer"),
+        }
+    }
+
+    // Only the configured tiers are filled
+    fn check_tier(&self, tier: Option<u64>) -> PowsyblResult<()> {
+        let Some(interval) = tier else {
+            return Ok(());
+        };
+        if self
+            .config
+            .tiers
+            .iter()
+            .any(|tier| tier.interval == interval)
+        {
+            return Ok(());
+        }
+        Err(PowsyblError::HistorianError(format!(
+            "No downsampling tier of interval {}",
+            interval
+        )))
+    }
+
+    pub async fn status(&self) -> PowsyblResult<HistorianStatus> {
+        Ok(HistorianStatus {
+            running: self.is_running(),
+            config: self.config.clone(),
+            counters: self.stats.counters(),
+            stored: HistorianStore::new(self.pool.clone()).stats().await?,
+        })
+    }
+}
+
+/// Start recording the telemetry feed, restarting the historian if it runs
+#[tauri::command(rename_all = "snake_case")]
+pub async fn start_historian(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    historian: State<'_, HistorianState>,
+    config: Option<HistorianConfig>,
+) -> PowsyblResult<HistorianStatus> {
+    let mut historian = historian.lock().await;
+
+    unsubscribe_diagram(&state, HISTORIAN_SUBSCRIPTION);
+    historian.stop().await;
+
+    let recorder = historian.start(config.unwrap_or_default());
+    subscribe_diagram(
+        app_handle,
+        &state,
+        HISTORIAN_SUBSCRIPTION.to_string(),
+        TelemetrySink::Historian(recorder),
+    )
+    .await?;
+
+    info!("Historian started");
+    historian.status().await
+}
+
+/// Stop recording, once the pending values are stored
+#[tauri::command(rename_all = "snake_case")]
+pub async fn stop_historian(
+    state: State<'_, AppState>,
+    historian: State<'_, HistorianState>,
+) -> PowsyblResult<HistorianStatus> {
+    let mut historian = historian.lock().await;
+
+    // Dropping the subscription closes the queue of the writer
+    unsubscribe_diagram(&state, HISTORIAN_SUBSCRIPTION);
+    historian.stop().await;
+
+    historian.status().await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_historian_status(
+    historian: State<'_, HistorianState>,
+) -> PowsyblResult<HistorianStatus> {
+    historian.lock().await.status().await
+}
+
+/// Get the recorded series of the given ids between two simulation times.
+///
+/// Raw values are returned by default; `tier` selects a downsampling tier by its
+/// interval, whose points are the mean of each bucket.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn query_historian(
+    historian: State<'_, HistorianState>,
+    ids: Vec<String>,
+    from: u64,
+    to: u64,
+    tier: Option<u64>,
+) -> PowsyblResult<Vec<HistorianSeries>> {
+    let historian = historian.lock().await;
+
+    historian.check_tier(tier)?;
+
+    HistorianStore::new(historian.pool.clone())
+        .query(&ids, from, to, tier)
+        .await
+}
+
+/// Get the min, max, mean, first, last and count of the given ids per time bucket.
+///
+/// Buckets are sized to give about `target_points` of them (1000 by default) between
+/// the two simulation times, and read from the coarsest tier that resolves them.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn query_historian_trend(
+    historian: State<'_, HistorianState>,
+    ids: Vec<String>,
+    from: u64,
+    to: u64,
+    target_points: Option<usize>,
+) -> PowsyblResult<TrendQueryResult> {
+    let historian = historian.lock().await;
+
+    trend::query_trend(
+        &HistorianStore::new(historian.pool.clone()),
+        &historian.config,
+        &ids,
+        from,
+        to,
+        target_points.unwrap_or(trend::DEFAULT_TARGET_POINTS),
+    )
+    .await
+}
+
+/// Export the recorded series of the given ids between two simulation times to a
+/// CSV file or an Arrow IPC (Feather) file.
+///
+/// Columns are named after `id_kind` through the game master outputs, which also
+/// give the units. CSV files are wide by default, with `,` separators and `.`
+/// decimal marks; Arrow files are always wide.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_historian(
+    state: State<'_, AppState>,
+    historian: State<'_, HistorianState>,
+    ids: Vec<String>,
+    from: u64,
+    to: u64,
+    format: TelemetryExportFormat,
+    output_path: String,
+    id_kind: Option<ExportIdKind>,
+    csv_options: Option<CsvOptions>,
+    tier: Option<u64>,
+) -> PowsyblResult<FetchStatus> {
+    let series = {
+        let historian = historian.lock().await;
+        historian.check_tier(tier)?;
+        HistorianStore::new(historian.pool.clone())
+            .query(&ids, from, to, tier)
+            .await?
+    };
+    let values: usize = series.iter().map(|series| series.points.len()).sum();
+    let columns = export::export_columns(
+        series,
+        &game_master_outputs(&state)?,
+        id_kind.unwrap_or_default(),
+    );
+
+    let path = output_path.clone();
+    tokio::task::spawn_blocking(move || {
+        let writer = BufWriter::new(File::create(&path)?);
+        match format {
+            TelemetryExportFormat::Csv => {
+                export::write_csv(writer, &columns, &csv_options.unwrap_or_default())
+            }
+            TelemetryExportFormat::Arrow => export::write_arrow(writer, &columns),
+        }
+    })
+    .await
+    .map_err(|e| PowsyblError::ExportError(e.to_string()))??;
+
+    Ok(FetchStatus {
+        success: true,
+        message: format!(
+            "Exported {} values of {} series to {}",
+            values,
+            ids.len(),
+            output_path
+        ),
+    })
+}
+
+/// Delete every recorded value; the historian must be stopped
+#[tauri::command(rename_all = "snake_case")]
+pub async fn clear_historian(
+    historian: State<'_, HistorianState>,
+) -> PowsyblResult<HistorianStatus> {
+    let historian = historian.lock().await;
+    if historian.is_running() {
+        return Err(PowsyblError::HistorianError(
+            "Stop the historian before clearing it".to_string(),
+        ));
+    }
+
+    HistorianStore::new(historian.pool.clone()).clear().await?;
+    historian.status().await
+}
@@ -0,0 +1,177 @@
+use super::super::diagrams::errors::{SubscriptionError, SubscriptionResult};
+use super::super::entities::TelemetryData;
+use super::store::{HistorianFrame, HistorianStore};
+use super::HistorianConfig;
+
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Interval between two purges of the values past their retention
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Counters of the historian since it was started
+#[derive(Debug, Default)]
+pub struct RecorderStats {
+    frames_received: AtomicU64,
+    frames_dropped: AtomicU64,
+    samples_stored: AtomicU64,
+    samples_failed: AtomicU64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecorderCounters {
+    pub frames_received: u64,
+    pub frames_dropped: u64,
+    pub samples_stored: u64,
+    pub samples_failed: u64,
+}
+
+impl RecorderStats {
+    pub fn counters(&self) -> RecorderCounters {
+        RecorderCounters {
+            frames_received: self.frames_received.load(Ordering::Relaxed),
+            frames_dropped: self.frames_dropped.load(Ordering::Relaxed),
+            samples_stored: self.samples_stored.load(Ordering::Relaxed),
+            samples_failed: self.samples_failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Sending half of the historian, handed to the telemetry subscription.
+///
+/// Frames are queued without waiting, so a slow disk never stalls the feed;
+/// when the queue is full they are dropped and counted.
+#[derive(Clone)]
+pub struct HistorianRecorder {
+    sender: mpsc::Sender<HistorianFrame>,
+    stats: Arc<RecorderStats>,
+}
+
+impl HistorianRecorder {
+    pub fn record(&self, data: TelemetryData) -> SubscriptionResult<()> {
+        self.stats.frames_received.fetch_add(1, Ordering::Relaxed);
+        let frame = HistorianFrame {
+            data,
+            wall_time: now_millis(),
+        };
+
+        match self.sender.try_send(frame) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.stats.frames_dropped.fetch_add(1, Ordering::Relaxed);
+                warn!("Historian queue full, telemetry frame dropped");
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(SubscriptionError::ChannelSendError(
+                "historian writer stopped".to_string(),
+            )),
+        }
+    }
+}
+
+pub fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Create the recorder and the writer task storing what it receives.
+///
+/// The writer flushes a batch once it holds `batch_size` samples or every
+/// `flush_interval_ms`, and ends after a last flush when every recorder is dropped.
+pub fn spawn_writer(
+    store: HistorianStore,
+    config: HistorianConfig,
+    stats: Arc<RecorderStats>,
+) -> (HistorianRecorder, tokio::task::JoinHandle<()>) {
+    let (sender, receiver) = mpsc::channel(config.queue_capacity.max(1));
+    let recorder = HistorianRecorder {
+        sender,
+        stats: stats.clone(),
+    };
+    let handle = tokio::spawn(Writer::new(store, config, stats).run(receiver));
+    (recorder, handle)
+}
+
+struct Writer {
+    store: HistorianStore,
+    config: HistorianConfig,
+    stats: Arc<RecorderStats>,
+    batch: Vec<HistorianFrame>,
+    batch_samples: usize,
+}
+
+impl Writer {
+    fn new(store: HistorianStore, config: HistorianConfig, stats: Arc<RecorderStats>) -> Self {
+        Self {
+            store,
+            config,
+            stats,
+            batch: Vec::new(),
+            batch_samples: 0,
+        }
+    }
+
+    async fn run(mut self, mut receiver: mpsc::Receiver<HistorianFrame>) {
+        info!("Historian writer started");
+        let mut flush =
+            tokio::time::interval(Duration::from_millis(self.config.flush_interval_ms.max(1)));
+        let mut purge = tokio::time::interval(PURGE_INTERVAL);
+
+        loop {
+            tokio::select! {
+                frame = receiver.recv() => match frame {
+                    Some(frame) => {
+                        self.batch_samples += frame.data.values.len();
+                        self.batch.push(frame);
+                        if self.batch_samples >= self.config.batch_size {
+                            self.flush().await;
+                        }
+                    }
+                    None => break,
+                },
+                _ = flush.tick() => self.flush().await,
+                _ = purge.tick() => self.purge().await,
+            }
+        }
+
+        self.flush().await;
+        info!("Historian writer finished");
+    }
+
+    async fn flush(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+        let batch = std::mem::take(&mut self.batch);
+        let samples = std::mem::take(&mut self.batch_samples);
+
+        match self.store.write(&batch, &self.config).await {
+            Ok(stored) => {
+                debug!("Historian stored {} samples", stored);
+                self.stats
+                    .samples_stored
+                    .fetch_add(stored as u64, Ordering::Relaxed);
+            }
+            Err(e) => {
+                error!("Historian failed to store {} samples: {}", samples, e);
+                self.stats
+                    .samples_failed
+                    .fetch_add(samples as u64, Ordering::Relaxed);
+            }
+        }
+    }
+
+    async fn purge(&self) {
+        match self.store.purge(&self.config, now_millis()).await {
+            Ok(0) => {}
+            Ok(deleted) => debug!("Historian purged {} expired rows", deleted),
+            Err(e) => warn!("Historian purge failed: {}", e),
+        }
+    }
+}
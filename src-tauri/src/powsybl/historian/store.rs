@@ -0,0 +1,706 @@
+use super::super::entities::TelemetryData;
+use super::super::errors::PowsyblResult;
+use super::{DownsampleTier, HistorianConfig};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, QueryBuilder, Sqlite};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Rows per insert statement, within the 32766 bound parameters of SQLite
+const SAMPLE_ROWS: usize = 4_000;
+const ROLLUP_ROWS: usize = 2_000;
+const SERIES_ROWS: usize = 10_000;
+
+/// A telemetry message and the wall time it was received at
+#[derive(Debug, Clone)]
+pub struct HistorianFrame {
+    pub data: TelemetryData,
+    /// Milliseconds since the Unix epoch
+    pub wall_time: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorianPoint {
+    /// Simulation time, or bucket start for downsampled points
+    pub time: u64,
+    pub wall_time: i64,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorianSeries {
+    pub id: String,
+    /// Interval of the downsampling tier, raw values when `None`
+    pub tier: Option<u64>,
+    pub points: Vec<HistorianPoint>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoreStats {
+    pub series: i64,
+    pub samples: i64,
+    pub rollups: i64,
+}
+
+/// Aggregate of the values of one series within one bucket
+#[derive(Debug, Clone)]
+pub(super) struct Rollup {
+    pub count: i64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Simulation time and value of the first and last samples
+    pub first: (i64, f64),
+    pub last: (i64, f64),
+    pub wall_time: i64,
+}
+
+impl Rollup {
+    fn new(time: i64, value: f64, wall_time: i64) -> Self {
+        Self {
+            count: 1,
+            sum: value,
+            min: value,
+            max: value,
+            first: (time, value),
+            last: (time, value),
+            wall_time,
+        }
+    }
+
+    fn add(&mut self, time: i64, value: f64, wall_time: i64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if time < self.first.0 {
+            self.first = (time, value);
+        }
+        if time >= self.last.0 {
+            self.last = (time, value);
+        }
+        self.wall_time = self.wall_time.max(wall_time);
+    }
+}
+
+/// A sample of a series: series id, simulation time, wall time and value
+type Sample = (i64, i64, i64, f64);
+
+/// Rollup key: series id, tier interval and bucket start
+type RollupKey = (i64, i64, i64);
+
+/// A bucket read back: start, count, sum, min, max, first time and value, last
+/// time and value, and wall time
+type BucketRow = (i64, i64, f64, f64, f64, i64, f64, i64, f64, i64);
+
+/// Rollups of a batch of samples, and the buckets to compute again from the
+/// stored samples
+#[derive(Debug, Default)]
+pub(super) struct RollupBatch {
+    /// Added to the stored rollups of their bucket
+    pub folded: BTreeMap<RollupKey, Rollup>,
+    pub recomputed: BTreeSet<RollupKey>,
+}
+
+impl RollupBatch {
+    /// Fold the samples of a bucket to recompute into its stored rollup instead,
+    /// for buckets whose raw samples were purged. A repeated time is then counted
+    /// twice, the value it replaces being no longer known.
+    pub(super) fn merge(&mut self, key: RollupKey, samples: &[Sample]) {
+        if !self.recomputed.remove(&key) {
+            return;
+        }
+        let (series_id, interval, bucket) = key;
+        for &(id, time, wall_time, value) in samples {
+            if id != series_id || time < bucket || time >= bucket + interval {
+                continue;
+            }
+            self.folded
+                .entry(key)
+                .and_modify(|rollup| rollup.add(time, value, wall_time))
+                .or_insert_with(|| Rollup::new(time, value, wall_time));
+        }
+    }
+}
+
+/// Fold a batch of samples into every tier.
+///
+/// A sample later than the last time of its series is added to the stored rollup
+/// of its bucket. An earlier or repeated time, after a restart of the simulation
+/// or a replay, replaces a stored sample instead, so its buckets are computed
+/// again from the stored samples rather than counted twice.
+pub(super) fn fold_rollups(
+    samples: &[Sample],
+    tiers: &[DownsampleTier],
+    last_times: &mut HashMap<i64, i64>,
+) -> RollupBatch {
+    let mut batch = RollupBatch::default();
+    for &(series_id, time, wall_time, value) in samples {
+        let repeated = last_times.get(&series_id).is_some_and(|&last| time <= last);
+        if !repeated {
+            last_times.insert(series_id, time);
+        }
+
+        for tier in tiers {
+            let interval = tier.interval.max(1) as i64;
+            let key = (series_id, interval, time - time.rem_euclid(interval));
+            if repeated {
+                batch.folded.remove(&key);
+                batch.recomputed.insert(key);
+            } else if !batch.recomputed.contains(&key) {
+                batch
+                    .folded
+                    .entry(key)
+                    .and_modify(|rollup| rollup.add(time, value, wall_time))
+                    .or_insert_with(|| Rollup::new(time, value, wall_time));
+            }
+        }
+    }
+    batch
+}
+
+/// SQLite storage of the historian: raw samples and their downsampling tiers
+pub struct HistorianStore {
+    pool: Pool<Sqlite>,
+    series: HashMap<String, i64>,
+    /// Last simulation time stored for each series, loaded on the first write
+    last_times: Option<HashMap<i64, i64>>,
+}
+
+impl HistorianStore {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self {
+            pool,
+            series: HashMap::new(),
+            last_times: None,
+        }
+    }
+
+    /// Store a batch of frames and fold them into every tier, in one transaction.
+    /// Returns the number of samples written; non finite values are skipped.
+    pub async fn write(
+        &mut self,
+        frames: &[HistorianFrame],
+        config: &HistorianConfig,
+    ) -> PowsyblResult<usize> {
+        self.resolve_series(frames).await?;
+
+        let mut samples = Vec::new();
+        for frame in frames {
+            for (name, &value) in &frame.data.values {
+                if !value.is_finite() {
+                    continue;
+                }
+                let Some(&series_id) = self.series.get(name) else {
+                    continue;
+                };
+                samples.push((series_id, frame.data.time as i64, frame.wall_time, value));
+            }
+        }
+        if samples.is_empty() {
+            return Ok(0);
+        }
+
+        let mut last_times = match self.last_times.take() {
+            Some(last_times) => last_times,
+            None => self.load_last_times().await?,
+        };
+        let batch = fold_rollups(&samples, &config.tiers, &mut last_times);
+
+        // The last times are loaded again if the batch is not stored
+        self.write_batch(&samples, batch).await?;
+        self.last_times = Some(last_times);
+        Ok(samples.len())
+    }
+
+    async fn load_last_times(&self) -> PowsyblResult<HashMap<i64, i64>> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT series_id, MAX(time) FROM (
+                 SELECT series_id, MAX(sim_time) AS time FROM historian_samples
+                 GROUP BY series_id
+                 UNION ALL
+                 SELECT series_id, MAX(last_time) AS time FROM historian_rollups
+                 GROUP BY series_id
+             )
+             GROUP BY series_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().collect())
+    }
+
+    async fn write_batch(&self, samples: &[Sample], mut batch: RollupBatch) -> PowsyblResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        // Raw samples are kept for less time than the tiers: a bucket holding
+        // fewer samples than its rollup can no longer be computed from them
+        let recomputed: Vec<_> = batch.recomputed.iter().copied().collect();
+        for chunk in recomputed.chunks(ROLLUP_ROWS) {
+            let mut query =
+                QueryBuilder::<Sqlite>::new("WITH buckets (series_id, tier, bucket) AS (");
+            query.push_values(chunk, |mut row, &(series_id, tier, bucket)| {
+                row.push_bind(series_id).push_bind(tier).push_bind(bucket);
+            });
+            query.push(
+                ") SELECT b.series_id, b.tier, b.bucket FROM buckets b
+                 JOIN historian_rollups r
+                 ON r.series_id = b.series_id AND r.tier = b.tier AND r.bucket = b.bucket
+                 WHERE r.count > (
+                     SELECT COUNT(*) FROM historian_samples s
+                     WHERE s.series_id = b.series_id
+                     AND s.sim_time >= b.bucket AND s.sim_time < b.bucket + b.tier
+                 )",
+            );
+            let purged: Vec<RollupKey> = query.build_query_as().fetch_all(&mut *tx).await?;
+            for key in purged {
+                batch.merge(key, samples);
+            }
+        }
+
+        for chunk in samples.chunks(SAMPLE_ROWS) {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "INSERT OR REPLACE INTO historian_samples (series_id, sim_time, wall_time, value) ",
+            );
+            query.push_values(chunk, |mut row, &(series_id, time, wall_time, value)| {
+                row.push_bind(series_id)
+                    .push_bind(time)
+                    .push_bind(wall_time)
+                    .push_bind(value);
+            });
+            query.build().execute(&mut *tx).await?;
+        }
+
+        let rollups: Vec<_> = batch.folded.into_iter().collect();
+        for chunk in rollups.chunks(ROLLUP_ROWS) {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "INSERT INTO historian_rollups
+                 (series_id, tier, bucket, count, sum, min, max,
+                  first_time, first, last_time, last, wall_time) ",
+            );
+            query.push_values(chunk, |mut row, ((series_id, tier, bucket), rollup)| {
+                row.push_bind(*series_id)
+                    .push_bind(*tier)
+                    .push_bind(*bucket)
+                    .push_bind(rollup.count)
+                    .push_bind(rollup.sum)
+                    .push_bind(rollup.min)
+                    .push_bind(rollup.max)
+                    .push_bind(rollup.first.0)
+                    .push_bind(rollup.first.1)
+                    .push_bind(rollup.last.0)
+                    .push_bind(rollup.last.1)
+                    .push_bind(rollup.wall_time);
+            });
+            // Expressions of the update all read the row before it
+            query.push(
+                " ON CONFLICT(series_id, tier, bucket) DO UPDATE SET
+                 count = count + excluded.count,
+                 sum = sum + excluded.sum,
+                 min = MIN(min, excluded.min),
+                 max = MAX(max, excluded.max),
+                 first = CASE WHEN excluded.first_time < first_time
+                         THEN excluded.first ELSE first END,
+                 first_time = MIN(first_time, excluded.first_time),
+                 last = CASE WHEN excluded.last_time >= last_time
+                        THEN excluded.last ELSE last END,
+                 last_time = MAX(last_time, excluded.last_time),
+                 wall_time = MAX(wall_time, excluded.wall_time)",
+            );
+            query.build().execute(&mut *tx).await?;
+        }
+
+        // Replaced samples are folded again with the other samples of their bucket
+        let recomputed: Vec<_> = batch.recomputed.into_iter().collect();
+        for chunk in recomputed.chunks(ROLLUP_ROWS) {
+            let mut query =
+                QueryBuilder::<Sqlite>::new("WITH buckets (series_id, tier, bucket) AS (");
+            query.push_values(chunk, |mut row, &(series_id, tier, bucket)| {
+                row.push_bind(series_id).push_bind(tier).push_bind(bucket);
+            });
+            query.push(
+                ") INSERT OR REPLACE INTO historian_rollups
+                 (series_id, tier, bucket, count, sum, min, max,
+                  first_time, first, last_time, last, wall_time)
+                 SELECT g.series_id, g.tier, g.bucket, g.count, g.sum, g.min, g.max,
+                 g.first_time, f.value, g.last_time, l.value, g.wall_time
+                 FROM (
+                     SELECT b.series_id, b.tier, b.bucket, COUNT(*) AS count,
+                     SUM(s.value) AS sum, MIN(s.value) AS min, MAX(s.value) AS max,
+                     MIN(s.sim_time) AS first_time, MAX(s.sim_time) AS last_time,
+                     MAX(s.wall_time) AS wall_time
+                     FROM buckets b
+                     JOIN historian_samples s
+                     ON s.series_id = b.series_id
+                     AND s.sim_time >= b.bucket AND s.sim_time < b.bucket + b.tier
+                     GROUP BY b.series_id, b.tier, b.bucket
+                 ) g
+                 JOIN historian_samples f
+                 ON f.series_id = g.series_id AND f.sim_time = g.first_time
+                 JOIN historian_samples l
+                 ON l.series_id = g.series_id AND l.sim_time = g.last_time",
+            );
+            query.build().execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Register the series seen for the first time and cache their row ids
+    async fn resolve_series(&mut self, frames: &[HistorianFrame]) -> PowsyblResult<()> {
+        let mut names: Vec<&String> = frames
+            .iter()
+            .flat_map(|frame| frame.data.values.keys())
+            .filter(|name| !self.series.contains_key(*name))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+
+        for chunk in names.chunks(SERIES_ROWS) {
+            let mut insert =
+                QueryBuilder::<Sqlite>::new("INSERT OR IGNORE INTO historian_series (name) ");
+            insert.push_values(chunk, |mut row, name| {
+                row.push_bind(*name);
+            });
+            insert.build().execute(&self.pool).await?;
+
+            let mut select = QueryBuilder::<Sqlite>::new(
+                "SELECT id, name FROM historian_series WHERE name IN (",
+            );
+            let mut separated = select.separated(", ");
+            for name in chunk {
+                separated.push_bind(*name);
+            }
+            select.push(")");
+
+            let rows: Vec<(i64, String)> = select.build_query_as().fetch_all(&self.pool).await?;
+            self.series
+                .extend(rows.into_iter().map(|(id, name)| (name, id)));
+        }
+        Ok(())
+    }
+
+    /// Delete the values past the retention of their tier, relative to `now`
+    pub async fn purge(&self, config: &HistorianConfig, now: i64) -> PowsyblResult<u64> {
+        let mut deleted = sqlx::query("DELETE FROM historian_samples WHERE wall_time < ?")
+            .bind(now - config.raw_retention_secs as i64 * 1000)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        for tier in &config.tiers {
+            deleted +=
+                sqlx::query("DELETE FROM historian_rollups WHERE tier = ? AND wall_time < ?")
+                    .bind(tier.interval.max(1) as i64)
+                    .bind(now - tier.retention_secs as i64 * 1000)
+                    .execute(&self.pool)
+                    .await?
+                    .rows_affected();
+        }
+
+        // Tiers removed from the configuration are never read again
+        let mut query =
+            QueryBuilder::<Sqlite>::new("DELETE FROM historian_rollups WHERE tier NOT IN (");
+        let mut separated = query.separated(", ");
+        separated.push_bind(0i64);
+        for tier in &config.tiers {
+            separated.push_bind(tier.interval.max(1) as i64);
+        }
+        query.push(")");
+        deleted += query.build().execute(&self.pool).await?.rows_affected();
+
+        Ok(deleted)
+    }
+
+    /// Series of the given ids between two simulation times, both included
+    pub async fn query(
+        &self,
+        ids: &[String],
+        from: u64,
+        to: u64,
+        tier: Option<u64>,
+    ) -> PowsyblResult<Vec<HistorianSeries>> {
+        let mut series = Vec::with_capacity(ids.len());
+
+        for id in ids {
+            let rows: Vec<(i64, i64, f64)> = match tier {
+                None => {
+                    sqlx::query_as(
+                        "SELECT sim_time, wall_time, value FROM historian_samples
+                         WHERE series_id = (SELECT id FROM historian_series WHERE name = ?)
+                         AND sim_time BETWEEN ? AND ?
+                         ORDER BY sim_time",
+                    )
+                    .bind(id)
+                    .bind(from as i64)
+                    .bind(to as i64)
+                    .fetch_all(&self.pool)
+                    .await?
+                }
+                Some(interval) => {
+                    sqlx::query_as(
+                        "SELECT bucket, wall_time, sum / count FROM historian_rollups
+                         WHERE series_id = (SELECT id FROM historian_series WHERE name = ?)
+                         AND tier = ? AND bucket BETWEEN ? AND ?
+                         ORDER BY bucket",
+                    )
+                    .bind(id)
+                    .bind(interval.max(1) as i64)
+                    // The bucket holding `from` starts before it
+                    .bind(from as i64 - (from % interval.max(1)) as i64)
+                    .bind(to as i64)
+                    .fetch_all(&self.pool)
+                    .await?
+                }
+            };
+
+            series.push(HistorianSeries {
+                id: id.clone(),
+                tier,
+                points: rows
+                    .into_iter()
+                    .map(|(time, wall_time, value)| HistorianPoint {
+                        time: time as u64,
+                        wall_time,
+                        value,
+                    })
+                    .collect(),
+            });
+        }
+
+        Ok(series)
+    }
+
+    /// Aggregates of one series per bucket of `width` between two simulation times,
+    /// by bucket start. Buckets are folded from the raw samples or, when `tier` is
+    /// given, from the rollups of that tier, whose interval must divide `width`.
+    pub(super) async fn buckets(
+        &self,
+        id: &str,
+        from: u64,
+        to: u64,
+        tier: Option<u64>,
+        width: u64,
+    ) -> PowsyblResult<Vec<(i64, Rollup)>> {
+        let series_id: Option<(i64,)> =
+            sqlx::query_as("SELECT id FROM historian_series WHERE name = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        let Some((series_id,)) = series_id else {
+            return Ok(Vec::new());
+        };
+
+        // Grouped in SQLite, the first and last values are then read on the
+        // primary key, so only one row per bucket is decoded
+        let rows: Vec<BucketRow> = match tier {
+            None => {
+                sqlx::query_as(
+                    "SELECT g.start, g.count, g.sum, g.min, g.max,
+                     f.sim_time, f.value, l.sim_time, l.value, g.wall_time
+                     FROM (
+                         SELECT sim_time - sim_time % ? AS start, COUNT(*) AS count,
+                         SUM(value) AS sum, MIN(value) AS min, MAX(value) AS max,
+                         MIN(sim_time) AS first_time, MAX(sim_time) AS last_time,
+                         MAX(wall_time) AS wall_time
+                         FROM historian_samples
+                         WHERE series_id = ? AND sim_time BETWEEN ? AND ?
+                         GROUP BY start
+                     ) g
+                     JOIN historian_samples f
+                     ON f.series_id = ? AND f.sim_time = g.first_time
+                     JOIN historian_samples l
+                     ON l.series_id = ? AND l.sim_time = g.last_time
+                     ORDER BY g.start",
+                )
+                .bind(width.max(1) as i64)
+                .bind(series_id)
+                .bind(from as i64)
+                .bind(to as i64)
+                .bind(series_id)
+                .bind(series_id)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            Some(interval) => {
+                let interval = interval.max(1) as i64;
+                sqlx::query_as(
+                    "SELECT g.start, g.count, g.sum, g.min, g.max,
+                     f.first_time, f.first, l.last_time, l.last, g.wall_time
+                     FROM (
+                         SELECT bucket - bucket % ? AS start, SUM(count) AS count,
+                         SUM(sum) AS sum, MIN(min) AS min, MAX(max) AS max,
+                         MIN(bucket) AS first_bucket, MAX(bucket) AS last_bucket,
+                         MAX(wall_time) AS wall_time
+                         FROM historian_rollups
+                         WHERE series_id = ? AND tier = ? AND bucket BETWEEN ? AND ?
+                         GROUP BY start
+                     ) g
+                     JOIN historian_rollups f
+                     ON f.series_id = ? AND f.tier = ? AND f.bucket = g.first_bucket
+                     JOIN historian_rollups l
+                     ON l.series_id = ? AND l.tier = ? AND l.bucket = g.last_bucket
+                     ORDER BY g.start",
+                )
+                .bind(width.max(1) as i64)
+                .bind(series_id)
+                .bind(interval)
+                // The bucket holding `from` starts before it
+                .bind(from as i64 - (from as i64).rem_euclid(interval))
+                .bind(to as i64)
+                .bind(series_id)
+                .bind(interval)
+                .bind(series_id)
+                .bind(interval)
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(start, count, sum, min, max, first_time, first, last_time, last, wall_time)| {
+                    let rollup = Rollup {
+                        count,
+                        sum,
+                        min,
+                        max,
+                        first: (first_time, first),
+                        last: (last_time, last),
+                        wall_time,
+                    };
+                    (start, rollup)
+                },
+            )
+            .collect())
+    }
+
+    pub async fn stats(&self) -> PowsyblResult<StoreStats> {
+        let (series, samples, rollups) = sqlx::query_as::<_, (i64, i64, i64)>(
+            "SELECT
+             (SELECT COUNT(*) FROM historian_series),
+             (SELECT COUNT(*) FROM historian_samples),
+             (SELECT COUNT(*) FROM historian_rollups)",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(StoreStats {
+            series,
+            samples,
+            rollups,
+        })
+    }
+
+    /// Delete every stored value
+    pub async fn clear(&mut self) -> PowsyblResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for table in ["historian_samples", "historian_rollups", "historian_series"] {
+            sqlx::query(&format!("DELETE FROM {}", table))
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        self.series.clear();
+        self.last_times = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiers(intervals: &[u64]) -> Vec<DownsampleTier> {
+        intervals
+            .iter()
+            .map(|&interval| DownsampleTier {
+                interval,
+                retention_secs: 3600,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn folds_increasing_times_into_every_tier() {
+        let samples = [
+            (1, 0, 10, 2.0),
+            (1, 1, 11, -1.0),
+            (1, 2, 12, 5.0),
+            (1, 3, 13, 4.0),
+        ];
+        let mut last_times = HashMap::new();
+
+        let batch = fold_rollups(&samples, &tiers(&[2, 10]), &mut last_times);
+
+        assert!(batch.recomputed.is_empty());
+        assert_eq!(last_times[&1], 3);
+
+        let first = &batch.folded[&(1, 2, 0)];
+        assert_eq!(first.count, 2);
+        assert_eq!(first.sum, 1.0);
+        assert_eq!((first.min, first.max), (-1.0, 2.0));
+        assert_eq!((first.first, first.last), ((0, 2.0), (1, -1.0)));
+
+        let second = &batch.folded[&(1, 2, 2)];
+        assert_eq!((second.first, second.last), ((2, 5.0), (3, 4.0)));
+        assert_eq!(second.wall_time, 13);
+
+        let coarse = &batch.folded[&(1, 10, 0)];
+        assert_eq!(coarse.count, 4);
+        assert_eq!((coarse.min, coarse.max), (-1.0, 5.0));
+    }
+
+    #[test]
+    fn recomputes_the_buckets_of_repeated_times() {
+        // Stored up to time 5, then the simulation starts over
+        let mut last_times = HashMap::from([(1, 5), (2, 5)]);
+        let samples = [(1, 0, 20, 1.0), (1, 1, 21, 1.0), (2, 6, 20, 3.0)];
+
+        let batch = fold_rollups(&samples, &tiers(&[2]), &mut last_times);
+
+        assert_eq!(batch.recomputed, BTreeSet::from([(1, 2, 0)]));
+        assert!(!batch.folded.contains_key(&(1, 2, 0)));
+        assert_eq!(batch.folded[&(2, 2, 6)].count, 1);
+        assert_eq!(last_times, HashMap::from([(1, 5), (2, 6)]));
+    }
+
+    #[test]
+    fn recomputes_a_bucket_once_a_time_repeats_within_a_batch() {
+        let mut last_times = HashMap::new();
+        let samples = [
+            (1, 0, 10, 1.0),
+            (1, 1, 11, 2.0),
+            (1, 1, 12, 3.0),
+            (1, 4, 13, 4.0),
+        ];
+
+        let batch = fold_rollups(&samples, &tiers(&[2]), &mut last_times);
+
+        assert_eq!(batch.recomputed, BTreeSet::from([(1, 2, 0)]));
+        assert_eq!(batch.folded.keys().collect::<Vec<_>>(), [&(1, 2, 4)]);
+    }
+
+    #[test]
+    fn merges_the_buckets_of_purged_samples() {
+        // The raw samples of times 0 to 3 are gone, the rollups are kept
+        let mut last_times = HashMap::from([(1, 3)]);
+        let samples = [(1, 0, 20, 1.0), (1, 1, 21, 5.0), (1, 2, 22, 2.0)];
+
+        let mut batch = fold_rollups(&samples, &tiers(&[2]), &mut last_times);
+        batch.merge((1, 2, 0), &samples);
+
+        assert_eq!(batch.recomputed, BTreeSet::from([(1, 2, 2)]));
+        let merged = &batch.folded[&(1, 2, 0)];
+        assert_eq!(merged.count, 2);
+        assert_eq!(merged.sum, 6.0);
+        assert_eq!((merged.first, merged.last), ((0, 1.0), (1, 5.0)));
+        assert_eq!(merged.wall_time, 21);
+
+        // Only buckets to recompute are merged
+        batch.merge((1, 2, 4), &samples);
+        assert!(!batch.folded.contains_key(&(1, 2, 4)));
+    }
+}
@@ -0,0 +1,179 @@
+use super::super::errors::PowsyblResult;
+use super::store::{HistorianStore, Rollup};
+use super::HistorianConfig;
+
+use serde::{Deserialize, Serialize};
+
+/// Points per series when the caller gives no target
+pub const DEFAULT_TARGET_POINTS: usize = 1_000;
+
+/// Statistics of the values of one series within one bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendBucket {
+    /// Simulation time the bucket starts at
+    pub time: u64,
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub first: f64,
+    pub last: f64,
+}
+
+impl TrendBucket {
+    fn new(time: i64, rollup: &Rollup) -> Self {
+        Self {
+            time: time as u64,
+            count: rollup.count as u64,
+            min: rollup.min,
+            max: rollup.max,
+            mean: rollup.sum / rollup.count as f64,
+            first: rollup.first.1,
+            last: rollup.last.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendSeries {
+    pub id: String,
+    pub buckets: Vec<TrendBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendQueryResult {
+    /// Width of the buckets, in simulation time units
+    pub bucket_width: u64,
+    /// Interval of the downsampling tier read, raw values when `None`
+    pub tier: Option<u64>,
+    pub series: Vec<TrendSeries>,
+}
+
+/// Bucket width giving about `target_points` buckets between `from` and `to`
+pub fn bucket_width(from: u64, to: u64, target_points: usize) -> u64 {
+    let span = to.saturating_sub(from) + 1;
+    span.div_ceil(target_points.max(1) as u64).max(1)
+}
+
+/// Sources able to fill buckets of a width, from the finest one: the coarsest tier
+/// not wider than the buckets (raw values if none), then the coarser tiers, which
+/// still hold values once the finer ones are past their retention, and last the
+/// raw values, the only ones stored before a tier was configured
+fn sources(config: &HistorianConfig, width: u64) -> Vec<Option<u64>> {
+    let mut intervals: Vec<u64> = config
+        .tiers
+        .iter()
+        .map(|tier| tier.interval.max(1))
+        .collect();
+    intervals.sort_unstable();
+    intervals.dedup();
+
+    let finer = intervals.iter().rposition(|&interval| interval <= width);
+    let mut sources = vec![finer.map(|index| intervals[index])];
+    let coarser = finer.map_or(0, |index| index + 1);
+    sources.extend(intervals[coarser..].iter().map(|&interval| Some(interval)));
+    if finer.is_some() {
+        sources.push(None);
+    }
+    sources
+}
+
+/// Bucketed statistics of the given ids between two simulation times.
+///
+/// The bucket width is derived from the target point count and rounded up to a
+/// multiple of the tier read, so every stored rollup falls in a single bucket and
+/// weeks of data are folded from a few thousand rows per series, inside SQLite.
+pub async fn query_trend(
+    store: &HistorianStore,
+    config: &HistorianConfig,
+    ids: &[String],
+    from: u64,
+    to: u64,
+    target_points: usize,
+) -> PowsyblResult<TrendQueryResult> {
+    let width = bucket_width(from, to, target_points);
+
+    let mut result = TrendQueryResult {
+        bucket_width: width,
+        tier: None,
+        series: Vec::new(),
+    };
+    for tier in sources(config, width) {
+        let bucket_width = match tier {
+            Some(interval) => width.div_ceil(interval) * interval,
+            None => width,
+        };
+
+        let mut series = Vec::with_capacity(ids.len());
+        for id in ids {
+            let buckets = store.buckets(id, from, to, tier, bucket_width).await?;
+            series.push(TrendSeries {
+                id: id.clone(),
+                buckets: buckets
+                    .iter()
+                    .map(|(start, rollup)| TrendBucket::new(*start, rollup))
+                    .collect(),
+            });
+        }
+
+        let found = series.iter().any(|series| !series.buckets.is_empty());
+        result = TrendQueryResult {
+            bucket_width,
+            tier,
+            series,
+        };
+        if found {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::DownsampleTier;
+
+    fn config(intervals: &[u64]) -> HistorianConfig {
+        HistorianConfig {
+            tiers: intervals
+                .iter()
+                .map(|&interval| DownsampleTier {
+                    interval,
+                    retention_secs: 3600,
+                })
+                .collect(),
+            ..HistorianConfig::default()
+        }
+    }
+
+    #[test]
+    fn sizes_buckets_from_the_target_points() {
+        assert_eq!(bucket_width(0, 999, 1_000), 1);
+        assert_eq!(bucket_width(0, 1_000, 1_000), 2);
+        assert_eq!(bucket_width(100, 3_699, 60), 60);
+        // Never empty, even for a single time or no target
+        assert_eq!(bucket_width(5, 5, 1_000), 1);
+        assert_eq!(bucket_width(0, 9, 0), 10);
+        assert_eq!(bucket_width(10, 0, 1_000), 1);
+    }
+
+    #[test]
+    fn reads_the_coarsest_tier_within_the_buckets_first() {
+        let config = config(&[3600, 60, 60]);
+
+        assert_eq!(sources(&config, 60), [Some(60), Some(3600), None]);
+        assert_eq!(sources(&config, 600), [Some(60), Some(3600), None]);
+        assert_eq!(sources(&config, 7200), [Some(3600), None]);
+    }
+
+    #[test]
+    fn reads_raw_values_for_buckets_finer_than_every_tier() {
+        assert_eq!(
+            sources(&config(&[60, 3600]), 10),
+            [None, Some(60), Some(3600)]
+        );
+        assert_eq!(sources(&config(&[]), 10), [None]);
+    }
+}
@@ -1,19 +1,62 @@
+use crate::state::AppState;
+use base64::Engine;
 use errors::{PowsyblError, PowsyblResult};
 use serde_json::{json, Value};
+use std::path::Path;
+use tauri::State;
 use uuid::Uuid;
 use zeromq::{Socket, SocketRecv, SocketSend};
 
+mod comparison;
 mod diagrams;
+mod networks;
 mod substations;
+mod topology;
 mod voltage_levels;
 
+pub mod alarms;
+pub mod balance;
+pub mod cache;
+pub mod calculations;
 pub mod commands;
 pub mod entities;
 pub mod errors;
+pub mod historian;
+pub mod quality;
 pub mod state;
+pub mod switching;
+
+pub use diagrams::{render_svg, telemetry_codec, ExportFormat};
+// Diagram subscriptions, also fed by the session recorder and restarted on
+// configuration reloads
+pub(crate) use diagrams::{
+    sld_subscriptions::{restart_telemetry, subscribe_diagram, unsubscribe_diagram},
+    TelemetrySink,
+};
 
 const ENDPOINT: &str = "tcp://localhost:4267";
 
+// Helper function to resolve the network targeted by a command (the active one by default)
+fn resolve_network_id(
+    state: &State<'_, AppState>,
+    network_id: Option<String>,
+) -> PowsyblResult<Option<String>> {
+    let app_state = state.read().map_err(|_| PowsyblError::LockError)?;
+    Ok(app_state.powsybl.resolve_network_id(network_id))
+}
+
+// Helper function to read a file sent to the sidecar, as its base64 content and
+// its base name
+fn encode_file(path: &str) -> PowsyblResult<(String, String)> {
+    let file_data = std::fs::read(path)?;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(&file_data);
+    let filename = match Path::new(path).file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.to_string(),
+    };
+    Ok((encoded, filename))
+}
+
 // Helper function to send ZMQ request
 async fn send_zmq_request(method: &str, params: Option<Value>) -> PowsyblResult<Value> {
     let mut socket = zeromq::ReqSocket::new();
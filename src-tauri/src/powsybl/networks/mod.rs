@@ -0,0 +1,125 @@
+use super::cache::QueryCacheState;
+use super::entities::{NetworkInfo, NetworkList};
+use super::errors::{PowsyblError, PowsyblResult};
+use super::{encode_file, send_zmq_request};
+
+use crate::state::AppState;
+
+use serde_json::json;
+use tauri::State;
+
+/// Upload an IIDM file to the sidecar and register it as a loaded network.
+/// Without an id, the file is loaded as a new network; uploading with the id of
+/// a loaded network replaces it and drops its caches.
+pub(crate) async fn upload_network_file(
+    state: &State<'_, AppState>,
+    cache: &State<'_, QueryCacheState>,
+    path: &str,
+    label: Option<String>,
+    network_id: Option<String>,
+    activate: bool,
+) -> PowsyblResult<NetworkInfo> {
+    let (encoded, filename) = encode_file(path)?;
+
+    let params = json!({
+        "file_data": encoded,
+        "filename": filename,
+        "network_id": network_id,
+        "label": label,
+        "activate": activate
+    });
+
+    let result = send_zmq_request("upload_iidm", Some(params)).await?;
+    let network_id = result
+        .get("network_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| PowsyblError::JsonParseError("Missing 'network_id' field".to_string()))?
+        .to_string();
+
+    cache.lock().await.invalidate_network(&network_id).await?;
+
+    let list = fetch_network_list().await?;
+    {
+        let mut app_state = state.write().map_err(|_| PowsyblError::LockError)?;
+        app_state.powsybl.networks.remove(&network_id);
+        app_state.powsybl.sync_networks(&list);
+    }
+
+    list.networks
+        .into_iter()
+        .find(|network| network.id == network_id)
+        .ok_or(PowsyblError::ElementNotFound(network_id))
+}
+
+pub(crate) async fn fetch_network_list() -> PowsyblResult<NetworkList> {
+    let result = send_zmq_request("list_networks", None).await?;
+    serde_json::from_value(result).map_err(|e| PowsyblError::JsonParseError(e.to_string()))
+}
+
+fn store_network_list(
+    state: &State<'_, AppState>,
+    list: &NetworkList,
+) -> PowsyblResult<Vec<NetworkInfo>> {
+    let mut app_state = state.write().map_err(|_| PowsyblError::LockError)?;
+    app_state.powsybl.sync_networks(list);
+    Ok(app_state.powsybl.network_infos())
+}
+
+/// Upload an IIDM file as an additional network
+#[tauri::command(rename_all = "snake_case")]
+pub async fn upload_network(
+    state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
+    iidm_path: String,
+    label: Option<String>,
+    network_id: Option<String>,
+    activate: Option<bool>,
+) -> PowsyblResult<NetworkInfo> {
+    upload_network_file(
+        &state,
+        &cache,
+        &iidm_path,
+        label,
+        network_id,
+        activate.unwrap_or(true),
+    )
+    .await
+}
+
+/// List the networks loaded in the sidecar
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_networks(state: State<'_, AppState>) -> PowsyblResult<Vec<NetworkInfo>> {
+    let list = fetch_network_list().await?;
+    store_network_list(&state, &list)
+}
+
+/// Select the network used by commands called without a network id
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_active_network(
+    state: State<'_, AppState>,
+    network_id: String,
+) -> PowsyblResult<Vec<NetworkInfo>> {
+    let params = json!({ "network_id": network_id });
+    let result = send_zmq_request("set_active_network", Some(params)).await?;
+    let list: NetworkList =
+        serde_json::from_value(result).map_err(|e| PowsyblError::JsonParseError(e.to_string()))?;
+
+    store_network_list(&state, &list)
+}
+
+/// Unload a network and drop its caches
+#[tauri::command(rename_all = "snake_case")]
+pub async fn remove_network(
+    state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
+    network_id: String,
+) -> PowsyblResult<Vec<NetworkInfo>> {
+    let params = json!({ "network_id": network_id });
+    let result = send_zmq_request("remove_network", Some(params)).await?;
+    let list: NetworkList =
+        serde_json::from_value(result).map_err(|e| PowsyblError::JsonParseError(e.to_string()))?;
+
+    cache.lock().await.invalidate_network(&network_id).await?;
+
+    store_network_list(&state, &list)
+}
@@ -0,0 +1,185 @@
+use super::diagrams::sld_subscriptions::game_master_outputs;
+use super::entities::{Quality, TelemetryData};
+use super::errors::PowsyblResult;
+
+use crate::shared::entities::dynawo::GameMasterOutput;
+use crate::state::AppState;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+mod tracker;
+
+use tracker::QualityTracker;
+
+/// Physical range of the measurements of a unit; either bound may be left open
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangeConfig {
+    pub unit: String,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl RangeConfig {
+    fn new(unit: &str, min: Option<f64>, max: Option<f64>) -> Self {
+        Self {
+            unit: unit.to_string(),
+            min,
+            max,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QualityConfig {
+    /// Time without update after which a measurement is stale
+    pub stale_timeout_ms: u64,
+    /// Time without change after which a measurement is frozen, 0 to disable
+    pub frozen_timeout_ms: u64,
+    /// Variation below which a value counts as unchanged
+    pub frozen_tolerance: f64,
+    /// Time without update after which a measurement is no longer tracked
+    pub expire_ms: u64,
+    pub ranges: Vec<RangeConfig>,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            stale_timeout_ms: 10_000,
+            frozen_timeout_ms: 60_000,
+            frozen_tolerance: 0.0,
+            expire_ms: 300_000,
+            ranges: vec![
+                RangeConfig::new("kV", Some(0.0), Some(1_200.0)),
+                RangeConfig::new("pu", Some(0.0), Some(3.0)),
+                RangeConfig::new("A", None, Some(100_000.0)),
+                RangeConfig::new("kA", None, Some(100.0)),
+                RangeConfig::new("MW", Some(-20_000.0), Some(20_000.0)),
+                RangeConfig::new("Mvar", Some(-20_000.0), Some(20_000.0)),
+                RangeConfig::new("MVA", Some(0.0), Some(20_000.0)),
+                RangeConfig::new("Hz", Some(0.0), Some(100.0)),
+                RangeConfig::new("deg", Some(-360.0), Some(360.0)),
+            ],
+        }
+    }
+}
+
+/// Measurement counts per quality, for data quality dashboards
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QualitySummary {
+    pub measurements: usize,
+    pub good: usize,
+    pub stale: usize,
+    pub frozen: usize,
+    pub out_of_range: usize,
+    pub invalid: usize,
+    /// NaN and infinite values rejected since startup
+    pub rejected_values: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeasurementQuality {
+    pub id: String,
+    pub quality: Quality,
+    /// Last accepted value
+    pub value: Option<f64>,
+    pub unit: Option<String>,
+    /// Time since the last update
+    pub age_ms: u64,
+    /// Time since the value last changed
+    pub unchanged_ms: u64,
+}
+
+/// Quality checks shared by the ZMQ subscriptions and the broker connections.
+///
+/// NaN and infinite values are rejected before they reach any sink; the other
+/// checks only flag the values, which are still delivered.
+#[derive(Clone, Default)]
+pub struct QualityMonitor {
+    tracker: Arc<Mutex<QualityTracker>>,
+}
+
+impl QualityMonitor {
+    // The tracker is never left inconsistent, so a poisoned lock is still usable
+    fn tracker(&self) -> MutexGuard<'_, QualityTracker> {
+        self.tracker
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Record the values of a message, removing the NaN and infinite ones
+    pub fn assess(&self, data: &mut TelemetryData) {
+        self.tracker().assess(data, Instant::now());
+    }
+
+    /// Record a single value, returning whether it is accepted
+    pub fn assess_value(&self, id: &str, value: f64) -> bool {
+        self.tracker().assess_value(id, value, Instant::now())
+    }
+
+    /// Time without update after which a measurement is stale
+    pub fn stale_timeout(&self) -> Duration {
+        Duration::from_millis(self.tracker().config().stale_timeout_ms)
+    }
+
+    /// Quality of the given telemetry ids that are not good
+    pub fn degraded<'a>(&self, ids: impl IntoIterator<Item = &'a str>) -> HashMap<String, Quality> {
+        self.tracker().degraded(ids, Instant::now())
+    }
+
+    /// Units of the outputs, which select the physical range of their values
+    pub fn set_outputs<'a>(&self, outputs: impl IntoIterator<Item = &'a GameMasterOutput>) {
+        let units = outputs
+            .into_iter()
+            .filter_map(|output| Some((output.dynawo_id.clone(), output.unit.clone()?)))
+            .collect();
+        self.tracker().set_units(units);
+    }
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_quality_summary(
+    quality: State<'_, QualityMonitor>,
+) -> PowsyblResult<QualitySummary> {
+    Ok(quality.tracker().summary(Instant::now()))
+}
+
+/// Quality of the given telemetry ids, or of every measurement that is not good
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_measurement_quality(
+    quality: State<'_, QualityMonitor>,
+    ids: Option<Vec<String>>,
+) -> PowsyblResult<Vec<MeasurementQuality>> {
+    Ok(quality
+        .tracker()
+        .measurements(ids.as_deref(), Instant::now()))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_quality_config(
+    quality: State<'_, QualityMonitor>,
+) -> PowsyblResult<QualityConfig> {
+    Ok(quality.tracker().config().clone())
+}
+
+/// Replace the quality checks, the defaults when no configuration is given
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_quality_config(
+    state: State<'_, AppState>,
+    quality: State<'_, QualityMonitor>,
+    config: Option<QualityConfig>,
+) -> PowsyblResult<QualityConfig> {
+    let outputs = game_master_outputs(&state)?;
+    quality.set_outputs(&outputs);
+
+    let config = config.unwrap_or_default();
+    quality.tracker().configure(config.clone());
+    Ok(config)
+}
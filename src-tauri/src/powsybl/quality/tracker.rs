@@ -0,0 +1,243 @@
+use super::super::entities::{Quality, TelemetryData};
+use super::{MeasurementQuality, QualityConfig, QualitySummary};
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Interval at which measurements that are no longer received are forgotten
+const EXPIRE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Last values received for a telemetry id
+#[derive(Debug)]
+struct Measurement {
+    /// Last accepted value, if any
+    value: Option<f64>,
+    last_update: Instant,
+    /// Last time the value moved by more than the frozen tolerance
+    last_change: Instant,
+    /// Whether the last value received was rejected
+    invalid: bool,
+}
+
+/// Quality of every telemetry id received, whatever the subscription
+#[derive(Debug)]
+pub struct QualityTracker {
+    config: QualityConfig,
+    measurements: HashMap<String, Measurement>,
+    units: HashMap<String, String>,
+    /// Physical range of the ids with a configured unit
+    ranges: HashMap<String, (f64, f64)>,
+    rejected: u64,
+    last_expiry: Instant,
+}
+
+impl Default for QualityTracker {
+    fn default() -> Self {
+        Self {
+            config: QualityConfig::default(),
+            measurements: HashMap::new(),
+            units: HashMap::new(),
+            ranges: HashMap::new(),
+            rejected: 0,
+            last_expiry: Instant::now(),
+        }
+    }
+}
+
+impl QualityTracker {
+    pub fn config(&self) -> &QualityConfig {
+        &self.config
+    }
+
+    pub fn configure(&mut self, config: QualityConfig) {
+        self.config = config;
+        self.resolve_ranges();
+    }
+
+    pub fn set_units(&mut self, units: HashMap<String, String>) {
+        self.units = units;
+        self.resolve_ranges();
+    }
+
+    fn resolve_ranges(&mut self) {
+        let ranges: HashMap<&str, (f64, f64)> = self
+            .config
+            .ranges
+            .iter()
+            .map(|range| {
+                let min = range.min.unwrap_or(f64::NEG_INFINITY);
+                let max = range.max.unwrap_or(f64::INFINITY);
+                (range.unit.as_str(), (min, max))
+            })
+            .collect();
+        self.ranges = self
+            .units
+            .iter()
+            .filter_map(|(id, unit)| Some((id.clone(), *ranges.get(unit.as_str())?)))
+            .collect();
+    }
+
+    /// Record the values of a message, removing the NaN and infinite ones
+    pub fn assess(&mut self, data: &mut TelemetryData, now: Instant) {
+        data.values.retain(|id, value| {
+            let accepted = value.is_finite();
+            Self::record(
+                &mut self.measurements,
+                &mut self.rejected,
+                self.config.frozen_tolerance,
+                id,
+                *value,
+                now,
+            );
+            accepted
+        });
+        self.expire(now);
+    }
+
+    /// Record a single value, returning whether it is accepted
+    pub fn assess_value(&mut self, id: &str, value: f64, now: Instant) -> bool {
+        Self::record(
+            &mut self.measurements,
+            &mut self.rejected,
+            self.config.frozen_tolerance,
+            id,
+            value,
+            now,
+        );
+        self.expire(now);
+        value.is_finite()
+    }
+
+    fn record(
+        measurements: &mut HashMap<String, Measurement>,
+        rejected: &mut u64,
+        frozen_tolerance: f64,
+        id: &str,
+        value: f64,
+        now: Instant,
+    ) {
+        let measurement = match measurements.get_mut(id) {
+            Some(measurement) => measurement,
+            None => measurements.entry(id.to_string()).or_insert(Measurement {
+                value: None,
+                last_update: now,
+                last_change: now,
+                invalid: false,
+            }),
+        };
+        measurement.last_update = now;
+
+        if !value.is_finite() {
+            measurement.invalid = true;
+            *rejected += 1;
+            return;
+        }
+        measurement.invalid = false;
+        let changed = measurement
+            .value
+            .is_none_or(|last| (value - last).abs() > frozen_tolerance);
+        if changed {
+            measurement.last_change = now;
+        }
+        measurement.value = Some(value);
+    }
+
+    // Forget the ids no longer received by any subscription
+    fn expire(&mut self, now: Instant) {
+        if now.duration_since(self.last_expiry) < EXPIRE_INTERVAL {
+            return;
+        }
+        self.last_expiry = now;
+        let expire_after = Duration::from_millis(self.config.expire_ms);
+        self.measurements
+            .retain(|_, measurement| now.duration_since(measurement.last_update) < expire_after);
+    }
+
+    fn quality(&self, id: &str, measurement: &Measurement, now: Instant) -> Quality {
+        let config = &self.config;
+        if now.duration_since(measurement.last_update)
+            > Duration::from_millis(config.stale_timeout_ms)
+        {
+            return Quality::Stale;
+        }
+        if measurement.invalid {
+            return Quality::Invalid;
+        }
+        let out_of_range = match (self.ranges.get(id), measurement.value) {
+            (Some(&(min, max)), Some(value)) => value < min || value > max,
+            _ => false,
+        };
+        if out_of_range {
+            return Quality::OutOfRange;
+        }
+        if config.frozen_timeout_ms > 0
+            && now.duration_since(measurement.last_change)
+                > Duration::from_millis(config.frozen_timeout_ms)
+        {
+            return Quality::Frozen;
+        }
+        Quality::Good
+    }
+
+    /// Quality of the given ids that are tracked and not good
+    pub fn degraded<'a>(
+        &self,
+        ids: impl IntoIterator<Item = &'a str>,
+        now: Instant,
+    ) -> HashMap<String, Quality> {
+        ids.into_iter()
+            .filter_map(|id| {
+                let measurement = self.measurements.get(id)?;
+                match self.quality(id, measurement, now) {
+                    Quality::Good => None,
+                    quality => Some((id.to_string(), quality)),
+                }
+            })
+            .collect()
+    }
+
+    pub fn summary(&self, now: Instant) -> QualitySummary {
+        let mut summary = QualitySummary {
+            measurements: self.measurements.len(),
+            rejected_values: self.rejected,
+            ..QualitySummary::default()
+        };
+        for (id, measurement) in &self.measurements {
+            match self.quality(id, measurement, now) {
+                Quality::Good => summary.good += 1,
+                Quality::Frozen => summary.frozen += 1,
+                Quality::OutOfRange => summary.out_of_range += 1,
+                Quality::Invalid => summary.invalid += 1,
+                Quality::Stale => summary.stale += 1,
+            }
+        }
+        summary
+    }
+
+    /// Details of the given ids, or of every measurement that is not good
+    pub fn measurements(&self, ids: Option<&[String]>, now: Instant) -> Vec<MeasurementQuality> {
+        let describe = |id: &String, measurement: &Measurement| MeasurementQuality {
+            id: id.clone(),
+            quality: self.quality(id, measurement, now),
+            value: measurement.value,
+            unit: self.units.get(id).cloned(),
+            age_ms: now.duration_since(measurement.last_update).as_millis() as u64,
+            unchanged_ms: now.duration_since(measurement.last_change).as_millis() as u64,
+        };
+
+        let mut measurements: Vec<MeasurementQuality> = match ids {
+            Some(ids) => ids
+                .iter()
+                .filter_map(|id| Some(describe(id, self.measurements.get(id)?)))
+                .collect(),
+            None => self
+                .measurements
+                .iter()
+                .map(|(id, measurement)| describe(id, measurement))
+                .filter(|measurement| measurement.quality != Quality::Good)
+                .collect(),
+        };
+        measurements.sort_by(|a, b| a.id.cmp(&b.id));
+        measurements
+    }
+}
@@ -1,6 +1,12 @@
+use super::calculations::CalculationSet;
+use super::diagrams::entities::TelemetrySinks;
+use super::entities::{NetworkInfo, NetworkList};
+use super::topology::NetworkGraph;
 use crate::shared::entities::iidm::{Substation, VoltageLevel};
 
+use log::debug;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 
@@ -10,41 +16,111 @@ pub struct SubscriptionHandle {
     pub shutdown_sender: broadcast::Sender<()>,
 }
 
+/// Data cached for a single loaded network
 #[derive(Debug, Default)]
-pub struct PowsyblState {
+pub struct NetworkCache {
+    pub info: NetworkInfo,
     pub substations: HashMap<String, Substation>,
     pub voltage_levels: HashMap<String, VoltageLevel>,
-    pub ti_subscriptions: HashMap<String, SubscriptionHandle>,
+    pub topology: Option<NetworkGraph>,
+}
+
+#[derive(Debug, Default)]
+pub struct PowsyblState {
+    pub networks: HashMap<String, NetworkCache>,
+    pub active_network: Option<String>,
+    /// The telemetry subscription, running while it has consumers
+    pub telemetry: Option<SubscriptionHandle>,
+    pub telemetry_sinks: TelemetrySinks,
+    /// Derived points computed by every subscription
+    pub calculations: Arc<CalculationSet>,
 }
 
 impl PowsyblState {
-    pub fn spawn_task<F>(&mut self, id: String, task_fn: F)
+    /// Network targeted by a command, falling back to the active network
+    pub fn resolve_network_id(&self, network_id: Option<String>) -> Option<String> {
+        network_id.or_else(|| self.active_network.clone())
+    }
+
+    pub fn network(&self, network_id: Option<&str>) -> Option<&NetworkCache> {
+        self.networks.get(network_id?)
+    }
+
+    /// Cache of a network, created on first use; there is none without a network id
+    pub fn network_mut(&mut self, network_id: Option<&str>) -> Option<&mut NetworkCache> {
+        Some(self.networks.entry(network_id?.to_string()).or_default())
+    }
+
+    /// Align the cached networks with the ones loaded in the sidecar.
+    /// Caches of networks that are still loaded are kept.
+    pub fn sync_networks(&mut self, list: &NetworkList) {
+        self.networks
+            .retain(|id, _| list.networks.iter().any(|network| &network.id == id));
+
+        for info in &list.networks {
+            self.networks.entry(info.id.clone()).or_default().info = info.clone();
+        }
+
+        self.active_network = list.active_network_id.clone();
+    }
+
+    /// Drop every cached network, to be listed again from the sidecar.
+    /// A restarted sidecar may hold other files under the same network ids.
+    pub fn forget_networks(&mut self) {
+        self.networks.clear();
+        self.active_network = None;
+    }
+
+    pub fn network_infos(&self) -> Vec<NetworkInfo> {
+        let mut infos: Vec<NetworkInfo> = self
+            .networks
+            .values()
+            .filter(|network| !network.info.id.is_empty())
+            .map(|network| NetworkInfo {
+                active: self.active_network.as_deref() == Some(network.info.id.as_str()),
+                ..network.info.clone()
+            })
+            .collect();
+        infos.sort_by(|a, b| a.label.cmp(&b.label));
+        infos
+    }
+
+    /// Whether the telemetry subscription runs; it ends on its own after too many
+    /// failures, or once its consumers are gone
+    pub fn telemetry_running(&self) -> bool {
+        self.telemetry
+            .as_ref()
+            .is_some_and(|task| !task.handle.is_finished())
+    }
+
+    /// Start the telemetry subscription, replacing one that ended
+    pub fn spawn_telemetry<F>(&mut self, task_fn: F)
     where
         F: FnOnce(broadcast::Receiver<()>) -> JoinHandle<()>,
     {
+        if self.telemetry.take().is_some() {
+            debug!("Replacing the finished telemetry subscription");
+        }
+
         let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
         let handle = task_fn(shutdown_rx);
 
-        self.ti_subscriptions.insert(
-            id,
-            SubscriptionHandle {
-                handle,
-                shutdown_sender: shutdown_tx,
-            },
-        );
-    }
-
-    pub fn stop_task(&mut self, id: &str) -> bool {
-        if let Some(task) = self.ti_subscriptions.remove(id) {
-            let _ = task.shutdown_sender.send(());
-            task.handle.abort();
-            true
-        } else {
-            false
-        }
+        self.telemetry = Some(SubscriptionHandle {
+            handle,
+            shutdown_sender: shutdown_tx,
+        });
     }
 
-    pub fn has_task(&self, id: &str) -> bool {
-        self.ti_subscriptions.contains_key(id)
+    /// Stop the telemetry subscription, returning whether it was running
+    pub fn stop_telemetry(&mut self) -> bool {
+        match self.telemetry.take() {
+            Some(task) => {
+                let running = !task.handle.is_finished();
+                let _ = task.shutdown_sender.send(());
+                task.handle.abort();
+                running
+            }
+            None => false,
+        }
     }
 }
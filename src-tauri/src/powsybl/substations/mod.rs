@@ -1,6 +1,6 @@
 use super::entities::{FetchStatus, PaginatedResponse, PaginationParams};
 use super::errors::{PowsyblError, PowsyblResult};
-use super::send_zmq_request;
+use super::{resolve_network_id, send_zmq_request};
 
 use crate::shared::entities::iidm::Substation;
 use crate::state::AppState;
@@ -9,9 +9,17 @@ use tauri::State;
 
 /// Get all substations from the API
 #[tauri::command(rename_all = "snake_case")]
-pub async fn get_substations(state: State<'_, AppState>) -> PowsyblResult<Vec<Substation>> {
+pub async fn get_substations(
+    state: State<'_, AppState>,
+    network_id: Option<String>,
+) -> PowsyblResult<Vec<Substation>> {
+    let network_id = resolve_network_id(&state, network_id)?;
+    let params = serde_json::json!({
+        "network_id": network_id
+    });
+
     // Send request to get network substations
-    let result = send_zmq_request("get_network_substations", None).await?;
+    let result = send_zmq_request("get_network_substations", Some(params)).await?;
     let substations =
         if let Some(substations_arr) = result.get("substations").and_then(|s| s.as_array()) {
             substations_arr
@@ -23,15 +31,16 @@ pub async fn get_substations(state: State<'_, AppState>) -> PowsyblResult<Vec<Su
                 "Failed to parse substations".to_string(),
             ));
         };
-    // Update the state
+    // Update the cache of this network
     {
         let mut app_state = state.write().map_err(|_| PowsyblError::LockError)?;
-        app_state.powsybl.substations.clear();
-        for substation in &substations {
-            app_state
-                .powsybl
-                .substations
-                .insert(substation.id.clone(), substation.clone());
+        if let Some(network) = app_state.powsybl.network_mut(network_id.as_deref()) {
+            network.substations.clear();
+            for substation in &substations {
+                network
+                    .substations
+                    .insert(substation.id.clone(), substation.clone());
+            }
         }
     }
 
@@ -40,8 +49,11 @@ pub async fn get_substations(state: State<'_, AppState>) -> PowsyblResult<Vec<Su
 
 /// Load all substations from the ZMQ server and store them in the application state
 #[tauri::command(rename_all = "snake_case")]
-pub async fn load_substations(state: State<'_, AppState>) -> PowsyblResult<FetchStatus> {
-    let substations = get_substations(state).await?;
+pub async fn load_substations(
+    state: State<'_, AppState>,
+    network_id: Option<String>,
+) -> PowsyblResult<FetchStatus> {
+    let substations = get_substations(state, network_id).await?;
 
     Ok(FetchStatus {
         success: true,
@@ -54,18 +66,23 @@ pub async fn load_substations(state: State<'_, AppState>) -> PowsyblResult<Fetch
 pub fn get_paginated_substations(
     state: State<'_, AppState>,
     pagination: Option<PaginationParams>,
+    network_id: Option<String>,
 ) -> PowsyblResult<PaginatedResponse<Vec<Substation>>> {
     // This function remains the same as it reads from local state
     let params = pagination.unwrap_or_default();
     let app_state = state.read().map_err(|_| PowsyblError::LockError)?;
+    let network_id = app_state.powsybl.resolve_network_id(network_id);
+    let substations: Vec<&Substation> = app_state
+        .powsybl
+        .network(network_id.as_deref())
+        .map(|network| network.substations.values().collect())
+        .unwrap_or_default();
 
-    let total = app_state.powsybl.substations.len();
+    let total = substations.len();
     let total_pages = (total + params.per_page - 1) / params.per_page;
 
-    let page_items: Vec<Substation> = app_state
-        .powsybl
-        .substations
-        .values()
+    let page_items: Vec<Substation> = substations
+        .into_iter()
         .skip((params.page - 1) * params.per_page)
         .take(params.per_page)
         .cloned()
@@ -85,10 +102,15 @@ pub fn get_paginated_substations(
 pub fn get_substation_by_id(
     state: State<'_, AppState>,
     id: String,
+    network_id: Option<String>,
 ) -> PowsyblResult<Option<Substation>> {
     // This function remains the same as it reads from local state
     let app_state = state.read().map_err(|_| PowsyblError::LockError)?;
-    let substation = app_state.powsybl.substations.get(&id).cloned();
+    let network_id = app_state.powsybl.resolve_network_id(network_id);
+    let substation = app_state
+        .powsybl
+        .network(network_id.as_deref())
+        .and_then(|network| network.substations.get(&id).cloned());
     Ok(substation)
 }
 
@@ -99,6 +121,7 @@ pub fn search_substations(
     query: String,
     pagination: Option<PaginationParams>,
     search_fields: Option<Vec<String>>,
+    network_id: Option<String>,
 ) -> PowsyblResult<PaginatedResponse<Vec<Substation>>> {
     // This function remains the same as it reads from local state
     let params = pagination.unwrap_or_default();
@@ -113,12 +136,14 @@ pub fn search_substations(
     });
 
     let app_state = state.read().map_err(|_| PowsyblError::LockError)?;
+    let network_id = app_state.powsybl.resolve_network_id(network_id);
     let query = query.to_lowercase();
 
     let filtered_substations: Vec<Substation> = app_state
         .powsybl
-        .substations
-        .values()
+        .network(network_id.as_deref())
+        .into_iter()
+        .flat_map(|network| network.substations.values())
         .filter(|substation| {
             if query.is_empty() {
                 return true;
@@ -0,0 +1,69 @@
+use super::super::entities::TelemetryData;
+use super::{SwitchConfig, SwitchEvent, SwitchState};
+
+use std::collections::HashMap;
+
+/// A switch output, by telemetry id
+#[derive(Debug, Clone)]
+pub struct SwitchOutput {
+    pub equipment_id: String,
+    pub component_type: String,
+}
+
+/// Last known state of every switch output, turning value changes into events
+pub struct SwitchDetector {
+    config: SwitchConfig,
+    switches: HashMap<String, SwitchOutput>,
+    states: HashMap<String, SwitchState>,
+    last_time: Option<u64>,
+}
+
+impl SwitchDetector {
+    pub fn new(config: SwitchConfig, switches: HashMap<String, SwitchOutput>) -> Self {
+        Self {
+            config,
+            switches,
+            states: HashMap::new(),
+            last_time: None,
+        }
+    }
+
+    /// Events of the switches whose state differs from their last value.
+    ///
+    /// The first value of a switch only sets its state. When the simulation time
+    /// goes back, as when a replay is seeked, the states are forgotten rather than
+    /// reported as operations.
+    pub fn detect(&mut self, data: &TelemetryData, wall_time: i64) -> Vec<SwitchEvent> {
+        if self.last_time.is_some_and(|last| data.time < last) {
+            self.states.clear();
+        }
+        self.last_time = Some(data.time);
+
+        let mut events = Vec::new();
+        for (id, &value) in &data.values {
+            let Some(switch) = self.switches.get(id) else {
+                continue;
+            };
+            let new_state = self.config.state(value);
+            let Some(old_state) = self.states.insert(id.clone(), new_state) else {
+                continue;
+            };
+            if old_state == new_state {
+                continue;
+            }
+            events.push(SwitchEvent {
+                telemetry_id: id.clone(),
+                equipment_id: switch.equipment_id.clone(),
+                component_type: switch.component_type.clone(),
+                old_state,
+                new_state,
+                value,
+                sim_time: data.time,
+                wall_time,
+            });
+        }
+        // Frames carry values in no particular order
+        events.sort_by(|a, b| a.equipment_id.cmp(&b.equipment_id));
+        events
+    }
+}
@@ -0,0 +1,105 @@
+use super::super::diagrams::errors::{SubscriptionError, SubscriptionResult};
+use super::super::entities::TelemetryData;
+use super::super::historian::now_millis;
+use super::detector::SwitchDetector;
+use super::store::SwitchStore;
+use super::{SwitchEvent, SWITCH_EVENT};
+
+use log::{debug, error, info, warn};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+
+/// Sending half of the switch log, handed to the telemetry subscription.
+///
+/// Like the alarm engine, frames are dropped and counted rather than queued
+/// without bound when the log lags behind.
+#[derive(Clone)]
+pub struct SwitchFeed {
+    sender: mpsc::Sender<TelemetryData>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl SwitchFeed {
+    pub fn send(&self, data: TelemetryData) -> SubscriptionResult<()> {
+        match self.sender.try_send(data) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                warn!("Switch log queue full, telemetry frame dropped");
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(SubscriptionError::ChannelSendError(
+                "switch log stopped".to_string(),
+            )),
+        }
+    }
+}
+
+/// Counters shared between the log task and its status
+#[derive(Debug, Default)]
+pub struct SwitchCounters {
+    pub events: AtomicU64,
+    pub dropped: Arc<AtomicU64>,
+}
+
+/// Create the feed and the task logging the switch operations it receives,
+/// which ends when every feed is dropped
+pub fn spawn_log(
+    detector: SwitchDetector,
+    store: SwitchStore,
+    app_handle: AppHandle,
+    queue_capacity: usize,
+    counters: Arc<SwitchCounters>,
+) -> (SwitchFeed, tokio::task::JoinHandle<()>) {
+    let (sender, receiver) = mpsc::channel(queue_capacity.max(1));
+    let feed = SwitchFeed {
+        sender,
+        dropped: counters.dropped.clone(),
+    };
+    let handle = tokio::spawn(run(detector, store, app_handle, receiver, counters));
+    (feed, handle)
+}
+
+async fn run(
+    mut detector: SwitchDetector,
+    store: SwitchStore,
+    app_handle: AppHandle,
+    mut receiver: mpsc::Receiver<TelemetryData>,
+    counters: Arc<SwitchCounters>,
+) {
+    info!("Switch log started");
+
+    while let Some(data) = receiver.recv().await {
+        let events = detector.detect(&data, now_millis());
+        publish(&store, &app_handle, &events).await;
+        counters
+            .events
+            .fetch_add(events.len() as u64, Ordering::Relaxed);
+    }
+
+    info!("Switch log finished");
+}
+
+// Store the events and push them to the frontend
+async fn publish(store: &SwitchStore, app_handle: &AppHandle, events: &[SwitchEvent]) {
+    if events.is_empty() {
+        return;
+    }
+    if let Err(e) = store.insert(events).await {
+        error!("Failed to store {} switch events: {}", events.len(), e);
+    }
+    for event in events {
+        debug!(
+            "{} {} from {} to {}",
+            event.component_type,
+            event.equipment_id,
+            event.old_state.as_str(),
+            event.new_state.as_str()
+        );
+        if let Err(e) = app_handle.emit(SWITCH_EVENT, event) {
+            warn!("Failed to emit switch event: {}", e);
+        }
+    }
+}
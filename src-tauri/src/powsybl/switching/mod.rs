@@ -0,0 +1,289 @@
+use super::diagrams::sld_subscriptions::{
+    game_master_outputs, subscribe_diagram, unsubscribe_diagram,
+};
+use super::diagrams::TelemetrySink;
+use super::errors::{PowsyblError, PowsyblResult};
+
+use crate::state::AppState;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+use tokio::task::JoinHandle;
+
+mod detector;
+mod engine;
+mod store;
+
+pub use engine::SwitchFeed;
+pub use store::{SwitchLogEntry, SwitchLogQuery};
+
+use detector::{SwitchDetector, SwitchOutput};
+use engine::SwitchCounters;
+use store::SwitchStore;
+
+/// Event carrying every switch operation
+pub const SWITCH_EVENT: &str = "switch-event";
+
+/// Subscription id of the switch log feed, alongside the diagram ones
+const SWITCH_SUBSCRIPTION: &str = "switching";
+
+/// Time the log is given to store its last frames when stopped
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwitchState {
+    Open,
+    Closed,
+    /// Neither the open nor the closed value, as a switch moving or faulty
+    Intermediate,
+}
+
+impl SwitchState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwitchState::Open => "open",
+            SwitchState::Closed => "closed",
+            SwitchState::Intermediate => "intermediate",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SwitchConfig {
+    /// Component types of the game master outputs carrying a switch state
+    pub component_types: Vec<String>,
+    /// Values of an open and a closed switch, as the open flag of the diagram
+    /// snapshots by default
+    pub open_value: f64,
+    pub closed_value: f64,
+    pub tolerance: f64,
+    /// Frames waiting for the log before new ones are dropped
+    pub queue_capacity: usize,
+}
+
+impl Default for SwitchConfig {
+    fn default() -> Self {
+        Self {
+            component_types: [
+                "BREAKER",
+                "DISCONNECTOR",
+                "LOAD_BREAK_SWITCH",
+                "GROUND_DISCONNECTION",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            open_value: 1.0,
+            closed_value: 0.0,
+            tolerance: 1e-6,
+            queue_capacity: 1_024,
+        }
+    }
+}
+
+impl SwitchConfig {
+    /// State of a switch from the value of its output
+    pub fn state(&self, value: f64) -> SwitchState {
+        let matches = |expected: f64| (value - expected).abs() <= self.tolerance;
+        if matches(self.open_value) {
+            SwitchState::Open
+        } else if matches(self.closed_value) {
+            SwitchState::Closed
+        } else {
+            SwitchState::Intermediate
+        }
+    }
+}
+
+/// A change of state of a switch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchEvent {
+    pub telemetry_id: String,
+    pub equipment_id: String,
+    pub component_type: String,
+    pub old_state: SwitchState,
+    pub new_state: SwitchState,
+    pub value: f64,
+    pub sim_time: u64,
+    pub wall_time: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchLogStatus {
+    pub running: bool,
+    pub config: SwitchConfig,
+    pub switches: usize,
+    pub events: u64,
+    pub frames_dropped: u64,
+}
+
+/// Sequence of events of the switches: every change of state of the switch
+/// outputs received from the ZMQ feed, stored in SQLite
+pub struct SwitchLog {
+    pool: Pool<Sqlite>,
+    config: SwitchConfig,
+    switches: usize,
+    counters: Arc<SwitchCounters>,
+    log: Option<JoinHandle<()>>,
+}
+
+pub type SwitchLogState = tokio::sync::Mutex<SwitchLog>;
+
+impl SwitchLog {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self {
+            pool,
+            config: SwitchConfig::default(),
+            switches: 0,
+            counters: Arc::default(),
+            log: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.log.as_ref().is_some_and(|log| !log.is_finished())
+    }
+
+    fn start(
+        &mut self,
+        config: SwitchConfig,
+        switches: HashMap<String, SwitchOutput>,
+        app_handle: AppHandle,
+    ) -> SwitchFeed {
+        self.switches = switches.len();
+        self.counters = Arc::default();
+
+        let (feed, log) = engine::spawn_log(
+            SwitchDetector::new(config.clone(), switches),
+            SwitchStore::new(self.pool.clone()),
+            app_handle,
+            config.queue_capacity,
+            self.counters.clone(),
+        );
+        self.config = config;
+        self.log = Some(log);
+        feed
+    }
+
+    async fn stop(&mut self) {
+        let Some(log) = self.log.take() else {
+            return;
+        };
+        match tokio::time::timeout(STOP_TIMEOUT, log).await {
+            Ok(Ok(())) => info!("Switch log stopped"),
+            Ok(Err(e)) => warn!("Switch log failed: {}", e),
+            Err(_) => warn!("Timeout while waiting for the switch log"),
+        }
+    }
+
+    pub fn status(&self) -> SwitchLogStatus {
+        SwitchLogStatus {
+            running: self.is_running(),
+            config: self.config.clone(),
+            switches: self.switches,
+            events: self.counters.events.load(Ordering::Relaxed),
+            frames_dropped: self.counters.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Start logging the state changes of the switch outputs, restarting the log if
+/// it runs. Switch states are only known from their first value received.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn start_switch_log(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    switching: State<'_, SwitchLogState>,
+    config: Option<SwitchConfig>,
+) -> PowsyblResult<SwitchLogStatus> {
+    let config = config.unwrap_or_default();
+    let switches: HashMap<String, SwitchOutput> = game_master_outputs(&state)?
+        .into_iter()
+        .filter_map(|output| {
+            let component_type = output.component_type?;
+            if !config.component_types.contains(&component_type) {
+                return None;
+            }
+            let equipment_id = output
+                .equipment_id
+                .or(output.graphical_id)
+                .unwrap_or(output.id);
+            Some((
+                output.dynawo_id,
+                SwitchOutput {
+                    equipment_id,
+                    component_type,
+                },
+            ))
+        })
+        .collect();
+    if switches.is_empty() {
+        return Err(PowsyblError::SwitchLogError(format!(
+            "No game master output of type {}",
+            config.component_types.join(", ")
+        )));
+    }
+
+    let mut switching = switching.lock().await;
+    unsubscribe_diagram(&state, SWITCH_SUBSCRIPTION);
+    switching.stop().await;
+
+    let feed = switching.start(config, switches, app_handle.clone());
+    subscribe_diagram(
+        app_handle,
+        &state,
+        SWITCH_SUBSCRIPTION.to_string(),
+        TelemetrySink::Switching(feed),
+    )
+    .await?;
+
+    let status = switching.status();
+    info!("Switch log started on {} switches", status.switches);
+    Ok(status)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn stop_switch_log(
+    state: State<'_, AppState>,
+    switching: State<'_, SwitchLogState>,
+) -> PowsyblResult<SwitchLogStatus> {
+    let mut switching = switching.lock().await;
+
+    // Dropping the subscription closes the queue of the log
+    unsubscribe_diagram(&state, SWITCH_SUBSCRIPTION);
+    switching.stop().await;
+
+    Ok(switching.status())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_switch_log_status(
+    switching: State<'_, SwitchLogState>,
+) -> PowsyblResult<SwitchLogStatus> {
+    Ok(switching.lock().await.status())
+}
+
+/// Stored switch events, in chronological order
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_switch_events(
+    switching: State<'_, SwitchLogState>,
+    query: Option<SwitchLogQuery>,
+) -> PowsyblResult<Vec<SwitchLogEntry>> {
+    let pool = switching.lock().await.pool.clone();
+    SwitchStore::new(pool)
+        .events(&query.unwrap_or_default())
+        .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn clear_switch_events(switching: State<'_, SwitchLogState>) -> PowsyblResult<u64> {
+    let pool = switching.lock().await.pool.clone();
+    SwitchStore::new(pool).clear().await
+}
@@ -0,0 +1,104 @@
+use super::super::errors::PowsyblResult;
+use super::SwitchEvent;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, QueryBuilder, Sqlite};
+
+/// Events per insert statement, within the 32766 bound parameters of SQLite
+const EVENT_ROWS: usize = 3_000;
+
+/// A stored switch event
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SwitchLogEntry {
+    pub id: i64,
+    pub telemetry_id: String,
+    pub equipment_id: String,
+    pub component_type: String,
+    pub old_state: String,
+    pub new_state: String,
+    pub value: f64,
+    pub sim_time: i64,
+    pub wall_time: i64,
+}
+
+/// Filter of the switch log; wall times are in milliseconds since the Unix epoch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SwitchLogQuery {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub equipment_id: Option<String>,
+    pub component_type: Option<String>,
+    /// Earliest events returned, 1000 by default
+    pub limit: Option<u32>,
+}
+
+pub struct SwitchStore {
+    pool: Pool<Sqlite>,
+}
+
+impl SwitchStore {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn insert(&self, events: &[SwitchEvent]) -> PowsyblResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for chunk in events.chunks(EVENT_ROWS) {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "INSERT INTO switch_events (telemetry_id, equipment_id, component_type, \
+                 old_state, new_state, value, sim_time, wall_time) ",
+            );
+            query.push_values(chunk, |mut row, event| {
+                row.push_bind(&event.telemetry_id)
+                    .push_bind(&event.equipment_id)
+                    .push_bind(&event.component_type)
+                    .push_bind(event.old_state.as_str())
+                    .push_bind(event.new_state.as_str())
+                    .push_bind(event.value)
+                    .push_bind(event.sim_time as i64)
+                    .push_bind(event.wall_time);
+            });
+            query.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Events in the order they happened
+    pub async fn events(&self, filter: &SwitchLogQuery) -> PowsyblResult<Vec<SwitchLogEntry>> {
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "SELECT id, telemetry_id, equipment_id, component_type, old_state, new_state, \
+             value, sim_time, wall_time FROM switch_events WHERE 1 = 1",
+        );
+        if let Some(from) = filter.from {
+            query.push(" AND wall_time >= ").push_bind(from);
+        }
+        if let Some(to) = filter.to {
+            query.push(" AND wall_time <= ").push_bind(to);
+        }
+        if let Some(equipment_id) = &filter.equipment_id {
+            query.push(" AND equipment_id = ").push_bind(equipment_id);
+        }
+        if let Some(component_type) = &filter.component_type {
+            query
+                .push(" AND component_type = ")
+                .push_bind(component_type);
+        }
+        query
+            .push(" ORDER BY wall_time, id LIMIT ")
+            .push_bind(filter.limit.unwrap_or(1_000) as i64);
+
+        Ok(query
+            .build_query_as::<SwitchLogEntry>()
+            .fetch_all(&self.pool)
+            .await?)
+    }
+
+    pub async fn clear(&self) -> PowsyblResult<u64> {
+        let result = sqlx::query("DELETE FROM switch_events")
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+/// Raw network topology as returned by the sidecar
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkTopologyData {
+    pub substations: Vec<TopologySubstation>,
+    pub voltage_levels: Vec<TopologyVoltageLevel>,
+    pub buses: Vec<TopologyBus>,
+    pub branches: Vec<TopologyBranch>,
+    #[serde(default)]
+    pub three_windings_transformers: Vec<TopologyThreeWindingsTransformer>,
+    #[serde(default)]
+    pub injections: Vec<TopologyInjection>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopologySubstation {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopologyVoltageLevel {
+    pub id: String,
+    pub substation_id: String,
+    pub nominal_v: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopologyBus {
+    pub id: String,
+    pub voltage_level_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BranchKind {
+    Line,
+    TwoWindingsTransformer,
+    TieLine,
+    /// Link between the buses of two converter stations, without reactance
+    HvdcLine,
+    /// A pair of legs of a three windings transformer
+    ThreeWindingsTransformer,
+}
+
+impl BranchKind {
+    /// Whether the ends of the branch are listed among the injections instead,
+    /// as converter stations or transformer legs
+    pub fn has_injection_ends(&self) -> bool {
+        matches!(
+            self,
+            BranchKind::HvdcLine | BranchKind::ThreeWindingsTransformer
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyBranch {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: BranchKind,
+    pub voltage_level1_id: String,
+    pub voltage_level2_id: String,
+    #[serde(default)]
+    pub bus1_id: Option<String>,
+    #[serde(default)]
+    pub bus2_id: Option<String>,
+    #[serde(default)]
+    pub connected1: bool,
+    #[serde(default)]
+    pub connected2: bool,
+    #[serde(default)]
+    pub x: f64,
+}
+
+impl TopologyBranch {
+    /// Whether the branch is closed at both ends and attached to a bus on each side
+    pub fn is_connected(&self) -> bool {
+        self.connected1
+            && self.connected2
+            && self.bus1_id.as_deref().is_some_and(|id| !id.is_empty())
+            && self.bus2_id.as_deref().is_some_and(|id| !id.is_empty())
+    }
+}
+
+/// Leg of a three windings transformer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyLeg {
+    pub side: String,
+    pub voltage_level_id: String,
+    #[serde(default)]
+    pub bus_id: Option<String>,
+    #[serde(default)]
+    pub connected: bool,
+    /// Reactance between the leg and the star point, in ohms at `rated_u0`
+    #[serde(default)]
+    pub x: f64,
+}
+
+impl TopologyLeg {
+    pub fn is_connected(&self) -> bool {
+        self.connected && self.bus_id.as_deref().is_some_and(|id| !id.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyThreeWindingsTransformer {
+    pub id: String,
+    /// Voltage of the star point
+    #[serde(default)]
+    pub rated_u0: f64,
+    pub legs: Vec<TopologyLeg>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InjectionKind {
+    Load,
+    Generator,
+    Battery,
+    ShuntCompensator,
+    StaticVarCompensator,
+    VscConverterStation,
+    LccConverterStation,
+    DanglingLine,
+    /// A leg of a three windings transformer
+    ThreeWindingsTransformer,
+    #[serde(other)]
+    Other,
+}
+
+impl InjectionKind {
+    /// Whether the equipment exchanges active power with its bus
+    pub fn carries_active_power(&self) -> bool {
+        !matches!(
+            self,
+            InjectionKind::ShuntCompensator | InjectionKind::StaticVarCompensator
+        )
+    }
+}
+
+/// Equipment connected to a single bus, or a leg of a three windings transformer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyInjection {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: InjectionKind,
+    /// Leg of a three windings transformer
+    #[serde(default)]
+    pub side: Option<String>,
+    #[serde(default)]
+    pub bus_id: Option<String>,
+    #[serde(default)]
+    pub connected: bool,
+}
+
+/// A substation reachable from the queried element
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyNeighbor {
+    pub substation_id: String,
+    pub name: String,
+    pub hops: usize,
+    pub voltage_level_ids: Vec<String>,
+}
+
+/// Shortest electrical path between two substations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyPath {
+    pub substation_ids: Vec<String>,
+    pub branch_ids: Vec<String>,
+    /// Sum of the branch reactances along the path, in per-unit on a 100 MVA base
+    pub total_reactance_pu: f64,
+}
+
+/// Set of buses electrically connected together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyIsland {
+    pub index: usize,
+    pub main: bool,
+    pub bus_ids: Vec<String>,
+    pub voltage_level_ids: Vec<String>,
+    pub substation_ids: Vec<String>,
+}
+
+/// Summary returned once a topology has been built
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologySummary {
+    pub substations: usize,
+    pub voltage_levels: usize,
+    pub buses: usize,
+    pub branches: usize,
+    pub islands: usize,
+}
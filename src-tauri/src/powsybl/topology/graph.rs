@@ -0,0 +1,616 @@
+use super::entities::{
+    BranchKind, InjectionKind, NetworkTopologyData, TopologyBranch, TopologyInjection,
+    TopologyIsland, TopologyNeighbor, TopologyPath, TopologySummary,
+};
+
+use petgraph::algo::articulation_points::articulation_points;
+use petgraph::algo::astar;
+use petgraph::graph::{NodeIndex, UnGraph};
+use petgraph::visit::EdgeRef;
+use std::cmp::Reverse;
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, VecDeque};
+
+/// Base power used to express reactances in per-unit
+const BASE_MVA: f64 = 100.0;
+
+/// Electrical node of the bus view
+#[derive(Debug, Clone)]
+pub struct BusNode {
+    pub id: String,
+    pub voltage_level_id: String,
+    pub substation_id: String,
+    /// Connected injections and three windings transformer legs
+    pub injections: Vec<TopologyInjection>,
+}
+
+/// Branch linking two buses
+#[derive(Debug, Clone)]
+pub struct BranchEdge {
+    pub id: String,
+    pub kind: BranchKind,
+    pub reactance_pu: f64,
+}
+
+/// Equipment end connected to a bus
+#[derive(Debug, Clone)]
+pub struct Terminal {
+    pub equipment_id: String,
+    /// `ONE` or `TWO` for branches, and up to `THREE` for three windings transformers
+    pub side: Option<String>,
+    /// Kind of the injection, `None` for a branch end
+    pub injection: Option<InjectionKind>,
+    /// Bus at the other end of a branch
+    pub remote_bus_id: Option<String>,
+}
+
+/// A bus and every equipment end connected to it
+#[derive(Debug, Clone)]
+pub struct BusTerminals {
+    pub bus_id: String,
+    pub substation_id: String,
+    pub terminals: Vec<Terminal>,
+}
+
+/// All the branches running between two substations
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct SubstationLink {
+    pub branch_ids: Vec<String>,
+    /// Branch of the lowest reactance, the one a path through the link takes
+    pub branch_id: String,
+    pub reactance_pu: f64,
+}
+
+/// Substation / bus / branch graph of the loaded network.
+///
+/// Two views are kept: the bus graph, where every connected branch is an edge
+/// between two buses, and the substation graph, where parallel branches between
+/// two substations are collapsed into a single link.
+#[derive(Debug, Default)]
+pub struct NetworkGraph {
+    buses: UnGraph<BusNode, BranchEdge>,
+    bus_index: HashMap<String, NodeIndex>,
+    substations: UnGraph<String, SubstationLink>,
+    substation_index: HashMap<String, NodeIndex>,
+    substation_names: HashMap<String, String>,
+    voltage_levels: HashMap<String, String>,
+    branches: usize,
+}
+
+impl NetworkGraph {
+    pub fn build(data: &NetworkTopologyData) -> Self {
+        let mut graph = NetworkGraph::default();
+
+        for substation in &data.substations {
+            let index = graph.substations.add_node(substation.id.clone());
+            graph.substation_index.insert(substation.id.clone(), index);
+            graph
+                .substation_names
+                .insert(substation.id.clone(), substation.name.clone());
+        }
+
+        let mut nominal_voltages = HashMap::new();
+        for voltage_level in &data.voltage_levels {
+            graph.voltage_levels.insert(
+                voltage_level.id.clone(),
+                voltage_level.substation_id.clone(),
+            );
+            nominal_voltages.insert(voltage_level.id.as_str(), voltage_level.nominal_v);
+        }
+
+        for bus in &data.buses {
+            let Some(substation_id) = graph.voltage_levels.get(&bus.voltage_level_id) else {
+                continue;
+            };
+            let index = graph.buses.add_node(BusNode {
+                id: bus.id.clone(),
+                voltage_level_id: bus.voltage_level_id.clone(),
+                substation_id: substation_id.clone(),
+                injections: Vec::new(),
+            });
+            graph.bus_index.insert(bus.id.clone(), index);
+        }
+
+        for injection in data.injections.iter().filter(|i| i.connected) {
+            let Some(&bus) = injection
+                .bus_id
+                .as_ref()
+                .and_then(|id| graph.bus_index.get(id))
+            else {
+                continue;
+            };
+            graph.buses[bus].injections.push(injection.clone());
+        }
+
+        for branch in data.branches.iter().filter(|b| b.is_connected()) {
+            let (Some(&bus1), Some(&bus2)) = (
+                branch
+                    .bus1_id
+                    .as_ref()
+                    .and_then(|id| graph.bus_index.get(id)),
+                branch
+                    .bus2_id
+                    .as_ref()
+                    .and_then(|id| graph.bus_index.get(id)),
+            ) else {
+                continue;
+            };
+
+            let reactance_pu = Self::reactance_pu(branch, &nominal_voltages);
+            graph.add_branch(bus1, bus2, &branch.id, branch.kind, reactance_pu);
+            graph.branches += 1;
+        }
+
+        // Three windings transformers link each pair of their connected legs
+        for transformer in &data.three_windings_transformers {
+            let legs: Vec<(NodeIndex, f64)> = transformer
+                .legs
+                .iter()
+                .filter(|leg| leg.is_connected())
+                .filter_map(|leg| {
+                    let &bus = graph.bus_index.get(leg.bus_id.as_ref()?)?;
+                    Some((bus, leg.x.abs()))
+                })
+                .collect();
+            if legs.len() < 2 {
+                continue;
+            }
+
+            let base_impedance = transformer.rated_u0 * transformer.rated_u0 / BASE_MVA;
+            for (i, &(bus1, x1)) in legs.iter().enumerate() {
+                for &(bus2, x2) in &legs[i + 1..] {
+                    let reactance_pu = if base_impedance > 0.0 {
+                        (x1 + x2) / base_impedance
+                    } else {
+                        x1 + x2
+                    };
+                    graph.add_branch(
+                        bus1,
+                        bus2,
+                        &transformer.id,
+                        BranchKind::ThreeWindingsTransformer,
+                        reactance_pu,
+                    );
+                }
+            }
+            graph.branches += 1;
+        }
+
+        graph
+    }
+
+    fn add_branch(
+        &mut self,
+        bus1: NodeIndex,
+        bus2: NodeIndex,
+        id: &str,
+        kind: BranchKind,
+        reactance_pu: f64,
+    ) {
+        self.buses.add_edge(
+            bus1,
+            bus2,
+            BranchEdge {
+                id: id.to_string(),
+                kind,
+                reactance_pu,
+            },
+        );
+
+        let substation1 = self.buses[bus1].substation_id.clone();
+        let substation2 = self.buses[bus2].substation_id.clone();
+        if substation1 != substation2 {
+            self.link_substations(&substation1, &substation2, id, reactance_pu);
+        }
+    }
+
+    /// Convert the branch reactance from ohms to per-unit, using the nominal voltage
+    /// of the side the reactance is expressed on (side 2 for transformers)
+    fn reactance_pu(branch: &TopologyBranch, nominal_voltages: &HashMap<&str, f64>) -> f64 {
+        let voltage_level_id = match branch.kind {
+            BranchKind::TwoWindingsTransformer => &branch.voltage_level2_id,
+            _ => &branch.voltage_level1_id,
+        };
+
+        match nominal_voltages.get(voltage_level_id.as_str()) {
+            Some(&nominal_v) if nominal_v > 0.0 => {
+                branch.x.abs() / (nominal_v * nominal_v / BASE_MVA)
+            }
+            _ => branch.x.abs(),
+        }
+    }
+
+    fn link_substations(&mut self, from: &str, to: &str, branch_id: &str, reactance_pu: f64) {
+        let (Some(&a), Some(&b)) = (
+            self.substation_index.get(from),
+            self.substation_index.get(to),
+        ) else {
+            return;
+        };
+
+        if let Some(edge) = self.substations.find_edge(a, b) {
+            let link = &mut self.substations[edge];
+            if !link.branch_ids.iter().any(|id| id == branch_id) {
+                link.branch_ids.push(branch_id.to_string());
+            }
+            if reactance_pu < link.reactance_pu {
+                link.branch_id = branch_id.to_string();
+                link.reactance_pu = reactance_pu;
+            }
+        } else {
+            self.substations.add_edge(
+                a,
+                b,
+                SubstationLink {
+                    branch_ids: vec![branch_id.to_string()],
+                    branch_id: branch_id.to_string(),
+                    reactance_pu,
+                },
+            );
+        }
+    }
+
+    pub fn summary(&self) -> TopologySummary {
+        TopologySummary {
+            substations: self.substations.node_count(),
+            voltage_levels: self.voltage_levels.len(),
+            buses: self.buses.node_count(),
+            branches: self.branches,
+            islands: self.islands().len(),
+        }
+    }
+
+    /// Resolve a substation, voltage level or bus id to its substation id
+    pub fn resolve_substation(&self, element_id: &str) -> Option<&str> {
+        if let Some(&index) = self.substation_index.get(element_id) {
+            return Some(self.substations[index].as_str());
+        }
+        if let Some(substation_id) = self.voltage_levels.get(element_id) {
+            return Some(substation_id.as_str());
+        }
+        self.bus_index
+            .get(element_id)
+            .map(|&index| self.buses[index].substation_id.as_str())
+    }
+
+    /// Substations reachable from `element_id` in at most `max_hops` substation-to-substation steps
+    pub fn neighbors(&self, element_id: &str, max_hops: usize) -> Option<Vec<TopologyNeighbor>> {
+        let origin = self.resolve_substation(element_id)?;
+        let &start = self.substation_index.get(origin)?;
+
+        let mut hops = HashMap::from([(start, 0usize)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            let depth = hops[&node];
+            if depth == max_hops {
+                continue;
+            }
+            for next in self.substations.neighbors(node) {
+                if let Entry::Vacant(entry) = hops.entry(next) {
+                    entry.insert(depth + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut neighbors: Vec<TopologyNeighbor> = hops
+            .into_iter()
+            .filter(|&(node, _)| node != start)
+            .map(|(node, hops)| self.neighbor(node, hops))
+            .collect();
+        neighbors.sort_by(|a, b| {
+            a.hops
+                .cmp(&b.hops)
+                .then_with(|| a.substation_id.cmp(&b.substation_id))
+        });
+
+        Some(neighbors)
+    }
+
+    fn neighbor(&self, node: NodeIndex, hops: usize) -> TopologyNeighbor {
+        let substation_id = self.substations[node].clone();
+        let mut voltage_level_ids: Vec<String> = self
+            .voltage_levels
+            .iter()
+            .filter(|(_, substation)| **substation == substation_id)
+            .map(|(voltage_level, _)| voltage_level.clone())
+            .collect();
+        voltage_level_ids.sort();
+
+        TopologyNeighbor {
+            name: self
+                .substation_names
+                .get(&substation_id)
+                .cloned()
+                .unwrap_or_default(),
+            substation_id,
+            hops,
+            voltage_level_ids,
+        }
+    }
+
+    /// Path between two substations minimising the total series reactance
+    pub fn shortest_path(&self, from: &str, to: &str) -> Option<TopologyPath> {
+        let &start = self.substation_index.get(self.resolve_substation(from)?)?;
+        let &goal = self.substation_index.get(self.resolve_substation(to)?)?;
+
+        let (total_reactance_pu, nodes) = astar(
+            &self.substations,
+            start,
+            |node| node == goal,
+            |edge| edge.weight().reactance_pu,
+            |_| 0.0,
+        )?;
+
+        let branch_ids = nodes
+            .windows(2)
+            .filter_map(|pair| self.substations.find_edge(pair[0], pair[1]))
+            .map(|edge| self.substations[edge].branch_id.clone())
+            .collect();
+
+        Some(TopologyPath {
+            substation_ids: nodes
+                .into_iter()
+                .map(|node| self.substations[node].clone())
+                .collect(),
+            branch_ids,
+            total_reactance_pu,
+        })
+    }
+
+    /// Connected components of the bus graph, largest first
+    pub fn islands(&self) -> Vec<TopologyIsland> {
+        let mut visited = vec![false; self.buses.node_count()];
+        let mut components: Vec<Vec<NodeIndex>> = Vec::new();
+
+        for start in self.buses.node_indices() {
+            if visited[start.index()] {
+                continue;
+            }
+            visited[start.index()] = true;
+
+            let mut component = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(node) = queue.pop_front() {
+                for edge in self.buses.edges(node) {
+                    let next = if edge.source() == node {
+                        edge.target()
+                    } else {
+                        edge.source()
+                    };
+                    if !visited[next.index()] {
+                        visited[next.index()] = true;
+                        component.push(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            components.push(component);
+        }
+
+        components.sort_by_key(|component| Reverse(component.len()));
+
+        components
+            .into_iter()
+            .enumerate()
+            .map(|(index, component)| {
+                let mut bus_ids = Vec::with_capacity(component.len());
+                let mut voltage_level_ids = BTreeSet::new();
+                let mut substation_ids = BTreeSet::new();
+                for node in component {
+                    let bus = &self.buses[node];
+                    bus_ids.push(bus.id.clone());
+                    voltage_level_ids.insert(bus.voltage_level_id.clone());
+                    substation_ids.insert(bus.substation_id.clone());
+                }
+                bus_ids.sort();
+
+                TopologyIsland {
+                    index,
+                    main: index == 0,
+                    bus_ids,
+                    voltage_level_ids: voltage_level_ids.into_iter().collect(),
+                    substation_ids: substation_ids.into_iter().collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Equipment ends connected to every bus, branch ends first
+    pub fn bus_terminals(&self) -> Vec<BusTerminals> {
+        let mut terminals: Vec<Vec<Terminal>> = vec![Vec::new(); self.buses.node_count()];
+
+        // Branches are added from their side 1 bus to their side 2 bus
+        for edge in self.buses.edge_references() {
+            let branch = edge.weight();
+            if branch.kind.has_injection_ends() {
+                continue;
+            }
+            for (side, bus, remote) in [
+                ("ONE", edge.source(), edge.target()),
+                ("TWO", edge.target(), edge.source()),
+            ] {
+                terminals[bus.index()].push(Terminal {
+                    equipment_id: branch.id.clone(),
+                    side: Some(side.to_string()),
+                    injection: None,
+                    remote_bus_id: Some(self.buses[remote].id.clone()),
+                });
+            }
+        }
+
+        self.buses
+            .node_indices()
+            .zip(terminals)
+            .map(|(node, mut terminals)| {
+                let bus = &self.buses[node];
+                terminals.extend(bus.injections.iter().map(|injection| Terminal {
+                    equipment_id: injection.id.clone(),
+                    side: injection.side.clone(),
+                    injection: Some(injection.kind),
+                    remote_bus_id: None,
+                }));
+                BusTerminals {
+                    bus_id: bus.id.clone(),
+                    substation_id: bus.substation_id.clone(),
+                    terminals,
+                }
+            })
+            .collect()
+    }
+
+    /// Substations whose loss would split the grid into more components
+    pub fn articulation_points(&self) -> Vec<String> {
+        let mut substation_ids: Vec<String> = articulation_points(&self.substations)
+            .into_iter()
+            .map(|node| self.substations[node].clone())
+            .collect();
+        substation_ids.sort();
+        substation_ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Four meshed substations, with two parallel lines between S1 and S2, a
+    // transformer to S4 and S5 only reached by an open line
+    fn graph() -> NetworkGraph {
+        let branch = |id: &str, kind: &str, bus1: &str, bus2: &str, x: f64| {
+            json!({
+                "id": id,
+                "type": kind,
+                "voltage_level1_id": format!("VL{}", &bus1[1..]),
+                "voltage_level2_id": format!("VL{}", &bus2[1..]),
+                "bus1_id": bus1,
+                "bus2_id": bus2,
+                "connected1": true,
+                "connected2": true,
+                "x": x,
+            })
+        };
+        let mut open = branch("L15", "LINE", "B1", "B5", 16.0);
+        open["connected2"] = json!(false);
+
+        let data: NetworkTopologyData = serde_json::from_value(json!({
+            "substations": (1..=5)
+                .map(|i| json!({"id": format!("S{}", i), "name": format!("Substation {}", i)}))
+                .collect::<Vec<_>>(),
+            "voltage_levels": (1..=5)
+                .map(|i| json!({
+                    "id": format!("VL{}", i),
+                    "substation_id": format!("S{}", i),
+                    "nominal_v": if i == 4 { 225.0 } else { 400.0 },
+                }))
+                .collect::<Vec<_>>(),
+            "buses": (1..=5)
+                .map(|i| json!({"id": format!("B{}", i), "voltage_level_id": format!("VL{}", i)}))
+                .collect::<Vec<_>>(),
+            "branches": [
+                branch("L12A", "LINE", "B1", "B2", 32.0),
+                branch("L12B", "LINE", "B1", "B2", 16.0),
+                branch("L23", "LINE", "B2", "B3", 16.0),
+                branch("L13", "LINE", "B1", "B3", 80.0),
+                branch("T34", "TWO_WINDINGS_TRANSFORMER", "B3", "B4", 5.0625),
+                open,
+            ],
+        }))
+        .unwrap();
+        NetworkGraph::build(&data)
+    }
+
+    fn hops(neighbors: &[TopologyNeighbor]) -> Vec<(&str, usize)> {
+        neighbors
+            .iter()
+            .map(|neighbor| (neighbor.substation_id.as_str(), neighbor.hops))
+            .collect()
+    }
+
+    #[test]
+    fn summary_counts_connected_branches() {
+        let summary = graph().summary();
+
+        assert_eq!(summary.substations, 5);
+        assert_eq!(summary.voltage_levels, 5);
+        assert_eq!(summary.buses, 5);
+        assert_eq!(summary.branches, 5);
+        assert_eq!(summary.islands, 2);
+    }
+
+    #[test]
+    fn neighbors_within_hops() {
+        let graph = graph();
+
+        let neighbors = graph.neighbors("S1", 1).unwrap();
+        assert_eq!(hops(&neighbors), [("S2", 1), ("S3", 1)]);
+        assert_eq!(neighbors[0].name, "Substation 2");
+        assert_eq!(neighbors[0].voltage_level_ids, ["VL2"]);
+
+        // Voltage levels and buses resolve to their substation
+        let neighbors = graph.neighbors("B4", 2).unwrap();
+        assert_eq!(hops(&neighbors), [("S3", 1), ("S1", 2), ("S2", 2)]);
+        assert!(graph.neighbors("VL5", 3).unwrap().is_empty());
+        assert!(graph.neighbors("unknown", 1).is_none());
+    }
+
+    #[test]
+    fn shortest_path_takes_the_lowest_reactance() {
+        let path = graph().shortest_path("S1", "VL4").unwrap();
+
+        assert_eq!(path.substation_ids, ["S1", "S2", "S3", "S4"]);
+        // Of the parallel lines, the one of the lowest reactance is taken
+        assert_eq!(path.branch_ids, ["L12B", "L23", "T34"]);
+        // 16 Ω at 400 kV and 5.0625 Ω at 225 kV are 0.01 pu on 100 MVA
+        assert!((path.total_reactance_pu - 0.03).abs() < 1e-9);
+    }
+
+    #[test]
+    fn shortest_path_between_islands() {
+        assert!(graph().shortest_path("S1", "S5").is_none());
+    }
+
+    #[test]
+    fn islands_largest_first() {
+        let islands = graph().islands();
+
+        assert_eq!(islands.len(), 2);
+        assert!(islands[0].main);
+        assert_eq!(islands[0].bus_ids, ["B1", "B2", "B3", "B4"]);
+        assert_eq!(islands[0].substation_ids, ["S1", "S2", "S3", "S4"]);
+        assert!(!islands[1].main);
+        assert_eq!(islands[1].bus_ids, ["B5"]);
+    }
+
+    #[test]
+    fn articulation_points_of_the_substation_graph() {
+        assert_eq!(graph().articulation_points(), ["S3"]);
+    }
+
+    #[test]
+    fn bus_terminals_of_branch_ends() {
+        let terminals = graph().bus_terminals();
+        let b2 = terminals.iter().find(|bus| bus.bus_id == "B2").unwrap();
+
+        let mut ends: Vec<(&str, Option<&str>, Option<&str>)> = b2
+            .terminals
+            .iter()
+            .map(|terminal| {
+                (
+                    terminal.equipment_id.as_str(),
+                    terminal.side.as_deref(),
+                    terminal.remote_bus_id.as_deref(),
+                )
+            })
+            .collect();
+        ends.sort();
+        assert_eq!(
+            ends,
+            [
+                ("L12A", Some("TWO"), Some("B1")),
+                ("L12B", Some("TWO"), Some("B1")),
+                ("L23", Some("ONE"), Some("B3")),
+            ]
+        );
+    }
+}
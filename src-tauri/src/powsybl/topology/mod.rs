@@ -0,0 +1,129 @@
+use super::entities::FetchStatus;
+use super::errors::{PowsyblError, PowsyblResult};
+use super::{resolve_network_id, send_zmq_request};
+
+use crate::state::AppState;
+
+use tauri::State;
+
+mod entities;
+mod graph;
+
+pub use entities::{
+    InjectionKind, TopologyIsland, TopologyNeighbor, TopologyPath, TopologySummary,
+};
+pub use graph::{BusTerminals, NetworkGraph, Terminal};
+
+use entities::NetworkTopologyData;
+
+// Helper function to run a query on the topology of a network
+pub(super) fn with_topology<T>(
+    state: &State<'_, AppState>,
+    network_id: Option<String>,
+    query: impl FnOnce(&NetworkGraph) -> PowsyblResult<T>,
+) -> PowsyblResult<T> {
+    let app_state = state.read().map_err(|_| PowsyblError::LockError)?;
+    let network_id = app_state.powsybl.resolve_network_id(network_id);
+    let graph = app_state
+        .powsybl
+        .network(network_id.as_deref())
+        .and_then(|network| network.topology.as_ref())
+        .ok_or(PowsyblError::TopologyNotLoaded)?;
+
+    query(graph)
+}
+
+/// Build the network graph from the sidecar and store it in the application state
+#[tauri::command(rename_all = "snake_case")]
+pub async fn load_network_topology(
+    state: State<'_, AppState>,
+    network_id: Option<String>,
+) -> PowsyblResult<FetchStatus> {
+    let network_id = resolve_network_id(&state, network_id)?.ok_or(PowsyblError::NoNetwork)?;
+    let params = serde_json::json!({
+        "network_id": network_id
+    });
+
+    let result = send_zmq_request("get_network_topology", Some(params)).await?;
+    let data: NetworkTopologyData =
+        serde_json::from_value(result).map_err(|e| PowsyblError::JsonParseError(e.to_string()))?;
+
+    let graph = NetworkGraph::build(&data);
+    let summary = graph.summary();
+
+    {
+        let mut app_state = state.write().map_err(|_| PowsyblError::LockError)?;
+        if let Some(network) = app_state.powsybl.network_mut(Some(&network_id)) {
+            network.topology = Some(graph);
+        }
+    }
+
+    Ok(FetchStatus {
+        success: true,
+        message: format!(
+            "Loaded topology with {} buses, {} branches and {} islands",
+            summary.buses, summary.branches, summary.islands
+        ),
+    })
+}
+
+/// Get the size of the loaded network graph
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_topology_summary(
+    state: State<'_, AppState>,
+    network_id: Option<String>,
+) -> PowsyblResult<TopologySummary> {
+    with_topology(&state, network_id, |graph| Ok(graph.summary()))
+}
+
+/// Get the substations within `hops` branches of a substation, voltage level or bus
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_topology_neighbors(
+    state: State<'_, AppState>,
+    element_id: String,
+    hops: Option<usize>,
+    network_id: Option<String>,
+) -> PowsyblResult<Vec<TopologyNeighbor>> {
+    with_topology(&state, network_id, |graph| {
+        graph
+            .neighbors(&element_id, hops.unwrap_or(1))
+            .ok_or(PowsyblError::ElementNotFound(element_id))
+    })
+}
+
+/// Get the shortest electrical path between two substations (or their voltage levels)
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_topology_shortest_path(
+    state: State<'_, AppState>,
+    from_id: String,
+    to_id: String,
+    network_id: Option<String>,
+) -> PowsyblResult<Option<TopologyPath>> {
+    with_topology(&state, network_id, |graph| {
+        for id in [&from_id, &to_id] {
+            if graph.resolve_substation(id).is_none() {
+                return Err(PowsyblError::ElementNotFound(id.clone()));
+            }
+        }
+
+        Ok(graph.shortest_path(&from_id, &to_id))
+    })
+}
+
+/// Get the connected components of the network, main island first
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_topology_islands(
+    state: State<'_, AppState>,
+    network_id: Option<String>,
+) -> PowsyblResult<Vec<TopologyIsland>> {
+    with_topology(&state, network_id, |graph| Ok(graph.islands()))
+}
+
+/// Get the substations whose loss would split the network
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_topology_articulation_points(
+    state: State<'_, AppState>,
+    network_id: Option<String>,
+) -> PowsyblResult<Vec<String>> {
+    with_topology(&state, network_id, |graph| Ok(graph.articulation_points()))
+}
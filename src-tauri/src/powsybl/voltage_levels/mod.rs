@@ -1,6 +1,7 @@
+use super::cache::{cached_zmq_request, QueryCacheState};
 use super::entities::{FetchStatus, PaginatedResponse, PaginationParams};
 use super::errors::{PowsyblError, PowsyblResult};
-use super::send_zmq_request;
+use super::{resolve_network_id, send_zmq_request};
 
 use crate::shared::entities::iidm::VoltageLevel;
 use crate::state::AppState;
@@ -9,9 +10,17 @@ use tauri::State;
 
 /// Get all voltage levels from the ZMQ broker
 #[tauri::command(rename_all = "snake_case")]
-pub async fn get_voltage_levels(state: State<'_, AppState>) -> PowsyblResult<Vec<VoltageLevel>> {
+pub async fn get_voltage_levels(
+    state: State<'_, AppState>,
+    network_id: Option<String>,
+) -> PowsyblResult<Vec<VoltageLevel>> {
+    let network_id = resolve_network_id(&state, network_id)?;
+    let params = serde_json::json!({
+        "network_id": network_id
+    });
+
     // Send request to get network voltage levels
-    let result = send_zmq_request("get_network_voltage_levels", None).await?;
+    let result = send_zmq_request("get_network_voltage_levels", Some(params)).await?;
     let voltage_levels =
         if let Some(voltage_levels_arr) = result.get("voltage_levels").and_then(|s| s.as_array()) {
             voltage_levels_arr
@@ -24,15 +33,16 @@ pub async fn get_voltage_levels(state: State<'_, AppState>) -> PowsyblResult<Vec
             ));
         };
 
-    // Update the state
+    // Update the cache of this network
     {
         let mut app_state = state.write().map_err(|_| PowsyblError::LockError)?;
-        app_state.powsybl.voltage_levels.clear();
-        for voltage_level in &voltage_levels {
-            app_state
-                .powsybl
-                .voltage_levels
-                .insert(voltage_level.id.clone(), voltage_level.clone());
+        if let Some(network) = app_state.powsybl.network_mut(network_id.as_deref()) {
+            network.voltage_levels.clear();
+            for voltage_level in &voltage_levels {
+                network
+                    .voltage_levels
+                    .insert(voltage_level.id.clone(), voltage_level.clone());
+            }
         }
     }
 
@@ -41,8 +51,11 @@ pub async fn get_voltage_levels(state: State<'_, AppState>) -> PowsyblResult<Vec
 
 /// Load all voltage levels from the ZMQ server and store them in the application state
 #[tauri::command(rename_all = "snake_case")]
-pub async fn load_voltage_levels(state: State<'_, AppState>) -> PowsyblResult<FetchStatus> {
-    let voltage_levels = get_voltage_levels(state).await?;
+pub async fn load_voltage_levels(
+    state: State<'_, AppState>,
+    network_id: Option<String>,
+) -> PowsyblResult<FetchStatus> {
+    let voltage_levels = get_voltage_levels(state, network_id).await?;
     Ok(FetchStatus {
         success: true,
         message: format!(
@@ -57,22 +70,27 @@ pub async fn load_voltage_levels(state: State<'_, AppState>) -> PowsyblResult<Fe
 pub fn get_paginated_voltage_levels(
     state: State<'_, AppState>,
     pagination: Option<PaginationParams>,
+    network_id: Option<String>,
 ) -> PowsyblResult<PaginatedResponse<Vec<VoltageLevel>>> {
     // Use pagination parameters or default values
     let params = pagination.unwrap_or_default();
 
     // Access state with a lock
     let app_state = state.read().map_err(|_| PowsyblError::LockError)?;
+    let network_id = app_state.powsybl.resolve_network_id(network_id);
+    let voltage_levels: Vec<&VoltageLevel> = app_state
+        .powsybl
+        .network(network_id.as_deref())
+        .map(|network| network.voltage_levels.values().collect())
+        .unwrap_or_default();
 
-    // Get total count directly from HashMap size
-    let total = app_state.powsybl.voltage_levels.len();
+    // Get total count from the cached voltage levels of this network
+    let total = voltage_levels.len();
     let total_pages = (total + params.per_page - 1) / params.per_page;
 
     // Collect only the needed items for the current page
-    let page_items: Vec<VoltageLevel> = app_state
-        .powsybl
-        .voltage_levels
-        .values()
+    let page_items: Vec<VoltageLevel> = voltage_levels
+        .into_iter()
         .skip((params.page - 1) * params.per_page)
         .take(params.per_page)
         .cloned()
@@ -93,11 +111,16 @@ pub fn get_paginated_voltage_levels(
 pub fn get_voltage_levels_by_id(
     state: State<'_, AppState>,
     id: String,
+    network_id: Option<String>,
 ) -> PowsyblResult<Option<VoltageLevel>> {
     let app_state = state.read().map_err(|_| PowsyblError::LockError)?;
+    let network_id = app_state.powsybl.resolve_network_id(network_id);
 
     // Directly get the voltage level from the HashMap by ID and clone it
-    let voltage_level = app_state.powsybl.voltage_levels.get(&id).cloned();
+    let voltage_level = app_state
+        .powsybl
+        .network(network_id.as_deref())
+        .and_then(|network| network.voltage_levels.get(&id).cloned());
 
     Ok(voltage_level)
 }
@@ -105,15 +128,28 @@ pub fn get_voltage_levels_by_id(
 /// Get voltage levels for a specific substation
 #[tauri::command(rename_all = "snake_case")]
 pub async fn get_voltage_levels_for_substation(
+    state: State<'_, AppState>,
+    cache: State<'_, QueryCacheState>,
     substation_id: String,
+    network_id: Option<String>,
 ) -> PowsyblResult<Vec<VoltageLevel>> {
+    let network_id = resolve_network_id(&state, network_id)?;
+
     // Create parameters for the request
     let params = serde_json::json!({
-        "substation_id": substation_id
+        "substation_id": substation_id,
+        "network_id": network_id
     });
 
     // Send request to get voltage levels for a specific substation
-    let result = send_zmq_request("get_voltage_levels_for_substation", Some(params)).await?;
+    let result = cached_zmq_request(
+        &cache,
+        &state,
+        "get_voltage_levels_for_substation",
+        params,
+        network_id.as_deref(),
+    )
+    .await?;
 
     let voltage_levels =
         if let Some(voltage_levels_arr) = result.get("voltage_levels").and_then(|s| s.as_array()) {
@@ -137,6 +173,7 @@ pub fn search_voltage_levels(
     query: String,
     pagination: Option<PaginationParams>,
     search_fields: Option<Vec<String>>,
+    network_id: Option<String>,
 ) -> PowsyblResult<PaginatedResponse<Vec<VoltageLevel>>> {
     let params = pagination.unwrap_or_default();
 
@@ -150,12 +187,14 @@ pub fn search_voltage_levels(
     });
 
     let app_state = state.read().map_err(|_| PowsyblError::LockError)?;
+    let network_id = app_state.powsybl.resolve_network_id(network_id);
     let query = query.to_lowercase();
 
     let filtered_voltage_levels: Vec<VoltageLevel> = app_state
         .powsybl
-        .voltage_levels
-        .values()
+        .network(network_id.as_deref())
+        .into_iter()
+        .flat_map(|network| network.voltage_levels.values())
         .filter(|voltage_level| {
             if query.is_empty() {
                 return true;
@@ -0,0 +1,156 @@
+use super::errors::{SessionError, SessionResult};
+use super::player::{ReplayControl, ReplayHandle, ReplayStatus};
+use super::recorder::{Recording, RecordingStatus};
+use super::state::{SessionState, SessionStatus, TelemetryFeed};
+
+use crate::broker::state::BrokerState;
+use crate::powsybl::{subscribe_diagram, unsubscribe_diagram, TelemetrySink};
+use crate::state::AppState;
+
+use log::info;
+use tauri::{AppHandle, State};
+use tokio::sync::broadcast;
+
+/// Id of the ZMQ subscription feeding the recording
+const SESSION_SUBSCRIPTION: &str = "session-recorder";
+
+/// Replayed messages a slow consumer may lag behind before skipping some
+const REPLAY_BUS_CAPACITY: usize = 4096;
+
+/// Record the live NATS and ZMQ telemetry to a session file
+#[tauri::command(rename_all = "snake_case")]
+pub async fn start_session_recording(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    broker: State<'_, BrokerState>,
+    sessions: State<'_, SessionState>,
+    feed: State<'_, TelemetryFeed>,
+    output_path: String,
+) -> SessionResult<RecordingStatus> {
+    let mut sessions = sessions.lock().await;
+    if let Some(recording) = &sessions.recording {
+        return Err(SessionError::AlreadyRecording(recording.status().path));
+    }
+
+    let client = broker.lock().await.client.clone();
+    let recording = Recording::start(output_path, &client, &feed.dictionary(false)).await?;
+
+    if let Err(e) = subscribe_diagram(
+        app_handle,
+        &state,
+        SESSION_SUBSCRIPTION.to_string(),
+        TelemetrySink::Session(recording.recorder()),
+    )
+    .await
+    {
+        recording.stop().await?;
+        return Err(e.into());
+    }
+
+    let status = recording.status();
+    sessions.recording = Some(recording);
+    Ok(status)
+}
+
+/// Stop the recording, once every message received is written
+#[tauri::command(rename_all = "snake_case")]
+pub async fn stop_session_recording(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionState>,
+) -> SessionResult<RecordingStatus> {
+    let mut sessions = sessions.lock().await;
+    let recording = sessions
+        .recording
+        .take()
+        .ok_or(SessionError::NotRecording)?;
+
+    unsubscribe_diagram(&state, SESSION_SUBSCRIPTION);
+    recording.stop().await
+}
+
+/// Open a session file for replay, paused at its start.
+///
+/// The broker connections and the diagram subscriptions read the replayed
+/// messages instead of the live feeds until the replay is closed.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn open_session_replay(
+    app_handle: AppHandle,
+    sessions: State<'_, SessionState>,
+    feed: State<'_, TelemetryFeed>,
+    path: String,
+    speed: Option<f64>,
+) -> SessionResult<ReplayStatus> {
+    let mut sessions = sessions.lock().await;
+    if let Some(replay) = sessions.replay.take() {
+        feed.set_replay(None);
+        replay.close().await;
+    }
+
+    let (bus, _) = broadcast::channel(REPLAY_BUS_CAPACITY);
+    let replay = ReplayHandle::open(path, bus.clone(), speed.unwrap_or(1.0), app_handle).await?;
+    feed.set_replay(Some(bus));
+
+    let status = replay.status();
+    sessions.replay = Some(replay);
+    Ok(status)
+}
+
+async fn control_replay(
+    sessions: State<'_, SessionState>,
+    control: ReplayControl,
+) -> SessionResult<ReplayStatus> {
+    let sessions = sessions.lock().await;
+    let replay = sessions.replay.as_ref().ok_or(SessionError::NotReplaying)?;
+    replay.control(control).await
+}
+
+/// Play the replay, from its start once ended
+#[tauri::command(rename_all = "snake_case")]
+pub async fn play_session_replay(sessions: State<'_, SessionState>) -> SessionResult<ReplayStatus> {
+    control_replay(sessions, ReplayControl::Play).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn pause_session_replay(
+    sessions: State<'_, SessionState>,
+) -> SessionResult<ReplayStatus> {
+    control_replay(sessions, ReplayControl::Pause).await
+}
+
+/// Jump to a position, in milliseconds since the start of the session
+#[tauri::command(rename_all = "snake_case")]
+pub async fn seek_session_replay(
+    sessions: State<'_, SessionState>,
+    position_ms: u64,
+) -> SessionResult<ReplayStatus> {
+    control_replay(sessions, ReplayControl::Seek(position_ms)).await
+}
+
+/// Set the replay speed, 1.0 being the speed of the recording
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_session_replay_speed(
+    sessions: State<'_, SessionState>,
+    speed: f64,
+) -> SessionResult<ReplayStatus> {
+    control_replay(sessions, ReplayControl::Speed(speed)).await
+}
+
+/// Close the replay and go back to the live feeds
+#[tauri::command(rename_all = "snake_case")]
+pub async fn close_session_replay(
+    sessions: State<'_, SessionState>,
+    feed: State<'_, TelemetryFeed>,
+) -> SessionResult<()> {
+    let mut sessions = sessions.lock().await;
+    let replay = sessions.replay.take().ok_or(SessionError::NotReplaying)?;
+
+    feed.set_replay(None);
+    replay.close().await;
+    info!("Back to the live telemetry");
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_session_status(sessions: State<'_, SessionState>) -> SessionResult<SessionStatus> {
+    Ok(sessions.lock().await.status())
+}
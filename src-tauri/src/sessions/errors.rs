@@ -0,0 +1,53 @@
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::powsybl::errors::PowsyblError;
+
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid session file: {0}")]
+    InvalidFile(String),
+
+    #[error("Subscription error: {0}")]
+    SubscriptionError(#[from] PowsyblError),
+
+    #[error("Nats subscribe error: {0}")]
+    NatsSubscribeError(#[from] async_nats::SubscribeError),
+
+    #[error("A session is already being recorded to {0}")]
+    AlreadyRecording(String),
+
+    #[error("No session is being recorded")]
+    NotRecording,
+
+    #[error("No session is being replayed")]
+    NotReplaying,
+
+    #[error("Invalid replay speed: {0}")]
+    InvalidSpeed(f64),
+
+    #[error("Session task error: {0}")]
+    TaskError(String),
+}
+
+// Implement Serialize for SessionError for Tauri command compatibility
+impl Serialize for SessionError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+// Convenience conversion for returning errors from Tauri commands
+impl From<SessionError> for String {
+    fn from(err: SessionError) -> Self {
+        err.to_string()
+    }
+}
+
+pub type SessionResult<T> = Result<T, SessionError>;
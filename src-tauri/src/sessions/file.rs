@@ -0,0 +1,222 @@
+use super::errors::{SessionError, SessionResult};
+use crate::powsybl::telemetry_codec::TelemetryHeader;
+
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+/// Start of every session file, followed by the format version
+const MAGIC: &[u8; 8] = b"ARGUSSES";
+const VERSION: u8 = 1;
+
+const ZMQ_RECORD: u8 = 0;
+const NATS_RECORD: u8 = 1;
+
+/// A raw telemetry message, as received from one of the feeds
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionMessage {
+    /// The frames of a ZMQ message, topic included
+    Zmq {
+        frames: Vec<Vec<u8>>,
+    },
+    Nats {
+        subject: String,
+        payload: Vec<u8>,
+    },
+}
+
+impl SessionMessage {
+    /// Whether the message carries the id dictionary of binary ZMQ telemetry,
+    /// which later messages depend on
+    pub fn is_dictionary(&self) -> bool {
+        match self {
+            SessionMessage::Zmq { frames } => frames
+                .len()
+                .checked_sub(2)
+                .and_then(|index| TelemetryHeader::parse(&frames[index]))
+                .is_some_and(|header| header.dictionary),
+            SessionMessage::Nats { .. } => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionRecord {
+    /// Microseconds since the start of the recording
+    pub offset: u64,
+    pub message: SessionMessage,
+}
+
+/// Writer of a session file: a header with the start time of the recording, then
+/// length-prefixed records in little endian
+pub struct SessionWriter<W: Write> {
+    writer: BufWriter<W>,
+}
+
+impl<W: Write> SessionWriter<W> {
+    /// `started_at` is the wall time of the start, in milliseconds since the Unix epoch
+    pub fn new(writer: W, started_at: i64) -> io::Result<Self> {
+        let mut writer = BufWriter::new(writer);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&started_at.to_le_bytes())?;
+        Ok(Self { writer })
+    }
+
+    pub fn write(&mut self, record: &SessionRecord) -> io::Result<()> {
+        self.writer.write_all(&record.offset.to_le_bytes())?;
+        match &record.message {
+            SessionMessage::Zmq { frames } => {
+                self.writer.write_all(&[ZMQ_RECORD])?;
+                self.write_len(frames.len())?;
+                for frame in frames {
+                    self.write_bytes(frame)?;
+                }
+            }
+            SessionMessage::Nats { subject, payload } => {
+                self.writer.write_all(&[NATS_RECORD])?;
+                self.write_bytes(subject.as_bytes())?;
+                self.write_bytes(payload)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_len(&mut self, len: usize) -> io::Result<()> {
+        let len = u32::try_from(len).map_err(|_| io::Error::other("record part too large"))?;
+        self.writer.write_all(&len.to_le_bytes())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.write_len(bytes.len())?;
+        self.writer.write_all(bytes)
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        self.writer.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+/// Position of a record in a session file
+#[derive(Debug, Clone, Copy)]
+pub struct IndexEntry {
+    pub offset: u64,
+    pub position: u64,
+    pub dictionary: bool,
+}
+
+pub struct SessionReader<R: Read + Seek> {
+    reader: BufReader<R>,
+    pub started_at: i64,
+}
+
+impl<R: Read + Seek> SessionReader<R> {
+    pub fn new(reader: R) -> SessionResult<Self> {
+        let mut reader = BufReader::new(reader);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if &magic != MAGIC || version[0] != VERSION {
+            return Err(SessionError::InvalidFile(
+                "not an Argus session file of a supported version".to_string(),
+            ));
+        }
+
+        let mut started_at = [0u8; 8];
+        reader.read_exact(&mut started_at)?;
+        Ok(Self {
+            reader,
+            started_at: i64::from_le_bytes(started_at),
+        })
+    }
+
+    /// Next record, or `None` at the end of the file. A record cut short, as left
+    /// by an interrupted recording, also ends the session.
+    pub fn next(&mut self) -> SessionResult<Option<SessionRecord>> {
+        match self.read_record() {
+            Ok(record) => Ok(Some(record)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn read_record(&mut self) -> io::Result<SessionRecord> {
+        let offset = self.read_u64()?;
+        let mut kind = [0u8; 1];
+        self.reader.read_exact(&mut kind)?;
+
+        let message = match kind[0] {
+            ZMQ_RECORD => {
+                let count = self.read_u32()?;
+                let frames = (0..count)
+                    .map(|_| self.read_bytes())
+                    .collect::<io::Result<_>>()?;
+                SessionMessage::Zmq { frames }
+            }
+            NATS_RECORD => {
+                let subject = String::from_utf8(self.read_bytes()?)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                let payload = self.read_bytes()?;
+                SessionMessage::Nats { subject, payload }
+            }
+            kind => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown record kind {}", kind),
+                ))
+            }
+        };
+
+        Ok(SessionRecord { offset, message })
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut bytes = [0u8; 4];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let mut bytes = [0u8; 8];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn read_bytes(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.read_u32()? as usize;
+        let mut bytes = vec![0u8; len];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn position(&mut self) -> SessionResult<u64> {
+        Ok(self.reader.stream_position()?)
+    }
+
+    pub fn seek(&mut self, position: u64) -> SessionResult<()> {
+        self.reader.seek(SeekFrom::Start(position))?;
+        Ok(())
+    }
+
+    /// Scan the remaining records for their offsets and positions.
+    ///
+    /// Messages of both feeds are recorded as they come, so offsets may step back
+    /// slightly; indexed offsets never do, which keeps them sorted for seeking.
+    pub fn index(&mut self) -> SessionResult<Vec<IndexEntry>> {
+        let mut index: Vec<IndexEntry> = Vec::new();
+        loop {
+            let position = self.position()?;
+            let Some(record) = self.next()? else {
+                break;
+            };
+            let previous = index.last().map_or(0, |entry| entry.offset);
+            index.push(IndexEntry {
+                offset: record.offset.max(previous),
+                position,
+                dictionary: record.message.is_dictionary(),
+            });
+        }
+        Ok(index)
+    }
+}
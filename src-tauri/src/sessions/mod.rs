@@ -0,0 +1,11 @@
+pub mod commands;
+pub mod errors;
+pub mod state;
+
+mod file;
+mod player;
+mod recorder;
+
+pub use file::SessionMessage;
+pub use player::{ReplayState, ReplayStatus, REPLAY_STATUS_EVENT};
+pub use recorder::{RecordingStatus, SessionRecorder};
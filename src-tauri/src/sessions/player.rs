@@ -0,0 +1,317 @@
+use super::errors::{SessionError, SessionResult};
+use super::file::{IndexEntry, SessionReader};
+use super::state::ReplayBus;
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+pub const REPLAY_STATUS_EVENT: &str = "replay-status";
+
+/// Interval of the status events sent while playing
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayState {
+    Playing,
+    Paused,
+    Ended,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayStatus {
+    pub path: String,
+    /// Wall time the session was recorded at, in milliseconds since the Unix epoch
+    pub started_at: i64,
+    pub state: ReplayState,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub speed: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ReplayControl {
+    Play,
+    Pause,
+    /// Position to jump to, in milliseconds since the start of the session
+    Seek(u64),
+    Speed(f64),
+}
+
+pub fn check_speed(speed: f64) -> SessionResult<f64> {
+    if speed.is_finite() && speed > 0.0 {
+        Ok(speed)
+    } else {
+        Err(SessionError::InvalidSpeed(speed))
+    }
+}
+
+/// A session file being replayed onto a bus, paused until told to play
+pub struct ReplayHandle {
+    controls: mpsc::UnboundedSender<(ReplayControl, oneshot::Sender<ReplayStatus>)>,
+    status: watch::Receiver<ReplayStatus>,
+    task: JoinHandle<()>,
+}
+
+impl ReplayHandle {
+    /// Index the session file and start its player
+    pub async fn open(
+        path: String,
+        bus: ReplayBus,
+        speed: f64,
+        app_handle: AppHandle,
+    ) -> SessionResult<Self> {
+        let speed = check_speed(speed)?;
+
+        let file_path = path.clone();
+        let (reader, index) = tokio::task::spawn_blocking(move || {
+            let mut reader = SessionReader::new(File::open(&file_path)?)?;
+            let index = reader.index()?;
+            SessionResult::Ok((reader, index))
+        })
+        .await
+        .map_err(|e| SessionError::TaskError(e.to_string()))??;
+        info!("Opened session {} with {} messages", path, index.len());
+
+        let (status_tx, status) = watch::channel(ReplayStatus {
+            path,
+            started_at: reader.started_at,
+            state: ReplayState::Paused,
+            position_ms: 0,
+            duration_ms: index.last().map_or(0, |entry| entry.offset / 1000),
+            speed,
+        });
+        let (controls, controls_rx) = mpsc::unbounded_channel();
+
+        let player = Player {
+            reader,
+            // Left at the end of the file by the indexing
+            reader_at: None,
+            index,
+            bus,
+            status: status_tx,
+            app_handle,
+            next: 0,
+            position: 0,
+            anchor: Instant::now(),
+            speed,
+            state: ReplayState::Paused,
+        };
+        let task = tokio::spawn(player.run(controls_rx));
+
+        Ok(Self {
+            controls,
+            status,
+            task,
+        })
+    }
+
+    /// Apply a control, returning the status right after it
+    pub async fn control(&self, control: ReplayControl) -> SessionResult<ReplayStatus> {
+        if let ReplayControl::Speed(speed) = control {
+            check_speed(speed)?;
+        }
+        let (reply, status) = oneshot::channel();
+        self.controls
+            .send((control, reply))
+            .map_err(|_| SessionError::TaskError("replay stopped".to_string()))?;
+        status
+            .await
+            .map_err(|_| SessionError::TaskError("replay stopped".to_string()))
+    }
+
+    pub fn status(&self) -> ReplayStatus {
+        self.status.borrow().clone()
+    }
+
+    pub async fn close(self) {
+        drop(self.controls);
+        if let Err(e) = self.task.await {
+            error!("Replay task failed: {}", e);
+        }
+    }
+}
+
+struct Player {
+    reader: SessionReader<File>,
+    /// Index entry the reader is positioned at, `None` when unknown
+    reader_at: Option<usize>,
+    index: Vec<IndexEntry>,
+    bus: ReplayBus,
+    status: watch::Sender<ReplayStatus>,
+    app_handle: AppHandle,
+    /// Index entry of the next message to send
+    next: usize,
+    /// Position in microseconds when paused, or at `anchor` when playing
+    position: u64,
+    anchor: Instant,
+    speed: f64,
+    state: ReplayState,
+}
+
+impl Player {
+    async fn run(
+        mut self,
+        mut controls: mpsc::UnboundedReceiver<(ReplayControl, oneshot::Sender<ReplayStatus>)>,
+    ) {
+        let mut progress = tokio::time::interval(PROGRESS_INTERVAL);
+        loop {
+            let due = match self.state {
+                ReplayState::Playing => self.index.get(self.next).map(|entry| self.due(entry)),
+                _ => None,
+            };
+
+            tokio::select! {
+                control = controls.recv() => {
+                    let Some((control, reply)) = control else {
+                        break;
+                    };
+                    self.apply(control);
+                    let _ = reply.send(self.publish_status());
+                }
+                _ = tokio::time::sleep_until(due.unwrap_or_else(Instant::now)), if due.is_some() => {
+                    if let Err(e) = self.send_next() {
+                        error!("Failed to replay session: {}", e);
+                        self.end();
+                    }
+                }
+                _ = progress.tick(), if self.state == ReplayState::Playing => {
+                    self.publish_status();
+                }
+            }
+        }
+        info!("Replay closed");
+    }
+
+    /// Instant a message is due at, given the current speed
+    fn due(&self, entry: &IndexEntry) -> Instant {
+        let ahead = entry.offset.saturating_sub(self.position) as f64 / self.speed;
+        self.anchor + Duration::from_micros(ahead as u64)
+    }
+
+    /// Current position in microseconds
+    fn current(&self) -> u64 {
+        match self.state {
+            ReplayState::Playing => {
+                let elapsed = self.anchor.elapsed().as_micros() as f64 * self.speed;
+                let current = self.position + elapsed as u64;
+                // Never ahead of a message not sent yet
+                match self.index.get(self.next) {
+                    Some(entry) => current.min(entry.offset.max(self.position)),
+                    None => current.min(self.duration()),
+                }
+            }
+            _ => self.position,
+        }
+    }
+
+    fn duration(&self) -> u64 {
+        self.index.last().map_or(0, |entry| entry.offset)
+    }
+
+    fn apply(&mut self, control: ReplayControl) {
+        match control {
+            ReplayControl::Play => {
+                if self.state == ReplayState::Ended {
+                    self.seek(0);
+                }
+                self.position = self.current();
+                self.anchor = Instant::now();
+                if self.next < self.index.len() {
+                    self.state = ReplayState::Playing;
+                }
+            }
+            ReplayControl::Pause => {
+                if self.state == ReplayState::Playing {
+                    self.position = self.current();
+                    self.state = ReplayState::Paused;
+                }
+            }
+            ReplayControl::Seek(position_ms) => {
+                self.seek(position_ms.saturating_mul(1000));
+                if self.state == ReplayState::Ended {
+                    self.state = ReplayState::Paused;
+                }
+            }
+            ReplayControl::Speed(speed) => {
+                self.position = self.current();
+                self.anchor = Instant::now();
+                self.speed = speed;
+            }
+        }
+    }
+
+    /// Move to a position, resending the last id dictionary before it so the
+    /// binary messages that follow can still be decoded
+    fn seek(&mut self, target: u64) {
+        let target = target.min(self.duration());
+        self.next = self.index.partition_point(|entry| entry.offset < target);
+        self.position = target;
+        self.anchor = Instant::now();
+
+        if let Some(dictionary) = self.index[..self.next]
+            .iter()
+            .rposition(|entry| entry.dictionary)
+        {
+            if let Err(e) = self.send(dictionary) {
+                error!("Failed to resend the id dictionary: {}", e);
+            }
+        }
+    }
+
+    fn send_next(&mut self) -> SessionResult<()> {
+        let entry = self.index[self.next];
+        self.send(self.next)?;
+
+        self.next += 1;
+        // Keep the position in step with the messages sent, so a speed change or
+        // a pause never replays or skips any of them
+        self.anchor = self.due(&entry).max(self.anchor);
+        self.position = entry.offset.max(self.position);
+        if self.next == self.index.len() {
+            self.end();
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, index: usize) -> SessionResult<()> {
+        if self.reader_at != Some(index) {
+            self.reader.seek(self.index[index].position)?;
+        }
+        let record = self.reader.next()?.ok_or_else(|| {
+            SessionError::InvalidFile("session file changed while replayed".to_string())
+        })?;
+        self.reader_at = Some(index + 1);
+
+        // Sending fails only without any consumer, which is fine
+        let _ = self.bus.send(Arc::new(record.message));
+        Ok(())
+    }
+
+    fn end(&mut self) {
+        self.position = self.duration();
+        self.state = ReplayState::Ended;
+        self.publish_status();
+        info!("Replay ended");
+    }
+
+    fn publish_status(&self) -> ReplayStatus {
+        let mut status = self.status.borrow().clone();
+        status.state = self.state;
+        status.position_ms = self.current() / 1000;
+        status.speed = self.speed;
+        self.status.send_replace(status.clone());
+
+        if let Err(e) = self.app_handle.emit(REPLAY_STATUS_EVENT, &status) {
+            error!("Failed to emit replay status: {}", e);
+        }
+        status
+    }
+}
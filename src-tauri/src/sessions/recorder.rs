@@ -0,0 +1,172 @@
+use super::errors::{SessionError, SessionResult};
+use super::file::{SessionMessage, SessionRecord, SessionWriter};
+use crate::errors::{SubscriptionError, SubscriptionResult};
+use crate::powsybl::telemetry_codec::TelemetryDictionary;
+
+use futures::stream::StreamExt;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// NATS subjects captured by a recording: the substation telemetry and the
+/// simulation clock and stop messages read by `connect_broker`
+const NATS_SUBJECTS: [&str; 3] = ["GameMaster.>", "time", "stop"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingStatus {
+    pub path: String,
+    /// Milliseconds since the Unix epoch
+    pub started_at: i64,
+    pub duration_ms: u64,
+    pub messages: u64,
+}
+
+/// Sending half of a recording, shared by the NATS capture and the ZMQ subscription
+#[derive(Clone)]
+pub struct SessionRecorder {
+    sender: mpsc::UnboundedSender<SessionRecord>,
+    started: Instant,
+    messages: Arc<AtomicU64>,
+}
+
+impl SessionRecorder {
+    pub fn record(&self, message: SessionMessage) -> SessionResult<()> {
+        let record = SessionRecord {
+            offset: self.started.elapsed().as_micros() as u64,
+            message,
+        };
+        self.sender
+            .send(record)
+            .map_err(|_| SessionError::TaskError("session writer stopped".to_string()))?;
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Record the frames of a ZMQ message, before any decoding
+    pub fn record_zmq(&self, frames: &[&[u8]]) -> SubscriptionResult<()> {
+        self.record(SessionMessage::Zmq {
+            frames: frames.iter().map(|frame| frame.to_vec()).collect(),
+        })
+        .map_err(|e| SubscriptionError::ChannelSendError(e.to_string()))
+    }
+}
+
+/// A session being recorded to a file
+pub struct Recording {
+    path: String,
+    started_at: i64,
+    recorder: SessionRecorder,
+    nats_task: JoinHandle<()>,
+    nats_shutdown: broadcast::Sender<()>,
+    writer: JoinHandle<SessionResult<()>>,
+}
+
+impl Recording {
+    /// Create the session file and start capturing the NATS messages; ZMQ
+    /// messages are fed through [`Recording::recorder`]. The id dictionary already
+    /// received is written first, as the publisher may not send it again for a while.
+    pub async fn start(
+        path: String,
+        client: &async_nats::Client,
+        dictionary: &TelemetryDictionary,
+    ) -> SessionResult<Self> {
+        let started_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+        let mut writer = SessionWriter::new(File::create(&path)?, started_at)?;
+
+        let mut subscriptions = Vec::new();
+        for subject in NATS_SUBJECTS {
+            subscriptions.push(client.subscribe(subject).await?);
+        }
+
+        let (sender, mut receiver) = mpsc::unbounded_channel::<SessionRecord>();
+        let recorder = SessionRecorder {
+            sender,
+            started: Instant::now(),
+            messages: Arc::new(AtomicU64::new(0)),
+        };
+        if let Some(frames) = dictionary.to_frames() {
+            recorder.record(SessionMessage::Zmq { frames })?;
+        }
+
+        // Written off the runtime, until every recorder is dropped
+        let writer = tokio::task::spawn_blocking(move || {
+            while let Some(record) = receiver.blocking_recv() {
+                writer.write(&record)?;
+            }
+            writer.finish()?;
+            Ok(())
+        });
+
+        let (nats_shutdown, mut shutdown_rx) = broadcast::channel::<()>(1);
+        let nats_recorder = recorder.clone();
+        let nats_task = tokio::spawn(async move {
+            let mut messages = futures::stream::select_all(subscriptions);
+            loop {
+                tokio::select! {
+                    Some(message) = messages.next() => {
+                        let message = SessionMessage::Nats {
+                            subject: message.subject.to_string(),
+                            payload: message.payload.to_vec(),
+                        };
+                        if let Err(e) = nats_recorder.record(message) {
+                            warn!("Failed to record NATS message: {}", e);
+                            break;
+                        }
+                    }
+                    _ = shutdown_rx.recv() => break,
+                }
+            }
+            debug!("NATS capture finished");
+        });
+
+        info!("Recording session to {}", path);
+        Ok(Self {
+            path,
+            started_at,
+            recorder,
+            nats_task,
+            nats_shutdown,
+            writer,
+        })
+    }
+
+    pub fn recorder(&self) -> SessionRecorder {
+        self.recorder.clone()
+    }
+
+    pub fn status(&self) -> RecordingStatus {
+        RecordingStatus {
+            path: self.path.clone(),
+            started_at: self.started_at,
+            duration_ms: self.recorder.started.elapsed().as_millis() as u64,
+            messages: self.recorder.messages.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Stop the NATS capture and wait for the file to be complete. The ZMQ
+    /// subscription must be stopped first, as it holds a recorder too.
+    pub async fn stop(self) -> SessionResult<RecordingStatus> {
+        let status = self.status();
+
+        let _ = self.nats_shutdown.send(());
+        if let Err(e) = self.nats_task.await {
+            warn!("NATS capture failed: {}", e);
+        }
+
+        drop(self.recorder);
+        self.writer
+            .await
+            .map_err(|e| SessionError::TaskError(e.to_string()))??;
+
+        info!("Recorded {} messages to {}", status.messages, status.path);
+        Ok(status)
+    }
+}
@@ -0,0 +1,117 @@
+use super::file::SessionMessage;
+use super::player::{ReplayHandle, ReplayStatus};
+use super::recorder::{Recording, RecordingStatus};
+use crate::powsybl::telemetry_codec::TelemetryDictionary;
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::{broadcast, watch};
+
+/// Messages of the session being replayed, sent in place of the live ones
+pub type ReplayBus = broadcast::Sender<Arc<SessionMessage>>;
+
+/// Source of the telemetry read by the broker connections and the diagram
+/// subscriptions: the live NATS and ZMQ feeds, or the bus of a replayed session
+pub struct TelemetryFeed {
+    replay: watch::Sender<Option<ReplayBus>>,
+    live_dictionary: TelemetryDictionary,
+    replay_dictionary: TelemetryDictionary,
+}
+
+impl Default for TelemetryFeed {
+    fn default() -> Self {
+        Self {
+            replay: watch::Sender::new(None),
+            live_dictionary: TelemetryDictionary::default(),
+            replay_dictionary: TelemetryDictionary::default(),
+        }
+    }
+}
+
+impl TelemetryFeed {
+    pub fn set_replay(&self, bus: Option<ReplayBus>) {
+        // The ids of another session are announced again by its own dictionary
+        self.replay_dictionary.clear();
+        self.replay.send_replace(bus);
+    }
+
+    /// Id dictionary shared by the decoders of the live feed, or of the replay
+    pub fn dictionary(&self, replay: bool) -> TelemetryDictionary {
+        if replay {
+            self.replay_dictionary.clone()
+        } else {
+            self.live_dictionary.clone()
+        }
+    }
+
+    pub fn watch(&self) -> FeedWatcher {
+        FeedWatcher {
+            replay: self.replay.subscribe(),
+        }
+    }
+}
+
+/// Follows the telemetry feed on behalf of one consumer
+pub struct FeedWatcher {
+    replay: watch::Receiver<Option<ReplayBus>>,
+}
+
+impl FeedWatcher {
+    /// Receiver of the replayed messages, or `None` when the live feeds are in use
+    pub fn replay(&mut self) -> Option<broadcast::Receiver<Arc<SessionMessage>>> {
+        self.replay
+            .borrow_and_update()
+            .as_ref()
+            .map(|bus| bus.subscribe())
+    }
+
+    /// Wait until a replay starts or ends
+    pub async fn changed(&mut self) {
+        if self.replay.changed().await.is_err() {
+            // The feed lives as long as the application
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Next replayed message, waiting forever when no session is replayed
+pub async fn next_replayed(
+    replay: &mut Option<broadcast::Receiver<Arc<SessionMessage>>>,
+) -> Arc<SessionMessage> {
+    let Some(receiver) = replay else {
+        return std::future::pending().await;
+    };
+    loop {
+        match receiver.recv().await {
+            Ok(message) => return message,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                log::warn!("{} replayed messages skipped by a slow consumer", skipped);
+            }
+            // The feed switches back to live right after
+            Err(broadcast::error::RecvError::Closed) => return std::future::pending().await,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SessionStateInner {
+    pub recording: Option<Recording>,
+    pub replay: Option<ReplayHandle>,
+}
+
+pub type SessionState = tokio::sync::Mutex<SessionStateInner>;
+
+impl SessionStateInner {
+    pub fn status(&self) -> SessionStatus {
+        SessionStatus {
+            recording: self.recording.as_ref().map(Recording::status),
+            replay: self.replay.as_ref().map(ReplayHandle::status),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStatus {
+    pub recording: Option<RecordingStatus>,
+    pub replay: Option<ReplayStatus>,
+}
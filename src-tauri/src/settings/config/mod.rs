@@ -1,11 +1,24 @@
 use std::{fs, path::Path};
 
+mod schema;
+pub mod validation;
+mod watcher;
+
+pub use schema::{
+    ArgusConfig, BrokerEndpoint, InputFiles, ThemeColor, ThemeMode, UiDefaults, ZmqEndpoints,
+};
+pub use validation::ConfigProblem;
+pub use watcher::{ConfigReload, ConfigWatcher, ConfigWatcherState, CONFIG_RELOAD_EVENT};
+
 use super::{
     errors::{SettingResult, SettingsError},
     get_setting, save_setting,
+    zmq::{set_zmq_subscription, set_zmq_url},
 };
 use crate::{
+    broker::state::BrokerState,
     database::DatabaseState,
+    powsybl::quality::QualityMonitor,
     shared::{
         entities::{
             dynawo::GameMasterOutput,
@@ -15,72 +28,146 @@ use crate::{
     },
     state::AppState,
 };
+use async_nats::ConnectError;
 use serde::Serialize;
-use tauri::State;
-use toml::Value;
+use sqlx::{Pool, Sqlite};
+use tauri::{AppHandle, Manager, State};
 
 #[derive(Debug, Serialize)]
 pub struct ConfigResponse {
     pub status: String,
 }
 
-#[tauri::command(rename_all = "snake_case")]
-pub async fn load_config_file(
-    state: State<'_, DatabaseState>,
-    config_path: String,
-) -> SettingResult<ConfigResponse> {
+/// Response of a loaded configuration file, with the interface defaults it sets
+#[derive(Debug, Serialize)]
+pub struct LoadedConfig {
+    pub status: String,
+    pub ui: UiDefaults,
+}
+
+/// Describe the problems of a configuration file, one per line
+pub fn describe_problems(config_path: &str, problems: &[ConfigProblem]) -> String {
+    let mut description = format!("Invalid configuration file {}", config_path);
+    for problem in problems {
+        description.push_str("\n  ");
+        description.push_str(&problem.to_string());
+    }
+    description
+}
+
+/// Read a configuration file and resolve its paths, or list every problem found
+/// in it. Files that cannot be read are errors.
+pub fn read_config(config_path: &str) -> SettingResult<Result<ArgusConfig, Vec<ConfigProblem>>> {
     if config_path.is_empty() {
         return Err(SettingsError::InvalidPath("Config path is empty".into()));
     }
 
-    let config_path = Path::new(&config_path);
-    if !config_path.exists() {
-        return Err(SettingsError::FileNotFound(
-            config_path.to_string_lossy().to_string(),
-        ));
+    let path = Path::new(config_path);
+    if !path.exists() {
+        return Err(SettingsError::FileNotFound(config_path.to_string()));
     }
+    let parent_dir = path.parent().ok_or_else(|| {
+        SettingsError::InvalidPath("Cannot get parent directory of config file".into())
+    })?;
 
-    let path = Path::new(&config_path);
-    if !path.exists() {
-        return Err(SettingsError::FileNotFound(
-            config_path.to_string_lossy().to_string(),
-        ));
+    let content = fs::read_to_string(path).map_err(|e| SettingsError::FileRead(e.to_string()))?;
+    let mut config = match validation::parse(&content) {
+        Ok(config) => config,
+        Err(problems) => return Ok(Err(problems)),
+    };
+    config.resolve_paths(parent_dir);
+
+    let problems = validation::validate(&config, &content);
+    Ok(if problems.is_empty() {
+        Ok(config)
+    } else {
+        Err(problems)
+    })
+}
+
+// Store the resolved configuration, with the keys read by the loaders
+async fn save_config(
+    pool: &Pool<Sqlite>,
+    config_path: &str,
+    config: &ArgusConfig,
+) -> SettingResult<()> {
+    let mut setting =
+        serde_json::to_value(config).map_err(|e| SettingsError::Deserialization(e.to_string()))?;
+    setting["config_file"] = serde_json::json!(config_path);
+    setting["dynawo_game_master_outputs_file"] =
+        serde_json::json!(config.input_files.dynawo_game_master_outputs_file.get_ref());
+
+    save_setting(pool, "config", &setting).await?;
+    if let Some(iidm_file) = &config.input_files.iidm_file {
+        let iidm = serde_json::json!({
+           "iidm_path": iidm_file.get_ref()
+        });
+        save_setting(pool, "iidm", &iidm).await?;
     }
+    Ok(())
+}
 
-    let toml_content =
-        fs::read_to_string(config_path).map_err(|e| SettingsError::FileRead(e.to_string()))?;
+// Connect the telemetry client to the configured publisher and topic prefix
+async fn apply_zmq(app_handle: &AppHandle, zmq: &ZmqEndpoints) -> SettingResult<()> {
+    set_zmq_url(app_handle.state(), zmq.telemetry_url.get_ref().clone()).await?;
+    if !zmq.subscription.is_empty() {
+        set_zmq_subscription(app_handle.state(), zmq.subscription.clone()).await?;
+    }
+    Ok(())
+}
 
-    let parsed_toml: Value =
-        toml::from_str(&toml_content).map_err(|e| SettingsError::Deserialization(e.to_string()))?;
+// Connect the broker client to the configured NATS server
+async fn apply_broker(
+    app_handle: &AppHandle,
+    broker: &BrokerEndpoint,
+) -> Result<(), ConnectError> {
+    let state = app_handle.state::<BrokerState>();
+    let mut state = state.lock().await;
+    state.reconnect(broker.url.get_ref()).await
+}
 
-    let outputs_file_rel_path = parsed_toml
-        .get("input_files")
-        .and_then(|input_files| input_files.get("dynawo_game_master_outputs_file"))
-        .and_then(|file_path| file_path.as_str())
-        .ok_or_else(|| {
-            SettingsError::InvalidConfig("Missing dynawo_game_master_outputs_file in config".into())
-        })?;
+/// Check a configuration file without loading it, returning every problem found
+#[tauri::command(rename_all = "snake_case")]
+pub async fn validate_config_file(config_path: String) -> SettingResult<Vec<ConfigProblem>> {
+    Ok(read_config(&config_path)?.err().unwrap_or_default())
+}
 
-    let parent_dir = config_path.parent().ok_or_else(|| {
-        SettingsError::InvalidPath("Cannot get parent directory of config file".into())
+/// Load a configuration file, connect to its endpoints and watch it, with the
+/// files it references, to apply their later changes
+#[tauri::command(rename_all = "snake_case")]
+pub async fn load_config_file(
+    app_handle: AppHandle,
+    state: State<'_, DatabaseState>,
+    watcher: State<'_, ConfigWatcherState>,
+    config_path: String,
+) -> SettingResult<LoadedConfig> {
+    let config = read_config(&config_path)?.map_err(|problems| {
+        SettingsError::InvalidConfig(describe_problems(&config_path, &problems))
     })?;
-    let outputs_file_abs_path = parent_dir.join(outputs_file_rel_path);
 
-    if !outputs_file_abs_path.exists() {
-        return Err(SettingsError::FileNotFound(
-            outputs_file_abs_path.to_string_lossy().to_string(),
-        ));
+    {
+        let state = state.lock().await;
+        save_config(&state.pool, &config_path, &config).await?;
     }
 
-    let config = serde_json::json!({
-       "dynawo_game_master_outputs_file": outputs_file_abs_path
-    });
+    // Unreachable endpoints do not prevent the rest of the file from loading
+    if let Err(e) = apply_zmq(&app_handle, &config.zmq).await {
+        warn!("ZMQ endpoint of {} not applied: {}", config_path, e);
+    }
+    if let Err(e) = apply_broker(&app_handle, &config.broker).await {
+        warn!("Broker endpoint of {} not applied: {}", config_path, e);
+    }
 
-    let state = state.lock().await;
-    save_setting(&state.pool, "config", &config).await?;
+    // Later changes of the file are applied as they are saved
+    let ui = config.ui.clone();
+    let mut watcher = watcher.lock().await;
+    if let Err(e) = watcher.watch(app_handle, Path::new(&config_path), config) {
+        warn!("Configuration file {} not watched: {}", config_path, e);
+    }
 
-    Ok(ConfigResponse {
+    Ok(LoadedConfig {
         status: "configured".into(),
+        ui,
     })
 }
 
@@ -135,6 +222,7 @@ use std::time::Instant;
 pub async fn load_game_master_outputs_in_db(
     db_state: State<'_, DatabaseState>,
     app_state: State<'_, AppState>,
+    quality: State<'_, QualityMonitor>,
 ) -> SettingResult<ConfigResponse> {
     let start = Instant::now();
     let state = db_state.lock().await;
@@ -155,8 +243,22 @@ pub async fn load_game_master_outputs_in_db(
 
         // Mesurer le temps de désérialisation
         let deser_start = Instant::now();
-        let game_master_outputs: Vec<GameMasterOutput> = serde_json::from_str(&content)
+        let mut game_master_outputs: Vec<GameMasterOutput> = serde_json::from_str(&content)
             .map_err(|e| SettingsError::Deserialization(e.to_string()))?;
+
+        // Fichiers de correspondance complémentaires, ajoutés à la suite
+        let mapping_files = config["input_files"]["mapping_files"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|path| path.as_str());
+        for mapping_file in mapping_files {
+            let content = fs::read_to_string(mapping_file)
+                .map_err(|e| SettingsError::FileRead(format!("{}: {}", mapping_file, e)))?;
+            let outputs: Vec<GameMasterOutput> = serde_json::from_str(&content)
+                .map_err(|e| SettingsError::Deserialization(format!("{}: {}", mapping_file, e)))?;
+            game_master_outputs.extend(outputs);
+        }
         let count = game_master_outputs.len();
         info!(
             "Désérialisation de {} sorties Game Master terminée en {:?}",
@@ -177,8 +279,15 @@ pub async fn load_game_master_outputs_in_db(
         // Si vous utilisez l'implémentation que j'ai fournie, elle affichera ses propres logs
         // game_master_outputs.insert(&state.pool).await?;
 
-        let mut state = app_state.try_write().unwrap();
+        let mut state = app_state
+            .write()
+            .map_err(|e| SettingsError::StateLock(e.to_string()))?;
         println!("GAME MASTER OUTPUS LEN : {}", &game_master_outputs.len());
+        quality.set_outputs(
+            game_master_outputs
+                .iter()
+                .chain(state.powsybl.calculations.outputs()),
+        );
         state.settings.game_master_outputs = Some(game_master_outputs);
 
 
@@ -0,0 +1,131 @@
+use crate::powsybl::{alarms::LimitConfig, calculations::CalculationConfig};
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use toml::Spanned;
+
+/// Argus configuration file.
+///
+/// Only `input_files.dynawo_game_master_outputs_file` is required. Values that
+/// are checked after parsing keep their position in the file, so that every
+/// problem can be reported with its line and column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArgusConfig {
+    pub input_files: InputFiles,
+    #[serde(default)]
+    pub broker: BrokerEndpoint,
+    #[serde(default)]
+    pub zmq: ZmqEndpoints,
+    /// Alarm limits, in the `[[limits]]` tables
+    #[serde(default)]
+    pub limits: Vec<Spanned<LimitConfig>>,
+    /// Derived points, in the `[[calculations]]` tables
+    #[serde(default)]
+    pub calculations: Vec<Spanned<CalculationConfig>>,
+    #[serde(default)]
+    pub ui: UiDefaults,
+}
+
+/// Files read at startup; relative paths are resolved against the directory of
+/// the configuration file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputFiles {
+    pub dynawo_game_master_outputs_file: Spanned<PathBuf>,
+    #[serde(default)]
+    pub iidm_file: Option<Spanned<PathBuf>>,
+    /// Further game master outputs files, appended to the main one
+    #[serde(default)]
+    pub mapping_files: Vec<Spanned<PathBuf>>,
+}
+
+/// NATS server of the broker telemetry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrokerEndpoint {
+    pub url: Spanned<String>,
+}
+
+impl Default for BrokerEndpoint {
+    fn default() -> Self {
+        Self {
+            url: Spanned::new(0..0, "nats://localhost:4222".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ZmqEndpoints {
+    /// Publisher of the Dynawo telemetry
+    pub telemetry_url: Spanned<String>,
+    /// Topic prefix subscribed to by default
+    pub subscription: String,
+}
+
+impl Default for ZmqEndpoints {
+    fn default() -> Self {
+        Self {
+            telemetry_url: Spanned::new(0..0, "tcp://127.0.0.1:5556".to_string()),
+            subscription: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    #[default]
+    Light,
+    Dark,
+    System,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeColor {
+    #[default]
+    Default,
+    Blue,
+    Gruvbox,
+    Purple,
+    Nord,
+}
+
+/// Defaults of the interface, until changed in its settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiDefaults {
+    pub theme_mode: ThemeMode,
+    pub theme_color: ThemeColor,
+    /// Substation whose diagram is opened at startup
+    pub substation: Option<String>,
+}
+
+impl ArgusConfig {
+    /// Make the relative paths absolute, from the directory of the configuration file
+    pub fn resolve_paths(&mut self, base_dir: &Path) {
+        let input_files = &mut self.input_files;
+        let paths = std::iter::once(&mut input_files.dynawo_game_master_outputs_file)
+            .chain(input_files.iidm_file.as_mut())
+            .chain(input_files.mapping_files.iter_mut());
+        for path in paths {
+            if path.get_ref().is_relative() {
+                *path.get_mut() = base_dir.join(path.get_ref());
+            }
+        }
+    }
+
+    pub fn limits(&self) -> Vec<LimitConfig> {
+        self.limits
+            .iter()
+            .map(|limit| limit.get_ref().clone())
+            .collect()
+    }
+
+    pub fn calculations(&self) -> Vec<CalculationConfig> {
+        self.calculations
+            .iter()
+            .map(|calculation| calculation.get_ref().clone())
+            .collect()
+    }
+}
@@ -0,0 +1,363 @@
+use super::schema::{ArgusConfig, BrokerEndpoint, InputFiles, UiDefaults, ZmqEndpoints};
+use crate::powsybl::{
+    alarms::{LimitConfig, LimitScope},
+    calculations::CalculationConfig,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml::Spanned;
+
+const BROKER_SCHEMES: [&str; 4] = ["nats://", "tls://", "ws://", "wss://"];
+const ZMQ_SCHEMES: [&str; 3] = ["tcp://", "ipc://", "inproc://"];
+
+/// A problem found in the configuration file, at a line and column counted from 1
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigProblem {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+// Sections of the file, each deserialized on its own from the whole content so
+// that the type errors of every section are reported with their position
+#[derive(Deserialize)]
+struct InputFilesSection {
+    input_files: InputFiles,
+}
+
+#[derive(Deserialize)]
+struct BrokerSection {
+    #[serde(default)]
+    broker: BrokerEndpoint,
+}
+
+#[derive(Deserialize)]
+struct ZmqSection {
+    #[serde(default)]
+    zmq: ZmqEndpoints,
+}
+
+#[derive(Deserialize)]
+struct UiSection {
+    #[serde(default)]
+    ui: UiDefaults,
+}
+
+// Tables are kept as values, then deserialized one by one
+#[derive(Deserialize)]
+struct LimitsSection {
+    #[serde(default)]
+    limits: Vec<Spanned<toml::Value>>,
+}
+
+#[derive(Deserialize)]
+struct CalculationsSection {
+    #[serde(default)]
+    calculations: Vec<Spanned<toml::Value>>,
+}
+
+// Problems of a file, located from the byte spans of its values
+struct Problems<'a> {
+    content: &'a str,
+    problems: Vec<ConfigProblem>,
+}
+
+impl<'a> Problems<'a> {
+    fn new(content: &'a str) -> Self {
+        Self {
+            content,
+            problems: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, span: Range<usize>, message: String) {
+        let mut start = span.start.min(self.content.len());
+        while !self.content.is_char_boundary(start) {
+            start -= 1;
+        }
+        let before = &self.content[..start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        self.problems.push(ConfigProblem {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message,
+        });
+    }
+
+    fn error(&mut self, e: toml::de::Error) {
+        self.push(e.span().unwrap_or(0..0), e.message().to_string());
+    }
+
+    fn section<T: DeserializeOwned>(&mut self) -> Option<T> {
+        toml::from_str(self.content).map_err(|e| self.error(e)).ok()
+    }
+
+    // Deserialize every table of an array, reporting each invalid one at its position
+    fn tables<T: DeserializeOwned>(
+        &mut self,
+        name: &str,
+        tables: Vec<Spanned<toml::Value>>,
+    ) -> Option<Vec<Spanned<T>>> {
+        let mut valid = Some(Vec::with_capacity(tables.len()));
+        for (index, table) in tables.into_iter().enumerate() {
+            let span = table.span();
+            match table.into_inner().try_into::<T>() {
+                Ok(value) => {
+                    if let Some(valid) = valid.as_mut() {
+                        valid.push(Spanned::new(span, value));
+                    }
+                }
+                Err(e) => {
+                    self.push(
+                        span,
+                        format!("[[{}]] table {}: {}", name, index + 1, e.message()),
+                    );
+                    valid = None;
+                }
+            }
+        }
+        valid
+    }
+
+    fn file(&mut self, name: &str, path: &Spanned<PathBuf>) {
+        let path_ref: &Path = path.get_ref();
+        if !path_ref.is_file() {
+            self.push(
+                path.span(),
+                format!("{} not found: {}", name, path_ref.display()),
+            );
+        }
+    }
+
+    fn url(&mut self, name: &str, url: &Spanned<String>, schemes: &[&str]) {
+        let value = url.get_ref();
+        let valid = schemes
+            .iter()
+            .any(|scheme| value.len() > scheme.len() && value.starts_with(scheme));
+        if !valid {
+            self.push(
+                url.span(),
+                format!(
+                    "{} '{}' must start with {}",
+                    name,
+                    value,
+                    schemes.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+/// Parse a configuration file, reporting its syntax error or the type errors of
+/// every section
+pub fn parse(content: &str) -> Result<ArgusConfig, Vec<ConfigProblem>> {
+    let mut problems = Problems::new(content);
+
+    // A syntax error stops the parsing of the whole file
+    if let Err(e) = toml::from_str::<toml::Table>(content) {
+        problems.error(e);
+        return Err(problems.problems);
+    }
+
+    let input_files = problems
+        .section::<InputFilesSection>()
+        .map(|section| section.input_files);
+    let broker = problems
+        .section::<BrokerSection>()
+        .map(|section| section.broker);
+    let zmq = problems.section::<ZmqSection>().map(|section| section.zmq);
+    let limits = problems
+        .section::<LimitsSection>()
+        .and_then(|section| problems.tables::<LimitConfig>("limits", section.limits));
+    let calculations = problems
+        .section::<CalculationsSection>()
+        .and_then(|section| {
+            problems.tables::<CalculationConfig>("calculations", section.calculations)
+        });
+    let ui = problems.section::<UiSection>().map(|section| section.ui);
+
+    match (input_files, broker, zmq, limits, calculations, ui) {
+        (
+            Some(input_files),
+            Some(broker),
+            Some(zmq),
+            Some(limits),
+            Some(calculations),
+            Some(ui),
+        ) => Ok(ArgusConfig {
+            input_files,
+            broker,
+            zmq,
+            limits,
+            calculations,
+            ui,
+        }),
+        _ => {
+            let mut problems = problems.problems;
+            problems.sort_by_key(|problem| (problem.line, problem.column));
+            Err(problems)
+        }
+    }
+}
+
+/// Check every value of a parsed configuration, whose paths are resolved, and
+/// report all the problems found
+pub fn validate(config: &ArgusConfig, content: &str) -> Vec<ConfigProblem> {
+    let mut problems = Problems::new(content);
+
+    let input_files = &config.input_files;
+    problems.file(
+        "Game master outputs file",
+        &input_files.dynawo_game_master_outputs_file,
+    );
+    if let Some(iidm_file) = &input_files.iidm_file {
+        problems.file("IIDM file", iidm_file);
+    }
+    for mapping_file in &input_files.mapping_files {
+        problems.file("Mapping file", mapping_file);
+    }
+
+    problems.url("Broker URL", &config.broker.url, &BROKER_SCHEMES);
+    problems.url("ZMQ telemetry URL", &config.zmq.telemetry_url, &ZMQ_SCHEMES);
+
+    for limit in &config.limits {
+        let span = limit.span();
+        let limit = limit.get_ref();
+        let target = match &limit.scope {
+            LimitScope::Output(id) | LimitScope::Unit(id) => id,
+        };
+        if target.trim().is_empty() {
+            problems.push(span.clone(), "Limit with an empty scope".to_string());
+        }
+        match (limit.low, limit.high) {
+            (None, None) => problems.push(
+                span.clone(),
+                format!("Limit on '{}' without high nor low", target),
+            ),
+            (Some(low), Some(high)) if low >= high => problems.push(
+                span.clone(),
+                format!(
+                    "Limit on '{}': low {} is not below high {}",
+                    target, low, high
+                ),
+            ),
+            _ => {}
+        }
+        if !limit.hysteresis.is_finite() || limit.hysteresis < 0.0 {
+            problems.push(span, format!("Limit on '{}': negative hysteresis", target));
+        }
+    }
+
+    let mut ids = HashSet::new();
+    for calculation in &config.calculations {
+        let span = calculation.span();
+        let calculation = calculation.get_ref();
+        if calculation.id.trim().is_empty() {
+            problems.push(span.clone(), "Calculation with an empty id".to_string());
+        } else if !ids.insert(calculation.id.as_str()) {
+            problems.push(
+                span.clone(),
+                format!("Calculation '{}': duplicate id", calculation.id),
+            );
+        }
+        if let Err(e) = calculation.parse_expression() {
+            problems.push(span, format!("Calculation '{}': {}", calculation.id, e));
+        }
+    }
+
+    problems.problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn located(content: &str, span: Range<usize>) -> (usize, usize) {
+        let mut problems = Problems::new(content);
+        problems.push(span, String::new());
+        let problem = &problems.problems[0];
+        (problem.line, problem.column)
+    }
+
+    #[test]
+    fn push_counts_lines_and_columns_from_one() {
+        let content = "a = 1\nb = \"x\"\n";
+        assert_eq!(located(content, 0..1), (1, 1));
+        assert_eq!(located(content, 10..13), (2, 5));
+    }
+
+    #[test]
+    fn push_counts_columns_in_characters() {
+        let content = "a = 1\nété = 2\n";
+        let value = content.find('2').unwrap();
+        assert_eq!(located(content, value..value + 1), (2, 7));
+        // A span starting inside a character points at that character
+        assert_eq!(located(content, 7..8), (2, 1));
+    }
+
+    #[test]
+    fn push_clamps_spans_past_the_end() {
+        assert_eq!(located("a = 1\n", 100..101), (2, 1));
+    }
+
+    #[test]
+    fn parse_reads_a_valid_file() {
+        let config = parse(
+            r#"
+[input_files]
+dynawo_game_master_outputs_file = "outputs.json"
+
+[[limits]]
+scope = { unit = "kV" }
+high = 420.0
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.limits.len(), 1);
+        assert_eq!(config.zmq, ZmqEndpoints::default());
+    }
+
+    #[test]
+    fn parse_reports_a_syntax_error_alone() {
+        let problems = parse("[input_files\nbroker = 1\n").unwrap_err();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].line, 1);
+    }
+
+    #[test]
+    fn parse_reports_the_type_errors_of_every_section() {
+        let problems = parse(
+            r#"[broker]
+url = 4222
+
+[[limits]]
+scope = { unit = "kV" }
+
+[[limits]]
+scope = "kV"
+
+[ui]
+theme_mode = "pink"
+"#,
+        )
+        .unwrap_err();
+        let lines: Vec<usize> = problems.iter().map(|problem| problem.line).collect();
+        assert_eq!(lines, [1, 2, 7, 11], "{:?}", problems);
+        assert!(problems[0].message.contains("input_files"));
+        assert!(problems[2].message.starts_with("[[limits]] table 2"));
+    }
+}
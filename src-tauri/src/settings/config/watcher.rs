@@ -0,0 +1,420 @@
+use super::super::errors::SettingResult;
+use super::{
+    apply_broker, apply_zmq, load_game_master_outputs_in_db, read_config, save_config, ArgusConfig,
+    ConfigProblem, UiDefaults,
+};
+use crate::{
+    database::DatabaseState,
+    powsybl::{alarms::reload_alarm_limits, commands::load_calculations, restart_telemetry},
+};
+
+use log::{info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Event describing every reload of the configuration file
+pub const CONFIG_RELOAD_EVENT: &str = "config-reload";
+
+/// Reason given for the mappings, read by the next subscriptions only
+const NEW_SUBSCRIPTIONS: &str = "Applies to new subscriptions";
+
+/// Time given to editors to finish saving before the files are read again
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReloadStatus {
+    /// The files are valid and their changes were applied, at least in part
+    Applied,
+    /// The files were saved without any change
+    Unchanged,
+    /// The files are invalid; the previous configuration stays in use
+    Refused,
+}
+
+/// A change that needs a restart, only applies to new subscriptions or failed to apply
+#[derive(Debug, Clone, Serialize)]
+pub struct NotApplied {
+    pub section: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigReload {
+    pub config_path: String,
+    pub status: ReloadStatus,
+    /// Sections applied without a restart
+    pub applied: Vec<String>,
+    pub not_applied: Vec<NotApplied>,
+    /// Problems of the files, when refused
+    pub problems: Vec<ConfigProblem>,
+    pub error: Option<String>,
+    /// New interface defaults, when changed
+    pub ui: Option<UiDefaults>,
+    /// Sections that failed to apply, stored as they were before
+    #[serde(skip)]
+    failed: Vec<String>,
+}
+
+impl ConfigReload {
+    fn new(config_path: &str) -> Self {
+        Self {
+            config_path: config_path.to_string(),
+            status: ReloadStatus::Unchanged,
+            applied: Vec::new(),
+            not_applied: Vec::new(),
+            problems: Vec::new(),
+            error: None,
+            ui: None,
+            failed: Vec::new(),
+        }
+    }
+
+    fn refused(mut self, problems: Vec<ConfigProblem>, error: Option<String>) -> Self {
+        self.status = ReloadStatus::Refused;
+        self.problems = problems;
+        self.error = error;
+        self
+    }
+
+    fn applied(&mut self, section: &str) {
+        self.applied.push(section.to_string());
+    }
+
+    fn not_applied(&mut self, section: &str, reason: impl ToString) {
+        self.not_applied.push(NotApplied {
+            section: section.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    fn failed(&mut self, section: &str, error: impl ToString) {
+        self.failed.push(section.to_string());
+        self.not_applied(section, error);
+    }
+}
+
+/// Watch of the loaded configuration file and of the files it references,
+/// applying their changes as they are saved
+#[derive(Default)]
+pub struct ConfigWatcher {
+    task: Option<JoinHandle<()>>,
+}
+
+pub type ConfigWatcherState = tokio::sync::Mutex<ConfigWatcher>;
+
+impl ConfigWatcher {
+    /// Watch a loaded configuration, replacing the previous watch
+    pub fn watch(
+        &mut self,
+        app_handle: AppHandle,
+        config_path: &Path,
+        config: ArgusConfig,
+    ) -> SettingResult<()> {
+        self.stop();
+
+        // Events are sent from the thread of the watcher
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let watcher =
+            notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                Ok(event) if is_write(&event.kind) => {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Configuration watch error: {}", e),
+            })?;
+
+        let mut watched = WatchedConfig {
+            config_path: config_path.to_string_lossy().to_string(),
+            config,
+            watcher,
+            files: HashSet::new(),
+            directories: HashSet::new(),
+        };
+        watched.update_watches()?;
+        info!(
+            "Watching {} and {} referenced files",
+            watched.config_path,
+            watched.files.len() - 1
+        );
+
+        self.task = Some(tokio::spawn(run(app_handle, watched, receiver)));
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+fn is_write(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    )
+}
+
+// Paths as reported by the watcher, for the files that exist
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+// The loaded configuration and the watch of its files
+struct WatchedConfig {
+    config_path: String,
+    config: ArgusConfig,
+    watcher: RecommendedWatcher,
+    files: HashSet<PathBuf>,
+    /// Parent directories of the files, watched rather than the files so that
+    /// files replaced on save are still followed
+    directories: HashSet<PathBuf>,
+}
+
+impl WatchedConfig {
+    fn mapping_files(config: &ArgusConfig) -> HashSet<PathBuf> {
+        let input_files = &config.input_files;
+        std::iter::once(&input_files.dynawo_game_master_outputs_file)
+            .chain(&input_files.mapping_files)
+            .map(|path| canonical(path.get_ref()))
+            .collect()
+    }
+
+    fn iidm_file(config: &ArgusConfig) -> Option<PathBuf> {
+        config
+            .input_files
+            .iidm_file
+            .as_ref()
+            .map(|path| canonical(path.get_ref()))
+    }
+
+    // Watch the directories of the files of the current configuration
+    fn update_watches(&mut self) -> SettingResult<()> {
+        let mut files = Self::mapping_files(&self.config);
+        files.extend(Self::iidm_file(&self.config));
+        files.insert(canonical(Path::new(&self.config_path)));
+
+        let directories: HashSet<PathBuf> = files
+            .iter()
+            .filter_map(|file| file.parent().map(Path::to_path_buf))
+            .collect();
+
+        for directory in self.directories.difference(&directories) {
+            let _ = self.watcher.unwatch(directory);
+        }
+        for directory in directories.difference(&self.directories) {
+            self.watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        }
+
+        self.files = files;
+        self.directories = directories;
+        Ok(())
+    }
+
+    /// Read the files again and apply what changed since the last reload
+    async fn reload(&mut self, app_handle: &AppHandle, changed: &HashSet<PathBuf>) -> ConfigReload {
+        let reload = ConfigReload::new(&self.config_path);
+        let config = match read_config(&self.config_path) {
+            Ok(Ok(config)) => config,
+            Ok(Err(problems)) => return reload.refused(problems, None),
+            Err(e) => return reload.refused(Vec::new(), Some(e.to_string())),
+        };
+
+        // Loaders and commands read the stored configuration
+        let previous = std::mem::replace(&mut self.config, config);
+        if let Err(e) = self.save(app_handle).await {
+            self.config = previous;
+            return reload.refused(Vec::new(), Some(e.to_string()));
+        }
+
+        let reload = self.apply(app_handle, &previous, changed, reload).await;
+        // The stored configuration stays the one in use, so the sections that
+        // failed are applied again on the next reload
+        if !reload.failed.is_empty() {
+            self.restore(&previous, &reload.failed);
+            if let Err(e) = self.save(app_handle).await {
+                warn!("Failed to store the configuration in use: {}", e);
+            }
+        }
+        if let Err(e) = self.update_watches() {
+            warn!("Configuration files no longer watched: {}", e);
+        }
+        reload
+    }
+
+    async fn save(&self, app_handle: &AppHandle) -> SettingResult<()> {
+        let db_state = app_handle.state::<DatabaseState>();
+        let db_state = db_state.lock().await;
+        save_config(&db_state.pool, &self.config_path, &self.config).await
+    }
+
+    // Take back the previous value of the sections that failed to apply
+    fn restore(&mut self, previous: &ArgusConfig, sections: &[String]) {
+        let config = &mut self.config;
+        for section in sections {
+            match section.as_str() {
+                "mappings" => {
+                    config.input_files.dynawo_game_master_outputs_file =
+                        previous.input_files.dynawo_game_master_outputs_file.clone();
+                    config.input_files.mapping_files = previous.input_files.mapping_files.clone();
+                }
+                "limits" => config.limits = previous.limits.clone(),
+                "calculations" => config.calculations = previous.calculations.clone(),
+                "zmq" => config.zmq = previous.zmq.clone(),
+                "broker" => config.broker = previous.broker.clone(),
+                _ => {}
+            }
+        }
+    }
+
+    async fn apply(
+        &self,
+        app_handle: &AppHandle,
+        previous: &ArgusConfig,
+        changed: &HashSet<PathBuf>,
+        mut reload: ConfigReload,
+    ) -> ConfigReload {
+        let config = &self.config;
+
+        let mut mappings_loaded = false;
+        let mapping_files = Self::mapping_files(config);
+        if mapping_files != Self::mapping_files(previous) || !mapping_files.is_disjoint(changed) {
+            let loaded = load_game_master_outputs_in_db(
+                app_handle.state(),
+                app_handle.state(),
+                app_handle.state(),
+            )
+            .await;
+            match loaded {
+                // Running diagrams keep the outputs they were subscribed with
+                Ok(_) => {
+                    mappings_loaded = true;
+                    reload.not_applied("mappings", NEW_SUBSCRIPTIONS);
+                }
+                Err(e) => reload.failed("mappings", e),
+            }
+        }
+
+        let iidm_file = Self::iidm_file(config);
+        let iidm_changed = iidm_file
+            .as_ref()
+            .is_some_and(|file| changed.contains(file));
+        if iidm_file != Self::iidm_file(previous) || iidm_changed {
+            reload.not_applied("iidm_file", "The network is read again on its next upload");
+        }
+
+        // Limits of a unit class and calculation topics are resolved on the outputs
+        let limits_changed = config.limits != previous.limits;
+        if limits_changed || mappings_loaded {
+            // A running engine keeps its alarms; a stopped one reads them when started
+            match reload_alarm_limits(app_handle.clone(), config.limits()).await {
+                Ok(running) if running || limits_changed => reload.applied("limits"),
+                Ok(_) => {}
+                Err(e) => reload.failed("limits", e),
+            }
+        }
+
+        let calculations_changed = config.calculations != previous.calculations;
+        if calculations_changed || mappings_loaded {
+            let loaded = load_calculations(
+                app_handle.state(),
+                app_handle.state(),
+                app_handle.state(),
+                Some(self.config_path.clone()),
+                None,
+            )
+            .await;
+            match loaded {
+                Ok(_) if calculations_changed => reload.applied("calculations"),
+                Ok(_) => {}
+                Err(e) => reload.failed("calculations", e),
+            }
+        }
+
+        let zmq = &config.zmq;
+        if zmq.telemetry_url != previous.zmq.telemetry_url
+            || zmq.subscription != previous.zmq.subscription
+        {
+            // The running subscription connects again, keeping its consumers
+            let applied = match apply_zmq(app_handle, zmq).await {
+                Ok(()) => restart_telemetry(app_handle.clone(), &app_handle.state())
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match applied {
+                Ok(_) => reload.applied("zmq"),
+                Err(e) => reload.failed("zmq", e),
+            }
+        }
+
+        if config.broker.url != previous.broker.url {
+            // Running connections subscribe again on the new server
+            match apply_broker(app_handle, &config.broker).await {
+                Ok(()) => reload.applied("broker"),
+                Err(e) => reload.failed("broker", e),
+            }
+        }
+
+        if config.ui != previous.ui {
+            reload.ui = Some(config.ui.clone());
+            reload.applied("ui");
+        }
+
+        if !reload.applied.is_empty() || !reload.not_applied.is_empty() {
+            reload.status = ReloadStatus::Applied;
+        }
+        reload
+    }
+}
+
+async fn run(
+    app_handle: AppHandle,
+    mut watched: WatchedConfig,
+    mut receiver: mpsc::UnboundedReceiver<PathBuf>,
+) {
+    while let Some(path) = receiver.recv().await {
+        let mut changed = HashSet::from([path]);
+        tokio::time::sleep(DEBOUNCE).await;
+        while let Ok(path) = receiver.try_recv() {
+            changed.insert(path);
+        }
+        changed.retain(|path| watched.files.contains(path));
+        if changed.is_empty() {
+            continue;
+        }
+
+        let reload = watched.reload(&app_handle, &changed).await;
+        match reload.status {
+            ReloadStatus::Refused => warn!(
+                "Reload of {} refused: {}",
+                reload.config_path,
+                reload
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| format!("{} problems", reload.problems.len()))
+            ),
+            _ => info!(
+                "Reloaded {}: applied {:?}, not applied {:?}",
+                reload.config_path,
+                reload.applied,
+                reload
+                    .not_applied
+                    .iter()
+                    .map(|change| &change.section)
+                    .collect::<Vec<_>>()
+            ),
+        }
+        if let Err(e) = app_handle.emit(CONFIG_RELOAD_EVENT, &reload) {
+            warn!("Failed to emit configuration reload: {}", e);
+        }
+    }
+}
@@ -29,6 +29,9 @@ pub enum SettingsError {
 
     #[error("Sqlite error error: {0}")]
     Sqlite(#[from] sqlx::Error),
+
+    #[error("File watch error: {0}")]
+    Watch(#[from] notify::Error),
 }
 
 // Implement Serialize for SettingsError for Tauri command compatibility
@@ -2,12 +2,12 @@ use errors::SettingResult;
 use serde_json::Value;
 use sqlx::{Pool, Sqlite};
 
-mod config;
 mod proxy;
 mod url;
 mod zmq;
 
 pub mod commands;
+pub mod config;
 pub mod entities;
 pub mod errors;
 pub mod state;
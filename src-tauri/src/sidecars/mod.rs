@@ -1,5 +1,7 @@
 pub mod commands;
 
+use crate::state::AppState;
+
 use log::{debug, error, info, warn};
 use std::sync::{Arc, Mutex};
 use tauri::{Emitter, Manager};
@@ -29,6 +31,7 @@ pub fn spawn_and_monitor_sidecar(app_handle: tauri::AppHandle) -> Result<(), Str
     } else {
         return Err("Failed to access app state".to_string());
     }
+    forget_networks(&app_handle);
 
     // Spawn an async task to handle sidecar communication
     tauri::async_runtime::spawn(async move {
@@ -58,6 +61,7 @@ pub fn spawn_and_monitor_sidecar(app_handle: tauri::AppHandle) -> Result<(), Str
                 }
                 CommandEvent::Terminated(payload) => {
                     info!("Sidecar terminated: {:?}", payload);
+                    forget_networks(&app_handle);
                     app_handle
                         .emit("sidecar-terminated", format!("{:?}", payload))
                         .expect("Failed to emit sidecar terminated event");
@@ -70,6 +74,16 @@ pub fn spawn_and_monitor_sidecar(app_handle: tauri::AppHandle) -> Result<(), Str
     Ok(())
 }
 
+// Versions of the sidecar networks key the query cache, so they are listed again
+// from the new sidecar rather than trusted across a restart
+fn forget_networks(app_handle: &tauri::AppHandle) {
+    if let Some(state) = app_handle.try_state::<AppState>() {
+        if let Ok(mut state) = state.write() {
+            state.powsybl.forget_networks();
+        }
+    }
+}
+
 // Helper function ensure the sidecar is killed when the app is close
 pub fn despawn_sidecar(app_handle: &tauri::AppHandle) {
     if let Some(child_process) = app_handle.try_state::<Arc<Mutex<Option<CommandChild>>>>() {
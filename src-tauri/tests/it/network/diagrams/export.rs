@@ -0,0 +1,70 @@
+//! Rendering of diagrams to PNG and PDF, which must work without a display.
+
+use argus_lib::{render_svg, ExportFormat};
+use resvg::tiny_skia::Pixmap;
+
+const SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20" viewBox="0 0 40 20">
+<style><![CDATA[
+.sld-vl300to500 {--sld-vl-color: #ff0000}
+.sld-wire {stroke: var(--sld-vl-color, #000000); stroke-width: 2}
+]]></style>
+<g class="sld-vl300to500"><line class="sld-wire" x1="0" y1="10" x2="40" y2="10"/></g>
+<text x="2" y="8" font-family="sans-serif" font-size="6">VL1</text>
+</svg>"#;
+
+fn png_size(png: &[u8]) -> (u32, u32) {
+    let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+    (width, height)
+}
+
+#[test]
+fn renders_png_at_scale() {
+    let png = render_svg(SVG, ExportFormat::Png, 2.0).unwrap();
+
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    assert_eq!(png_size(&png), (80, 40));
+}
+
+#[test]
+fn clamps_png_scale() {
+    let png = render_svg(SVG, ExportFormat::Png, 100.0).unwrap();
+
+    assert_eq!(png_size(&png), (320, 160));
+}
+
+#[test]
+fn resolves_css_variables() {
+    let png = render_svg(SVG, ExportFormat::Png, 2.0).unwrap();
+    let pixmap = Pixmap::decode_png(&png).unwrap();
+
+    // The wire takes the colour of its voltage class, not the black fallback
+    let pixel = pixmap.pixel(40, 20).unwrap();
+    assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (255, 0, 0));
+}
+
+#[test]
+fn rejects_oversized_png() {
+    let svg = SVG.replacen(
+        r#"width="40" height="20""#,
+        r#"width="20000" height="20000""#,
+        1,
+    );
+
+    let error = render_svg(&svg, ExportFormat::Png, 1.0).unwrap_err();
+
+    assert!(error.to_string().starts_with("Export error: "), "{}", error);
+    assert!(render_svg(&svg, ExportFormat::Pdf, 1.0).is_ok());
+}
+
+#[test]
+fn renders_pdf() {
+    let pdf = render_svg(SVG, ExportFormat::Pdf, 1.0).unwrap();
+
+    assert!(pdf.starts_with(b"%PDF-"));
+}
+
+#[test]
+fn rejects_invalid_svg() {
+    assert!(render_svg("<svg", ExportFormat::Png, 1.0).is_err());
+}
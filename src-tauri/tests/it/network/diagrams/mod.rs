@@ -1 +1 @@
-
+mod export;
@@ -2,28 +2,33 @@ import { SldMetadata } from '../types/sld-metatada.type';
 import { SldSubscriptionResponse } from '../types/sld-subscription.type';
 import { invoke, Channel } from '@tauri-apps/api/core';
 import { handleApiError } from '@/lib/api-utils';
-import { TelemetryCurves } from '../types/telemetry-curves.type';
+import {
+  FeederInfoType,
+  FeederValues,
+} from '../types/telemetry-curves.type';
 
-const channels = new Map<string, Channel<TelemetryCurves>>();
+const channels = new Map<string, Channel<FeederValues>>();
 
 export const subscribeSingleLineDiagram = async (
   id: string,
   sld_metadata: SldMetadata,
-  handler: (tc: TelemetryCurves) => void,
+  handler: (values: FeederValues) => void,
+  feeder_types?: FeederInfoType[],
+  bus_infos?: boolean,
 ): Promise<SldSubscriptionResponse> => {
   try {
     if (channels.has(id)) {
       return { status: 'connected' };
     }
 
-    const on_event = new Channel<TelemetryCurves>();
+    const on_event = new Channel<FeederValues>();
     on_event.onmessage = handler;
 
     channels.set(id, on_event);
 
     const response = await invoke<SldSubscriptionResponse>(
       'subscribe_single_line_diagram',
-      { sld_metadata, on_event },
+      { sld_metadata, feeder_types, bus_infos, on_event },
     );
 
     return response;
@@ -1,6 +1,9 @@
 import { Channel, invoke } from '@tauri-apps/api/core';
 import { Effect } from 'effect';
-import { TelemetryCurves } from '../types/telemetry-curves.type';
+import {
+  FeederInfoType,
+  FeederValues,
+} from '../types/telemetry-curves.type';
 import { SldMetadata } from '../types/sld-metatada.type';
 
 // ------------------------------
@@ -24,12 +27,14 @@ class SubscriptionSLDError extends Error {
 // External API
 // ------------------------------
 
-const channels = new Map<string, Channel<TelemetryCurves>>();
+const channels = new Map<string, Channel<FeederValues>>();
 
 export const subscribeSLD = (
   id: string,
   sld_metadata: SldMetadata,
-  handler: (ti: TelemetryCurves) => void,
+  handler: (values: FeederValues) => void,
+  feeder_types?: FeederInfoType[],
+  bus_infos?: boolean,
 ) =>
   Effect.gen(function* () {
     // Réutilisation d'un canal existant
@@ -38,7 +43,7 @@ export const subscribeSLD = (
     }
 
     // Création d'un nouveau canal
-    const on_event = new Channel<TelemetryCurves>();
+    const on_event = new Channel<FeederValues>();
     on_event.onmessage = handler;
     channels.set(id, on_event);
 
@@ -47,6 +52,8 @@ export const subscribeSLD = (
       try: () =>
         invoke<SldSubscriptionResponse>('subscribe_single_line_diagram', {
           sld_metadata,
+          feeder_types,
+          bus_infos,
           on_event,
         }),
       catch: (error) => new SubscriptionSLDError(id, error),
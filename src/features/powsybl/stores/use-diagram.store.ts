@@ -3,7 +3,7 @@ import { Effect } from 'effect';
 import { SldMetadata } from '../types/sld-metatada.type';
 import { getSingleLineDiagramWithMetadata } from '../api/get-single-line-diagram';
 import { SldSubscriptionStatus } from '../types/sld-subscription.type';
-import { TelemetryCurves } from '../types/telemetry-curves.type';
+import { FeederValues } from '../types/telemetry-curves.type';
 import {
   connectBroker,
   disconnectBroker,
@@ -26,7 +26,7 @@ export interface DiagramData {
 export interface DiagramStore extends DiagramData {
   loadDiagram: (lineId: string) => Promise<void>;
   resetDiagram: () => void;
-  subscribeDiagram: (handler: (tc: TelemetryCurves) => void) => void;
+  subscribeDiagram: (handler: (values: FeederValues) => void) => void;
   unsubscribeDiagram: () => void;
   connectBroker: (
     id: string,
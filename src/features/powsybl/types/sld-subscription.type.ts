@@ -3,3 +3,11 @@ export type SldSubscriptionStatus = 'connected' | 'disconnected';
 export interface SldSubscriptionResponse {
   readonly status: SldSubscriptionStatus;
 }
+
+export const SUBSCRIPTION_STATUS_EVENT = 'subscription-status';
+
+export interface SubscriptionStatusEvent {
+  readonly diagram_id: string;
+  readonly status: 'connected' | 'stale' | 'failed';
+  readonly message?: string;
+}
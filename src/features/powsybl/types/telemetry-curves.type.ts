@@ -6,4 +6,25 @@ export interface TelemetryData {
   values: Record<string, number>;
   time: number;
 }
-  
\ No newline at end of file
+  
+export type MeasurementKind =
+  | 'active_power'
+  | 'reactive_power'
+  | 'current'
+  | 'bus_voltage'
+  | 'bus_angle';
+
+export type FeederInfoType = 'ARROW_ACTIVE' | 'ARROW_REACTIVE' | 'ARROW_CURRENT';
+
+export interface FeederValue {
+  id: string;
+  kind: MeasurementKind;
+  value: number;
+  unit?: string;
+  side?: string;
+}
+
+export interface FeederValues {
+  values: FeederValue[];
+  time: number;
+}
@@ -5,7 +5,14 @@ export const setConfig = (config_path: string) =>
   Effect.gen(function* () {
     return yield* Effect.tryPromise({
       try: () =>
-        invoke<{ status: string }>('load_config_file', {
+        invoke<{
+          status: string;
+          ui: {
+            theme_mode: 'light' | 'dark' | 'system';
+            theme_color: string;
+            substation: string | null;
+          };
+        }>('load_config_file', {
           config_path,
         }),
       catch: (error) => console.error(error),